@@ -0,0 +1,243 @@
+"use strict";
+
+/**
+ * Renders the operations of the OpenAPI document and lets them be tried out.
+ * Everything is built with DOM APIs and text content, so the document cannot inject markup.
+ */
+
+const OPENAPI_DOCUMENT_URL = "/api/v1/openapi.json";
+const HTTP_METHODS = ["get", "post", "put", "patch", "delete"];
+const MAX_EXAMPLE_DEPTH = 8;
+
+function element(tagName, properties = {}, children = []) {
+    const node = document.createElement(tagName);
+    Object.assign(node, properties);
+    for (const child of children) {
+        node.append(child);
+    }
+    return node;
+}
+
+function resolveReference(document, schema) {
+    if (schema && schema.$ref) {
+        const name = schema.$ref.replace("#/components/schemas/", "");
+        return document.components?.schemas?.[name] ?? {};
+    }
+    return schema ?? {};
+}
+
+/** Builds an example value from a schema, used to prefill request bodies and show response shapes. */
+function getExample(document, schema, depth = 0) {
+    schema = resolveReference(document, schema);
+    if (depth > MAX_EXAMPLE_DEPTH) {
+        return null;
+    }
+    if (schema.example !== undefined) {
+        return schema.example;
+    }
+    if (schema.examples?.length) {
+        return schema.examples[0];
+    }
+    if (schema.default !== undefined) {
+        return schema.default;
+    }
+    if (schema.enum?.length) {
+        return schema.enum[0];
+    }
+    if (schema.allOf) {
+        return Object.assign({}, ...schema.allOf.map((part) => getExample(document, part, depth + 1)));
+    }
+    const variants = schema.oneOf ?? schema.anyOf;
+    if (variants) {
+        const variant = variants.find((part) => part.type !== "null") ?? variants[0];
+        return getExample(document, variant, depth + 1);
+    }
+
+    const types = Array.isArray(schema.type) ? schema.type : [schema.type];
+    const type = types.find((candidate) => candidate && candidate !== "null") ?? (schema.properties ? "object" : undefined);
+    switch (type) {
+        case "object": {
+            const example = {};
+            for (const [name, property] of Object.entries(schema.properties ?? {})) {
+                example[name] = getExample(document, property, depth + 1);
+            }
+            return example;
+        }
+        case "array":
+            return [getExample(document, schema.items, depth + 1)];
+        case "integer":
+        case "number":
+            return schema.minimum ?? 0;
+        case "boolean":
+            return false;
+        case "string":
+            return schema.format === "date-time" ? new Date().toISOString() : "";
+        default:
+            return null;
+    }
+}
+
+function renderParameterInputs(parameters) {
+    return parameters.map((parameter) => {
+        const input = element("input", {type: "text", name: parameter.name, placeholder: parameter.description ?? ""});
+        input.dataset.location = parameter.in;
+        const label = element("label", {}, [
+            element("code", {textContent: parameter.name}),
+            ` (${parameter.in}${parameter.required ? ", required" : ""})`,
+            input,
+        ]);
+        return {parameter, input, label};
+    });
+}
+
+function buildUrl(server, path, parameterInputs) {
+    let url = server + path;
+    const query = new URLSearchParams();
+    for (const {parameter, input} of parameterInputs) {
+        if (parameter.in === "path") {
+            url = url.replace(`{${parameter.name}}`, encodeURIComponent(input.value));
+        } else if (parameter.in === "query" && input.value !== "") {
+            query.set(parameter.name, input.value);
+        }
+    }
+    const queryString = query.toString();
+    return queryString ? `${url}?${queryString}` : url;
+}
+
+async function sendRequest(method, url, body, contentType, output) {
+    output.replaceChildren(element("p", {className: "muted", textContent: `${method.toUpperCase()} ${url}`}));
+    try {
+        const options = {method: method.toUpperCase()};
+        if (body !== undefined) {
+            options.body = body;
+            options.headers = {"Content-Type": contentType};
+        }
+        const response = await fetch(url, options);
+        const responseContentType = response.headers.get("content-type") ?? "";
+        let text;
+        if (responseContentType.includes("json")) {
+            text = JSON.stringify(await response.json(), null, 2);
+        } else if (responseContentType.startsWith("text/")) {
+            text = await response.text();
+        } else {
+            text = `${(await response.blob()).size} bytes of ${responseContentType || "unknown content"}`;
+        }
+        output.append(
+            element("p", {textContent: `${response.status} ${response.statusText}`}),
+            element("pre", {textContent: text}),
+        );
+    } catch (error) {
+        output.append(element("p", {className: "error", textContent: `Request failed: ${error}`}));
+    }
+}
+
+function renderOperation(document, server, path, method, operation) {
+    const parameterInputs = renderParameterInputs(operation.parameters ?? []);
+    const content = element("div");
+
+    if (operation.description) {
+        content.append(element("p", {textContent: operation.description}));
+    }
+    for (const {label} of parameterInputs) {
+        content.append(label);
+    }
+
+    const requestContent = operation.requestBody?.content ?? {};
+    const [requestContentType, requestMediaType] = Object.entries(requestContent)[0] ?? [];
+    let readBody = () => undefined;
+    if (requestContentType === "application/json") {
+        const textarea = element("textarea", {
+            value: JSON.stringify(getExample(document, requestMediaType.schema), null, 2),
+            spellcheck: false,
+        });
+        content.append(element("label", {textContent: "Request body (application/json)"}), textarea);
+        readBody = () => textarea.value;
+    } else if (requestContentType) {
+        const fileInput = element("input", {type: "file"});
+        content.append(element("label", {textContent: `Request body (${requestContentType})`}), fileInput);
+        readBody = () => fileInput.files[0];
+    }
+
+    const responses = element("div");
+    for (const [status, response] of Object.entries(operation.responses ?? {})) {
+        const [responseContentType, responseMediaType] = Object.entries(response.content ?? {})[0] ?? [];
+        responses.append(element("p", {}, [
+            element("strong", {textContent: status}),
+            ` ${response.description ?? ""}${responseContentType ? ` (${responseContentType})` : ""}`,
+        ]));
+        if (responseContentType === "application/json") {
+            responses.append(element("pre", {
+                textContent: JSON.stringify(getExample(document, responseMediaType.schema), null, 2),
+            }));
+        }
+    }
+
+    const output = element("div");
+    const button = element("button", {type: "button", textContent: "Send request"});
+    button.addEventListener("click", () => {
+        const url = buildUrl(server, path, parameterInputs);
+        sendRequest(method, url, readBody(), requestContentType, output);
+    });
+
+    content.append(button, output, element("h4", {textContent: "Responses"}), responses);
+
+    return element("details", {className: "operation"}, [
+        element("summary", {}, [
+            element("span", {className: `method ${method}`, textContent: method}),
+            element("span", {className: "path", textContent: path}),
+            element("span", {className: "muted", textContent: operation.summary ?? operation.operationId ?? ""}),
+        ]),
+        content,
+    ]);
+}
+
+function renderDocument(document) {
+    const server = document.servers?.[0]?.url ?? "";
+    window.document.title = document.info.title;
+    window.document.getElementById("title").textContent = `${document.info.title} ${document.info.version}`;
+    window.document.getElementById("description").textContent = document.info.description ?? "";
+
+    const sections = new Map((document.tags ?? []).map((tag) => [tag.name, {tag, operations: []}]));
+    for (const [path, pathItem] of Object.entries(document.paths ?? {})) {
+        for (const method of HTTP_METHODS) {
+            const operation = pathItem[method];
+            if (!operation) {
+                continue;
+            }
+            const tagName = operation.tags?.[0] ?? "other";
+            if (!sections.has(tagName)) {
+                sections.set(tagName, {tag: {name: tagName}, operations: []});
+            }
+            sections.get(tagName).operations.push(renderOperation(document, server, path, method, operation));
+        }
+    }
+
+    const operations = window.document.getElementById("operations");
+    operations.replaceChildren();
+    for (const {tag, operations: tagOperations} of sections.values()) {
+        if (tagOperations.length === 0) {
+            continue;
+        }
+        operations.append(
+            element("h2", {textContent: tag.name}),
+            element("p", {className: "muted", textContent: tag.description ?? ""}),
+            ...tagOperations,
+        );
+    }
+}
+
+async function loadDocument() {
+    try {
+        const response = await fetch(OPENAPI_DOCUMENT_URL);
+        if (!response.ok) {
+            throw new Error(`${response.status} ${response.statusText}`);
+        }
+        renderDocument(await response.json());
+    } catch (error) {
+        document.getElementById("operations").replaceChildren(
+            element("p", {className: "error", textContent: `Failed to load the OpenAPI document: ${error}`}),
+        );
+    }
+}
+
+loadDocument();
@@ -0,0 +1,75 @@
+//! Embeds the exported frontend (`ferrous-frontend/out`, created by `npm run build`) into the server binary.
+//! The directory can be changed using the `FERROUS_BEATS_FRONTEND_DIR` environment variable.
+//! Without an exported frontend the server is built without a UI and only serves the API.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_FRONTEND_DIR: &str = "ferrous-frontend/out";
+const FRONTEND_DIR_ENV_VAR: &str = "FERROUS_BEATS_FRONTEND_DIR";
+const FRONTEND_ASSETS_FILE_NAME: &str = "frontend_assets.rs";
+
+fn main() {
+    println!("cargo:rerun-if-env-changed={}", FRONTEND_DIR_ENV_VAR);
+
+    let manifest_dir = PathBuf::from(
+        std::env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo"),
+    );
+    let frontend_dir = manifest_dir.join(
+        std::env::var_os(FRONTEND_DIR_ENV_VAR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_FRONTEND_DIR)),
+    );
+    println!("cargo:rerun-if-changed={}", frontend_dir.display());
+
+    let mut asset_paths = Vec::new();
+    if frontend_dir.is_dir() {
+        collect_asset_paths(&frontend_dir, &mut asset_paths);
+        asset_paths.sort();
+    } else {
+        println!(
+            "cargo:warning=Frontend export not found at {}, building without the UI. Run `npm run build` in ferrous-frontend to embed it",
+            frontend_dir.display()
+        );
+    }
+
+    let mut frontend_assets = String::from(
+        "/// Files of the exported frontend, keyed by their path relative to the export directory.\npub static FRONTEND_ASSETS: &[(&str, &[u8])] = &[\n",
+    );
+    for asset_path in &asset_paths {
+        let relative_path = asset_path
+            .strip_prefix(&frontend_dir)
+            .expect("Asset is in the frontend directory")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        writeln!(
+            frontend_assets,
+            "    ({:?}, include_bytes!({:?})),",
+            relative_path, asset_path
+        )
+        .expect("Writing to a string cannot fail");
+    }
+    frontend_assets.push_str("];\n");
+
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    std::fs::write(out_dir.join(FRONTEND_ASSETS_FILE_NAME), frontend_assets)
+        .expect("Failed to write embedded frontend assets");
+}
+
+fn collect_asset_paths(dir: &Path, asset_paths: &mut Vec<PathBuf>) {
+    let entries = std::fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {}", dir.display(), err));
+    for entry in entries {
+        let path = entry
+            .unwrap_or_else(|err| panic!("Failed to read {}: {}", dir.display(), err))
+            .path();
+        println!("cargo:rerun-if-changed={}", path.display());
+        if path.is_dir() {
+            collect_asset_paths(&path, asset_paths);
+        } else {
+            asset_paths.push(path);
+        }
+    }
+}
@@ -1,4 +1,7 @@
 /** @type {import('next').NextConfig} */
-const nextConfig = {};
+const nextConfig = {
+    // Exported as static files into out/, which is embedded into the server binary
+    output: 'export',
+};
 
 export default nextConfig;
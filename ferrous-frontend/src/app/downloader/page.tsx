@@ -10,9 +10,24 @@ import {Card, CardBody} from '@nextui-org/card';
 import {Skeleton} from '@nextui-org/skeleton';
 import {useMusicPlayerContext} from "@/contexts/MusicPlayerContext";
 import {Divider} from "@nextui-org/divider";
+import {Checkbox, Select, SelectItem} from "@nextui-org/react";
+import {API_BASE_URL, getApiErrorMessage} from "@/lib/api";
+
+type AudioFormat = 'best-original' | 'mp3' | 'opus' | 'm4a' | 'flac';
+
+const AUDIO_FORMATS: { key: AudioFormat, label: string }[] = [
+    {key: 'best-original', label: 'Best original'},
+    {key: 'mp3', label: 'MP3'},
+    {key: 'opus', label: 'Opus'},
+    {key: 'm4a', label: 'M4A'},
+    {key: 'flac', label: 'FLAC'},
+];
 
 interface DownloadAudioRequest {
     audio_url: string;
+    audio_format: AudioFormat;
+    audio_quality?: string;
+    split_chapters: boolean;
 }
 
 interface CommandExecutionResults {
@@ -22,38 +37,108 @@ interface CommandExecutionResults {
     stderr: string | null;
 }
 
+interface PostProcessingStepResult {
+    library_file: string;
+    step: 'identify' | 'tag' | 'normalize' | 'convert' | 'organize';
+    state: 'completed' | 'skipped' | 'failed';
+    message: string | null;
+}
+
 interface MediaDownloadResponse {
     download_id: string;
     requested_url: string;
     library_dir: string;
+    library_files: string[];
+    already_in_library: boolean;
+    existing_library_entry: { file_name: string } | null;
+    command_execution_results: CommandExecutionResults;
+    post_processing: PostProcessingStepResult[];
+}
+
+type SearchSource = 'youtube' | 'soundcloud';
+
+const SEARCH_SOURCES: { key: SearchSource; label: string }[] = [
+    {key: 'youtube', label: 'YouTube'},
+    {key: 'soundcloud', label: 'SoundCloud'},
+];
+
+interface SearchMediaRequest {
+    query: string;
+    source: SearchSource;
+}
+
+interface SearchCandidate {
+    index: number;
+    id: string | null;
+    title: string | null;
+    uploader: string | null;
+    duration: number | null;
+    thumbnail: string | null;
+    url: string | null;
+    extractor: string | null;
+}
+
+interface MediaSearchResponse {
+    query: string;
+    source: SearchSource;
+    candidates: SearchCandidate[];
     command_execution_results: CommandExecutionResults;
 }
 
+const formatDuration = (duration: number | null) => {
+    if (duration === null) {
+        return '';
+    }
+    const totalSeconds = Math.round(duration);
+    return `${Math.floor(totalSeconds / 60)}:${String(totalSeconds % 60).padStart(2, '0')}`;
+};
+
 export default function DownloaderPage() {
     const [audioUrl, setAudioUrl] = useState('');
+    const [audioFormat, setAudioFormat] = useState<AudioFormat>('best-original');
+    const [audioQuality, setAudioQuality] = useState('');
+    const [splitChapters, setSplitChapters] = useState(false);
     const [isLoading, setIsLoading] = useState(false);
     const [downloadResult, setDownloadResult] = useState<MediaDownloadResponse | null>(null);
+    const [searchQuery, setSearchQuery] = useState('');
+    const [searchSource, setSearchSource] = useState<SearchSource>('youtube');
+    const [isSearching, setIsSearching] = useState(false);
+    const [searchCandidates, setSearchCandidates] = useState<SearchCandidate[]>([]);
     const {handleUpdatePlaylistContents} = useMusicPlayerContext();
 
-    const handleDownload = async () => {
+    const handleDownload = async (url: string) => {
         setIsLoading(true);
         setDownloadResult(null);
 
         try {
-            const response = await fetch('http://localhost:13337/download/audio', {
+            const response = await fetch(`${API_BASE_URL}/download/audio`, {
                 method: 'POST',
                 headers: {
                     'Content-Type': 'application/json',
                 },
-                body: JSON.stringify({audio_url: audioUrl} as DownloadAudioRequest),
+                body: JSON.stringify({
+                    audio_url: url,
+                    audio_format: audioFormat,
+                    audio_quality: audioQuality || undefined,
+                    split_chapters: splitChapters,
+                } as DownloadAudioRequest),
             });
 
             if (!response.ok) {
-                throw new Error('Network response was not ok');
+                throw new Error(await getApiErrorMessage(response));
             }
 
             const result: MediaDownloadResponse = await response.json();
-            toast.success(`${audioUrl} was downloaded successfully`, {
+            if (result.already_in_library) {
+                toast.info(`${url} is already in the library as ${result.existing_library_entry?.file_name}`, {
+                    duration: 7500,
+                    closeButton: true,
+                    position: "bottom-center"
+                });
+                setDownloadResult(result);
+                return;
+            }
+            toast.success(`${url} was downloaded successfully`, {
                 duration: 7500,
                 closeButton: true,
                 position: "bottom-center"
@@ -61,7 +146,7 @@ export default function DownloaderPage() {
             setDownloadResult(result);
 
             // Refresh the playlist
-            const libraryResponse = await fetch('http://localhost:13337/library/list');
+            const libraryResponse = await fetch(`${API_BASE_URL}/library/list`);
             const libraryData = await libraryResponse.json();
             handleUpdatePlaylistContents(libraryData.files);
             toast.success(`Playlist was refreshed with newly downloaded track`, {
@@ -71,7 +156,7 @@ export default function DownloaderPage() {
             });
         } catch (error) {
             console.error('Error downloading audio:', error);
-            toast.error(`Failed to download ${audioUrl}: ${error instanceof Error ? error.message : String(error)}`, {
+            toast.error(`Failed to download ${url}: ${error instanceof Error ? error.message : String(error)}`, {
                 duration: Infinity,
                 important: true,
                 closeButton: true,
@@ -81,6 +166,41 @@ export default function DownloaderPage() {
             setIsLoading(false);
         }
     };
+    const handleSearch = async () => {
+        setIsSearching(true);
+        setSearchCandidates([]);
+
+        try {
+            const response = await fetch(`${API_BASE_URL}/download/search`, {
+                method: 'POST',
+                headers: {
+                    'Content-Type': 'application/json',
+                },
+                body: JSON.stringify({
+                    query: searchQuery,
+                    source: searchSource,
+                } as SearchMediaRequest),
+            });
+
+            if (!response.ok) {
+                throw new Error(await getApiErrorMessage(response));
+            }
+
+            const result: MediaSearchResponse = await response.json();
+            setSearchCandidates(result.candidates);
+        } catch (error) {
+            console.error('Error searching media:', error);
+            toast.error(`Failed to search for ${searchQuery}: ${error instanceof Error ? error.message : String(error)}`, {
+                duration: Infinity,
+                important: true,
+                closeButton: true,
+                position: "bottom-center"
+            });
+        } finally {
+            setIsSearching(false);
+        }
+    };
+
     return (
         <>
             <FerrousNavbar/>
@@ -92,11 +212,85 @@ export default function DownloaderPage() {
                         value={audioUrl}
                         onChange={(e) => setAudioUrl(e.target.value)}
                     />
-                    <Button color="primary" onClick={handleDownload} disabled={isLoading}>
+                    <Select
+                        aria-label="Audio format"
+                        className="max-w-44"
+                        selectedKeys={[audioFormat]}
+                        onChange={(e) => setAudioFormat((e.target.value || 'best-original') as AudioFormat)}
+                    >
+                        {AUDIO_FORMATS.map((format) => (
+                            <SelectItem key={format.key}>{format.label}</SelectItem>
+                        ))}
+                    </Select>
+                    <Input
+                        aria-label="Audio quality"
+                        className="max-w-44"
+                        placeholder="Quality (0-10 or 192K)"
+                        value={audioQuality}
+                        onChange={(e) => setAudioQuality(e.target.value)}
+                    />
+                    <Checkbox
+                        className="min-w-fit"
+                        isSelected={splitChapters}
+                        onValueChange={setSplitChapters}
+                    >
+                        Split chapters
+                    </Checkbox>
+                    <Button color="primary" onClick={() => handleDownload(audioUrl)} disabled={isLoading}>
                         Download
                     </Button>
                 </div>
 
+                <div className="flex gap-2 mb-4">
+                    <Input
+                        placeholder="Search by artist and title"
+                        value={searchQuery}
+                        onChange={(e) => setSearchQuery(e.target.value)}
+                    />
+                    <Select
+                        aria-label="Search source"
+                        className="max-w-44"
+                        selectedKeys={[searchSource]}
+                        onChange={(e) => setSearchSource((e.target.value || 'youtube') as SearchSource)}
+                    >
+                        {SEARCH_SOURCES.map((source) => (
+                            <SelectItem key={source.key}>{source.label}</SelectItem>
+                        ))}
+                    </Select>
+                    <Button color="secondary" onClick={handleSearch} disabled={isSearching || !searchQuery.trim()}>
+                        Search
+                    </Button>
+                </div>
+
+                {searchCandidates.length > 0 && (
+                    <Card className="mb-4">
+                        <CardBody className="gap-2">
+                            {searchCandidates.map((candidate) => (
+                                <div key={candidate.index} className="flex items-center gap-4">
+                                    {candidate.thumbnail && (
+                                        // eslint-disable-next-line @next/next/no-img-element
+                                        <img src={candidate.thumbnail} alt="" className="h-12 w-20 rounded object-cover"/>
+                                    )}
+                                    <div className="flex-grow">
+                                        <p>{candidate.title ?? candidate.url}</p>
+                                        <p className="text-small text-default-500">
+                                            {[candidate.uploader, formatDuration(candidate.duration)].filter(Boolean).join(' · ')}
+                                        </p>
+                                    </div>
+                                    <Button
+                                        size="sm"
+                                        color="primary"
+                                        onClick={() => candidate.url && handleDownload(candidate.url)}
+                                        disabled={isLoading || !candidate.url}
+                                    >
+                                        Download
+                                    </Button>
+                                </div>
+                            ))}
+                        </CardBody>
+                    </Card>
+                )}
+
                 {isLoading && (
                     <Card className="mb-4">
                         <CardBody>
@@ -126,6 +320,17 @@ export default function DownloaderPage() {
                             {downloadResult.command_execution_results.stderr && (
                                 <p>Standard Error: {downloadResult.command_execution_results.stderr}</p>
                             )}
+                            {downloadResult.post_processing.length > 0 && (
+                                <>
+                                    <h5 className="mt-2">Post-processing:</h5>
+                                    {downloadResult.post_processing.map((stepResult, index) => (
+                                        <p key={index}>
+                                            {stepResult.library_file}: {stepResult.step} {stepResult.state}
+                                            {stepResult.message && ` - ${stepResult.message}`}
+                                        </p>
+                                    ))}
+                                </>
+                            )}
                         </CardBody>
                     </Card>
                 )}
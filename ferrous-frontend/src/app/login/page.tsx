@@ -0,0 +1,84 @@
+"use client";
+
+import React, {useState} from 'react';
+import {useRouter} from "next/navigation";
+import FerrousNavbar from "@/components/FerrousNavbar";
+import {toast} from "sonner";
+import {Button} from '@nextui-org/button';
+import {Input} from '@nextui-org/input';
+import {Card, CardBody} from '@nextui-org/card';
+import {API_BASE_URL, getApiErrorMessage} from "@/lib/api";
+
+interface LoginRequest {
+    username: string;
+    password: string;
+}
+
+export default function LoginPage() {
+    const router = useRouter();
+    const [username, setUsername] = useState('');
+    const [password, setPassword] = useState('');
+    const [isLoading, setIsLoading] = useState(false);
+
+    const handleLogin = async (event: React.FormEvent) => {
+        event.preventDefault();
+        setIsLoading(true);
+
+        try {
+            const response = await fetch(`${API_BASE_URL}/auth/login`, {
+                method: 'POST',
+                headers: {
+                    'Content-Type': 'application/json',
+                },
+                body: JSON.stringify({username, password} as LoginRequest),
+            });
+
+            if (!response.ok) {
+                throw new Error(await getApiErrorMessage(response));
+            }
+
+            router.push('/library');
+        } catch (error) {
+            console.error('Error logging in:', error);
+            toast.error(`Failed to log in: ${error instanceof Error ? error.message : String(error)}`, {
+                duration: 7500,
+                closeButton: true,
+                position: "bottom-center"
+            });
+        } finally {
+            setPassword('');
+            setIsLoading(false);
+        }
+    };
+
+    return (
+        <>
+            <FerrousNavbar/>
+            <div className="container mx-auto px-4 py-8 max-w-md">
+                <h1 className="text-2xl font-bold mb-4">Log In</h1>
+                <Card>
+                    <CardBody>
+                        <form className="flex flex-col gap-4" onSubmit={handleLogin}>
+                            <Input
+                                label="Username"
+                                autoComplete="username"
+                                value={username}
+                                onChange={(e) => setUsername(e.target.value)}
+                            />
+                            <Input
+                                label="Password"
+                                type="password"
+                                autoComplete="current-password"
+                                value={password}
+                                onChange={(e) => setPassword(e.target.value)}
+                            />
+                            <Button type="submit" color="primary" disabled={isLoading || !username || !password}>
+                                Log In
+                            </Button>
+                        </form>
+                    </CardBody>
+                </Card>
+            </div>
+        </>
+    );
+}
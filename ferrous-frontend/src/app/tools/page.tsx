@@ -9,6 +9,7 @@ import {Skeleton} from '@nextui-org/skeleton';
 import {Button} from '@nextui-org/button';
 import {Divider} from "@nextui-org/divider";
 import {Select, SelectItem} from '@nextui-org/select';
+import {Progress} from '@nextui-org/progress';
 
 // TODO: check this
 function escapePath(path: string): string {
@@ -17,7 +18,7 @@ function escapePath(path: string): string {
 }
 
 export default function ToolsPage() {
-    const {toolStatus, downloadAndRecheckTool, updateYtDlp} = useContext(ToolsContext)!;
+    const {toolStatus, downloadProgress, downloadAndRecheckTool, updateYtDlp} = useContext(ToolsContext)!;
     const [ytDlpUpdateChannel, setYtDlpUpdateChannel] = useState<string>("stable");
     // States to disable buttons
     const [isDownloading, setIsDownloading] = useState<Record<string, boolean>>({});
@@ -52,6 +53,17 @@ export default function ToolsPage() {
                                 <p>Version: {status.version || 'Unknown version'}</p>
                                 <p>Path: {status.path ? escapePath(status.path) : 'Unknown path'}</p>
                                 {status.error && <p className="text-red-500">Error: {status.error}</p>}
+                                {isDownloading[tool] && downloadProgress[tool]?.state === 'in_progress' && (
+                                    <Progress
+                                        aria-label={`Downloading ${tool}`}
+                                        size="sm"
+                                        className="mt-2"
+                                        isIndeterminate={!downloadProgress[tool].total_bytes}
+                                        value={downloadProgress[tool].downloaded_bytes}
+                                        maxValue={downloadProgress[tool].total_bytes ?? undefined}
+                                        showValueLabel={!!downloadProgress[tool].total_bytes}
+                                    />
+                                )}
                                 {!status.isOk && (
                                     <Button
                                         color="primary"
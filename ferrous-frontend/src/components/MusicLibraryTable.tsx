@@ -7,6 +7,7 @@ import {Divider} from "@nextui-org/divider";
 import {Button} from "@nextui-org/button";
 import {useMusicPlayerContext} from "@/contexts/MusicPlayerContext";
 import {Skeleton} from "@nextui-org/skeleton";
+import {API_BASE_URL} from "@/lib/api";
 
 export default function MusicLibraryTable() {
     const [libraryPath, setLibraryPath] = useState<string>("");
@@ -20,7 +21,7 @@ export default function MusicLibraryTable() {
 
     useEffect(() => {
         setIsLoading(true);
-        fetch('http://localhost:13337/library/list')
+        fetch(`${API_BASE_URL}/library/list`)
             .then(response => response.json())
             .then(data => {
                 setLibraryPath(data.library_dir);
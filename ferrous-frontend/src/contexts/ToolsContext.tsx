@@ -2,9 +2,11 @@
 
 import {createContext, ReactNode, useEffect, useState} from "react";
 import {toast} from "sonner";
+import {API_BASE_URL, getApiErrorMessage} from "@/lib/api";
 
 export const ToolsContext = createContext<{
     toolStatus: Record<string, ToolStatus>;
+    downloadProgress: Record<string, DownloadProgress>;
     downloadAndRecheckTool: (tool: string) => Promise<void>;
     updateYtDlp: (updateChannel: string) => Promise<void>;
 } | undefined>(undefined);
@@ -30,6 +32,20 @@ interface ToolStatusResponse {
     command_execution_results: CommandExecutionResults
 }
 
+interface DownloadProgress {
+    download_url: string;
+    state: 'in_progress' | 'completed' | 'failed';
+    downloaded_bytes: number;
+    total_bytes: number | null;
+    resumed_from_bytes: number;
+    error: string | null;
+}
+
+interface ToolDownloadProgressResponse {
+    tool: string;
+    progress: DownloadProgress | null;
+}
+
 interface ToolDownloadResponse {
     download_url: string;
     tools_dir_path: string;
@@ -41,15 +57,31 @@ export function ToolsProvider({children}: { children: ReactNode }) {
         'chromaprint': {isOk: false, isLoading: true, error: null},
         'yt-dlp': {isOk: false, isLoading: true, error: null},
     });
+    const [downloadProgress, setDownloadProgress] = useState<Record<string, DownloadProgress>>({});
+
+    const fetchDownloadProgress = async (tool: string) => {
+        try {
+            const response = await fetch(`${API_BASE_URL}/tools/${tool}/download/progress`);
+            if (!response.ok) {
+                return;
+            }
+            const json: ToolDownloadProgressResponse = await response.json();
+            if (json.progress) {
+                setDownloadProgress(prevProgress => ({...prevProgress, [tool]: json.progress!}));
+            }
+        } catch (error) {
+            console.debug(`Failed to fetch download progress for ${tool}: ${error}`);
+        }
+    };
 
     const fetchToolStatus = async (tool: string, endpoint: string) => {
         console.debug(`Fetching status for ${tool} using endpoint ${endpoint}`);
         try {
             const response = await fetch(endpoint);
             if (!response.ok) {
-                const errorBody = await response.text();
-                console.error(`Error fetching ${tool}, status: ${response.status}, error: ${errorBody}`);
-                throw new Error(`Error fetching ${tool}, status: ${response.status}, error: ${errorBody}`);
+                const errorMessage = await getApiErrorMessage(response);
+                console.error(`Error fetching ${tool}, status: ${response.status}, error: ${errorMessage}`);
+                throw new Error(`Error fetching ${tool}, status: ${response.status}, error: ${errorMessage}`);
             }
             const json: ToolStatusResponse = await response.json();
             return {
@@ -73,12 +105,13 @@ export function ToolsProvider({children}: { children: ReactNode }) {
     };
 
     const downloadAndRecheckTool = async (tool: string) => {
+        const progressInterval = setInterval(() => fetchDownloadProgress(tool), 500);
         try {
-            const response = await fetch(`http://localhost:13337/tools/${tool}/download`, {
+            const response = await fetch(`${API_BASE_URL}/tools/${tool}/download`, {
                 method: 'POST',
             });
             if (!response.ok) {
-                throw new Error(`Failed to download ${tool}: ${response.statusText}`);
+                throw new Error(`Failed to download ${tool}: ${await getApiErrorMessage(response)}`);
             }
             const downloadResponse: ToolDownloadResponse = await response.json();
             console.log(`${tool} downloaded successfully to ${downloadResponse.tools_dir_path}`);
@@ -89,7 +122,7 @@ export function ToolsProvider({children}: { children: ReactNode }) {
             });
 
             // Recheck the tool status
-            const newStatus = await fetchToolStatus(tool, `http://localhost:13337/tools/${tool}/status`);
+            const newStatus = await fetchToolStatus(tool, `${API_BASE_URL}/tools/${tool}/status`);
             setToolStatus(prevStatus => ({
                 ...prevStatus,
                 [tool]: {
@@ -108,12 +141,15 @@ export function ToolsProvider({children}: { children: ReactNode }) {
                 closeButton: true,
                 position: "bottom-center"
             });
+        } finally {
+            clearInterval(progressInterval);
+            await fetchDownloadProgress(tool);
         }
     };
 
     const updateYtDlp = async (updateChannel: string) => {
         try {
-            const response = await fetch('http://localhost:13337/tools/yt-dlp/update', {
+            const response = await fetch(`${API_BASE_URL}/tools/yt-dlp/update`, {
                 method: 'POST',
                 headers: {
                     'Content-Type': 'application/json',
@@ -122,14 +158,14 @@ export function ToolsProvider({children}: { children: ReactNode }) {
             });
 
             if (!response.ok) {
-                throw new Error(`HTTP error! status: ${response.status}`);
+                throw new Error(await getApiErrorMessage(response));
             }
 
             const result = await response.json();
             console.log('Update result:', result);
 
             // Recheck the tool status
-            const newStatus = await fetchToolStatus('yt-dlp', `http://localhost:13337/tools/yt-dlp/status`);
+            const newStatus = await fetchToolStatus('yt-dlp', `${API_BASE_URL}/tools/yt-dlp/status`);
             setToolStatus(prevStatus => ({
                 ...prevStatus,
                 ['yt-dlp']: {
@@ -160,9 +196,9 @@ export function ToolsProvider({children}: { children: ReactNode }) {
     useEffect(() => {
         const fetchAllToolStatuses = async () => {
             const toolsWithEndpoints = [
-                {tool: 'ffmpeg', endpoint: 'http://localhost:13337/tools/ffmpeg/status'},
-                {tool: 'chromaprint', endpoint: 'http://localhost:13337/tools/chromaprint/status'},
-                {tool: 'yt-dlp', endpoint: 'http://localhost:13337/tools/yt-dlp/status'},
+                {tool: 'ffmpeg', endpoint: `${API_BASE_URL}/tools/ffmpeg/status`},
+                {tool: 'chromaprint', endpoint: `${API_BASE_URL}/tools/chromaprint/status`},
+                {tool: 'yt-dlp', endpoint: `${API_BASE_URL}/tools/yt-dlp/status`},
             ]
 
             const results = await Promise.all(
@@ -205,7 +241,7 @@ export function ToolsProvider({children}: { children: ReactNode }) {
     }, []);
 
     return (
-        <ToolsContext.Provider value={{toolStatus, downloadAndRecheckTool, updateYtDlp}}>
+        <ToolsContext.Provider value={{toolStatus, downloadProgress, downloadAndRecheckTool, updateYtDlp}}>
             {children}
         </ToolsContext.Provider>
     );
@@ -1,5 +1,6 @@
 import {useCallback, useEffect, useState} from "react";
 import {Howl} from "howler";
+import {API_BASE_URL} from "@/lib/api";
 
 export const useMusicPlayer = () => {
     const [playlist, setPlaylist] = useState<string[]>([]);
@@ -46,7 +47,7 @@ export const useMusicPlayer = () => {
         }
 
         const newHowl = new Howl({
-            src: [`http://localhost:13337/library/play/${encodeURIComponent(fileName)}`],
+            src: [`${API_BASE_URL}/library/play/${encodeURIComponent(fileName)}`],
             // html5: true,
             html5: false, // TODO: Enable HTML5 audio when supported, might now work currently with bass / treble filters
             volume: currentVolume,
@@ -0,0 +1,42 @@
+/**
+ * Base URL of the Ferrous Beats API. The UI is served by the API server, so the API is on the same origin by default.
+ * Set NEXT_PUBLIC_API_URL when using the development server, for example to http://localhost:13337/api/v1.
+ */
+export const API_BASE_URL = process.env.NEXT_PUBLIC_API_URL ?? '/api/v1';
+
+/**
+ * Body of API error responses, see RFC 9457. `code` is stable and can be used to handle specific errors,
+ * for example `tool_not_installed` when a tool has to be installed first.
+ */
+export interface ProblemDetails {
+    title: string;
+    status: number;
+    code: string;
+    detail: string;
+    request_id?: string;
+}
+
+/**
+ * Returns a message describing a failed API response, including the request ID so the error can be found in the server logs.
+ */
+export async function getApiErrorMessage(response: Response): Promise<string> {
+    const contentType = response.headers.get('content-type') ?? '';
+    if (contentType.includes('application/problem+json')) {
+        const problem: ProblemDetails = await response.json();
+        redirectToLoginIfRequired(problem);
+        return problem.request_id ? `${problem.detail} (request ID: ${problem.request_id})` : problem.detail;
+    }
+
+    const body = await response.text();
+    return body || `${response.status} ${response.statusText}`;
+}
+
+/**
+ * Opens the login page when authentication is enabled on the server and the session is missing or expired.
+ */
+function redirectToLoginIfRequired(problem: ProblemDetails) {
+    if (problem.code === 'authentication_required' && typeof window !== 'undefined'
+        && window.location.pathname !== '/login') {
+        window.location.assign('/login');
+    }
+}
@@ -1,10 +1,21 @@
+use crate::handlers::shared::model::auth::TokenScope;
+use crate::handlers::shared::model::media::{AudioFormat, ProxyUrl, RateLimit, SleepInterval};
+use crate::handlers::shared::model::naming::{FileNameTemplate, DEFAULT_FILE_NAME_TEMPLATE};
+use crate::handlers::shared::model::postprocessing::PostProcessingStep;
+use crate::handlers::shared::model::tools::Tool;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use tracing::Level;
+
+/// Range of integrated loudness supported by ffmpeg's loudnorm filter.
+pub const MIN_LOUDNESS_TARGET: f64 = -70.0;
+pub const MAX_LOUDNESS_TARGET: f64 = -5.0;
 
 #[derive(Debug, Parser)]
 #[command(version, about)]
 pub struct Cli {
-    /// Enable verbose logging
-    #[arg(short, long)]
+    /// Enable verbose logging. Takes precedence over the log level setting
+    #[arg(short, long, env = "FERROUS_BEATS_VERBOSE")]
     pub verbose: bool,
 
     #[command(subcommand)]
@@ -15,29 +26,112 @@ pub struct Cli {
 pub enum Commands {
     /// Main application command used to run the server and serve the frontend
     Run(RunCommand),
+    /// Install a tool from a local archive, binary or directory without network access
+    ImportTool(ImportToolCommand),
+    /// Check tools, directories and network access using the same settings as the run command. Exits with an error if any check fails
+    Doctor(RunCommand),
+    /// Inspect the configuration
+    Config(ConfigCommand),
+    /// Manage the users and API tokens used when authentication is enabled
+    Admin(AdminCommand),
+}
+
+#[derive(Debug, Parser)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    pub command: ConfigSubcommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigSubcommands {
+    /// Print the effective configuration merged from defaults, the config file, environment variables and flags, in the config file format
+    Print(RunCommand),
 }
 
 #[derive(Debug, Parser)]
+pub struct AdminCommand {
+    /// Directory for application data, where users and API tokens are stored
+    #[arg(
+        long = "data-dir",
+        env = "FERROUS_BEATS_DATA_DIR",
+        default_value = "data",
+        global = true
+    )]
+    pub data_dir: String,
+
+    #[command(subcommand)]
+    pub command: AdminSubcommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AdminSubcommands {
+    /// Create a user who can log in to the UI. The password is read from standard input
+    CreateUser { username: String },
+    /// Delete a user. Sessions of the user stop working immediately
+    DeleteUser { username: String },
+    /// List the users
+    ListUsers,
+    /// Create an API token for scripts. The token is printed once and cannot be shown again
+    CreateToken {
+        /// Name describing what the token is used for
+        name: String,
+        /// What the token can be used for. Can be given multiple times
+        #[arg(long = "scope", value_enum, required = true)]
+        scopes: Vec<TokenScope>,
+    },
+    /// List the API tokens
+    ListTokens,
+    /// Revoke an API token, so it can no longer be used
+    RevokeToken {
+        /// ID of the token, as shown by list-tokens
+        id: String,
+    },
+}
+
+/// Settings of the run command. Settings that are not set using a flag or an environment variable are read from the config file.
+#[derive(Debug, Clone, Parser)]
 pub struct RunCommand {
+    /// TOML config file. Defaults to ferrous-beats/config.toml in the XDG config directories if it exists
+    #[arg(short = 'c', long = "config", env = "FERROUS_BEATS_CONFIG")]
+    pub config: Option<PathBuf>,
     /// Server port
-    #[arg(short = 'p', long = "port", default_value_t = 13337)]
+    #[arg(
+        short = 'p',
+        long = "port",
+        env = "FERROUS_BEATS_PORT",
+        default_value_t = 13337
+    )]
     pub port: u16,
     /// Server host
-    #[arg(long = "host", default_value = "127.0.0.1")]
+    #[arg(long = "host", env = "FERROUS_BEATS_HOST", default_value = "127.0.0.1")]
     pub host: String,
     /// Disable DNS over HTTPS (DoH) for HTTP client. DoH provides some additional privacy compared to plain DNS.
-    #[arg(long = "disable-doh")]
+    #[arg(long = "disable-doh", env = "FERROUS_BEATS_DISABLE_DOH")]
     pub disable_doh: bool,
     /// Your file library directory
-    #[arg(short = 'l', long = "library-dir", default_value = "library")]
+    #[arg(
+        short = 'l',
+        long = "library-dir",
+        env = "FERROUS_BEATS_LIBRARY_DIR",
+        default_value = "library"
+    )]
     pub library_dir: String,
+    /// Template for names of downloaded audio files in the library. Use / for subdirectories. Fields: {artist}, {album}, {track} (zero padded with {track:02}), {title}, {uploader}, {id}, {extractor}, {upload_date} and {ext}
+    #[arg(long = "file-name-template", env = "FERROUS_BEATS_FILE_NAME_TEMPLATE", default_value = DEFAULT_FILE_NAME_TEMPLATE, value_parser = parse_file_name_template)]
+    pub file_name_template: FileNameTemplate,
     /// Download directory for all the used tools (yt-dlp, ffmpeg, chromparint)
-    #[arg(short = 't', long = "tools-download-dir", default_value = "tools")]
+    #[arg(
+        short = 't',
+        long = "tools-download-dir",
+        env = "FERROUS_BEATS_TOOLS_DOWNLOAD_DIR",
+        default_value = "tools"
+    )]
     pub tools_download_dir: String,
     /// Download directory for audio files. They will be moved to library directory after successful download
     #[arg(
         short = 'a',
         long = "audio-download-dir",
+        env = "FERROUS_BEATS_AUDIO_DOWNLOAD_DIR",
         default_value = "downloads/music"
     )]
     pub audio_download_dir: String,
@@ -45,7 +139,183 @@ pub struct RunCommand {
     #[arg(
         short = 'v',
         long = "video-download-dir",
+        env = "FERROUS_BEATS_VIDEO_DOWNLOAD_DIR",
         default_value = "downloads/videos"
     )]
     pub video_download_dir: String,
+    /// Directory for application data such as subscriptions
+    #[arg(
+        long = "data-dir",
+        env = "FERROUS_BEATS_DATA_DIR",
+        default_value = "data"
+    )]
+    pub data_dir: String,
+    /// Require logging in or an API token for the API, except for the health check. Create users and tokens with the admin command
+    #[arg(long = "enable-auth", env = "FERROUS_BEATS_ENABLE_AUTH")]
+    pub enable_auth: bool,
+    /// Comma separated origins of other sites that can call the API from browsers when authentication is enabled, for example https://music.example.com. Without authentication all origins can call it
+    #[arg(
+        long = "cors-origins",
+        env = "FERROUS_BEATS_CORS_ORIGINS",
+        value_delimiter = ',',
+        value_parser = parse_http_url
+    )]
+    pub cors_origins: Vec<String>,
+    /// Mark the session cookie as Secure, so browsers only send it over HTTPS. Enable it when the server is behind a reverse proxy serving HTTPS
+    #[arg(long = "secure-cookie", env = "FERROUS_BEATS_SECURE_COOKIE")]
+    pub secure_cookie: bool,
+    /// Run the doctor checks on startup and log a warning for each failed check
+    #[arg(long = "startup-self-test", env = "FERROUS_BEATS_STARTUP_SELF_TEST")]
+    pub startup_self_test: bool,
+    /// Base URL of the SponsorBlock API used when cutting segments. Defaults to the public API used by yt-dlp
+    #[arg(long = "sponsorblock-api-url", env = "FERROUS_BEATS_SPONSORBLOCK_API_URL", value_parser = parse_http_url)]
+    pub sponsorblock_api_url: Option<String>,
+    /// Cookies file in the Netscape format passed to yt-dlp for age-restricted and members-only content. Keep it readable only by the server user
+    #[arg(long = "cookies-file", env = "FERROUS_BEATS_COOKIES_FILE")]
+    pub cookies_file: Option<String>,
+    /// HTTP or SOCKS proxy used by yt-dlp, for example socks5://127.0.0.1:1080
+    #[arg(long = "proxy", env = "FERROUS_BEATS_PROXY", value_parser = parse_proxy_url)]
+    pub proxy: Option<ProxyUrl>,
+    /// Let API requests choose the proxy of yt-dlp, or disable the server proxy. Without it requests with a proxy are rejected
+    #[arg(
+        long = "allow-request-proxy",
+        env = "FERROUS_BEATS_ALLOW_REQUEST_PROXY"
+    )]
+    pub allow_request_proxy: bool,
+    /// Maximum download rate of yt-dlp in bytes per second, for example 50K or 4.2M
+    #[arg(long = "limit-rate", env = "FERROUS_BEATS_LIMIT_RATE", value_parser = parse_rate_limit)]
+    pub limit_rate: Option<RateLimit>,
+    /// Minimum number of seconds yt-dlp sleeps before each download
+    #[arg(long = "sleep-interval", env = "FERROUS_BEATS_SLEEP_INTERVAL", value_parser = parse_sleep_interval)]
+    pub sleep_interval: Option<SleepInterval>,
+    /// Maximum number of seconds yt-dlp sleeps before each download. Requires --sleep-interval
+    #[arg(long = "max-sleep-interval", env = "FERROUS_BEATS_MAX_SLEEP_INTERVAL", value_parser = parse_sleep_interval)]
+    pub max_sleep_interval: Option<SleepInterval>,
+    /// Number of seconds yt-dlp sleeps between requests during data extraction
+    #[arg(long = "sleep-requests", env = "FERROUS_BEATS_SLEEP_REQUESTS", value_parser = parse_sleep_interval)]
+    pub sleep_requests: Option<SleepInterval>,
+    /// Number of retries of failed yt-dlp downloads
+    #[arg(long = "retries", env = "FERROUS_BEATS_RETRIES")]
+    pub retries: Option<u32>,
+    /// Comma separated domains that can be downloaded from, including their subdomains. All domains are allowed if not set
+    #[arg(
+        long = "allowed-domains",
+        env = "FERROUS_BEATS_ALLOWED_DOMAINS",
+        value_delimiter = ','
+    )]
+    pub allowed_domains: Vec<String>,
+    /// Comma separated domains that cannot be downloaded from, including their subdomains. Takes precedence over allowed domains
+    #[arg(
+        long = "denied-domains",
+        env = "FERROUS_BEATS_DENIED_DOMAINS",
+        value_delimiter = ','
+    )]
+    pub denied_domains: Vec<String>,
+    /// Comma separated yt-dlp extractors that can be used, for example youtube,youtube:tab,youtube:search. All extractors are allowed if not set
+    #[arg(
+        long = "allowed-extractors",
+        env = "FERROUS_BEATS_ALLOWED_EXTRACTORS",
+        value_delimiter = ','
+    )]
+    pub allowed_extractors: Vec<String>,
+    /// Comma separated yt-dlp extractors that cannot be used, for example generic
+    #[arg(
+        long = "denied-extractors",
+        env = "FERROUS_BEATS_DENIED_EXTRACTORS",
+        value_delimiter = ','
+    )]
+    pub denied_extractors: Vec<String>,
+    /// Download direct links to audio files with yt-dlp instead of detecting them and downloading them over HTTP
+    #[arg(
+        long = "disable-direct-downloads",
+        env = "FERROUS_BEATS_DISABLE_DIRECT_DOWNLOADS"
+    )]
+    pub disable_direct_downloads: bool,
+    /// Maximum size in megabytes of audio files downloaded from direct links
+    #[arg(long = "max-direct-download-size", env = "FERROUS_BEATS_MAX_DIRECT_DOWNLOAD_SIZE", default_value_t = 1024, value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_direct_download_size: u64,
+    /// Comma separated steps run on audio files after they are downloaded: identify, tag, normalize, convert and organize. Steps always run in this order. No steps run if not set
+    #[arg(
+        long = "post-processing-steps",
+        env = "FERROUS_BEATS_POST_PROCESSING_STEPS",
+        value_enum,
+        value_delimiter = ','
+    )]
+    pub post_processing_steps: Vec<PostProcessingStep>,
+    /// Format audio files are converted to by the convert post-processing step
+    #[arg(long = "preferred-audio-format", env = "FERROUS_BEATS_PREFERRED_AUDIO_FORMAT", value_enum, default_value_t = AudioFormat::BestOriginal)]
+    pub preferred_audio_format: AudioFormat,
+    /// Integrated loudness in LUFS targeted by the normalize post-processing step
+    #[arg(long = "loudness-target", env = "FERROUS_BEATS_LOUDNESS_TARGET", default_value_t = -14.0, value_parser = parse_loudness_target, allow_negative_numbers = true)]
+    pub loudness_target: f64,
+    /// Logging level: trace, debug, info, warn or error
+    #[arg(long = "log-level", env = "FERROUS_BEATS_LOG_LEVEL", default_value = "info", value_parser = parse_log_level)]
+    pub log_level: Level,
+}
+
+#[derive(Debug, Parser)]
+pub struct ImportToolCommand {
+    /// Tool to import
+    #[arg(long = "tool", value_enum)]
+    pub tool: Tool,
+    /// Path to the archive (zip, tar, tar.gz, tar.xz, 7z), binary or directory containing the tool binaries
+    #[arg(long = "path")]
+    pub path: PathBuf,
+    /// Download directory for all the used tools (yt-dlp, ffmpeg, chromparint)
+    #[arg(short = 't', long = "tools-download-dir", default_value = "tools")]
+    pub tools_download_dir: String,
+}
+
+pub fn parse_http_url(value: &str) -> Result<String, String> {
+    if value.starts_with("http://") || value.starts_with("https://") {
+        Ok(value.trim_end_matches('/').to_string())
+    } else {
+        Err("URL must start with http:// or https://".to_string())
+    }
+}
+
+pub fn parse_file_name_template(value: &str) -> Result<FileNameTemplate, String> {
+    FileNameTemplate::try_from(value.to_string())
+}
+
+pub fn parse_proxy_url(value: &str) -> Result<ProxyUrl, String> {
+    ProxyUrl::try_from(value.to_string())
+}
+
+pub fn parse_rate_limit(value: &str) -> Result<RateLimit, String> {
+    RateLimit::try_from(value.to_string())
+}
+
+fn parse_sleep_interval(value: &str) -> Result<SleepInterval, String> {
+    let seconds = value
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid number of seconds: {}", value))?;
+    SleepInterval::try_from(seconds)
+}
+
+fn parse_loudness_target(value: &str) -> Result<f64, String> {
+    let loudness_target = value
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid loudness: {}", value))?;
+    check_loudness_target(loudness_target)
+}
+
+pub fn check_loudness_target(loudness_target: f64) -> Result<f64, String> {
+    if (MIN_LOUDNESS_TARGET..=MAX_LOUDNESS_TARGET).contains(&loudness_target) {
+        Ok(loudness_target)
+    } else {
+        Err(format!(
+            "Loudness target must be between {} and {} LUFS",
+            MIN_LOUDNESS_TARGET, MAX_LOUDNESS_TARGET
+        ))
+    }
+}
+
+pub fn parse_log_level(value: &str) -> Result<Level, String> {
+    value.parse::<Level>().map_err(|_| {
+        format!(
+            "Invalid log level: {}. Expected trace, debug, info, warn or error",
+            value
+        )
+    })
 }
@@ -1,12 +1,48 @@
 use crate::cli;
+use crate::handlers::shared::model::media::{AudioFormat, ProxyUrl, RateLimit, SleepInterval};
+use crate::handlers::shared::model::naming::FileNameTemplate;
+use crate::handlers::shared::model::postprocessing::PostProcessingStep;
+use anyhow::Context;
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
 use tracing::Level;
 
+/// Videos are kept in a subdirectory of the library so they are not listed together with music.
+const VIDEOS_LIBRARY_SUBDIR: &str = "videos";
+
+const BYTES_IN_MEGABYTE: u64 = 1024 * 1024;
+
+/// Config file searched for in the XDG config directories when no config file is given.
+const CONFIG_FILE_PATH: &str = "ferrous-beats/config.toml";
+/// Used when `XDG_CONFIG_DIRS` is not set, as defined by the XDG Base Directory Specification.
+const DEFAULT_XDG_CONFIG_DIRS: &str = "/etc/xdg";
+/// Shown in place of proxy URLs with credentials when the configuration is printed.
+const REDACTED_PROXY_URL: &str = "<redacted>";
+/// Settings that are only applied when the server starts. Reloading the config keeps their current values.
+const RESTART_REQUIRED_SETTINGS: &[&str] = &[
+    "server.port",
+    "server.host",
+    "server.disable_doh",
+    "server.startup_self_test",
+    "logging.level",
+];
+
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// Config file the settings were read from, if any.
+    pub config_file: Option<PathBuf>,
     pub server_settings: ServerSettings,
     pub library_settings: LibrarySettings,
     pub audio_download_settings: AudioDownloadSettings,
     pub video_download_settings: VideoDownloadSettings,
+    pub yt_dlp_settings: YtDlpSettings,
+    pub url_policy_settings: UrlPolicySettings,
+    pub post_processing_settings: PostProcessingSettings,
+    pub auth_settings: AuthSettings,
     pub logging_settings: LoggingSettings,
 }
 
@@ -16,16 +52,22 @@ pub struct ServerSettings {
     pub host: String,
     pub disable_doh: bool,
     pub tools_download_dir: String,
+    pub startup_self_test: bool,
+    pub data_dir: String,
 }
 
 #[derive(Debug, Clone)]
 pub struct LibrarySettings {
     pub dir: String,
+    pub videos_dir: String,
+    pub file_name_template: FileNameTemplate,
 }
 
 #[derive(Debug, Clone)]
 pub struct AudioDownloadSettings {
     pub download_dir: String,
+    pub direct_downloads_enabled: bool,
+    pub max_direct_download_bytes: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -33,48 +75,920 @@ pub struct VideoDownloadSettings {
     pub download_dir: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct YtDlpSettings {
+    pub sponsorblock_api_url: Option<String>,
+    pub cookies_file: Option<String>,
+    pub proxy: Option<ProxyUrl>,
+    /// Whether API requests can override the proxy. Otherwise requests could route server traffic through any host.
+    pub allow_request_proxy: bool,
+    pub limit_rate: Option<RateLimit>,
+    pub sleep_interval: Option<SleepInterval>,
+    pub max_sleep_interval: Option<SleepInterval>,
+    pub sleep_requests: Option<SleepInterval>,
+    pub retries: Option<u32>,
+}
+
+/// Domains and extractors that can be downloaded from. Empty allow lists allow everything that is not denied.
+#[derive(Debug, Clone)]
+pub struct UrlPolicySettings {
+    pub allowed_domains: Vec<String>,
+    pub denied_domains: Vec<String>,
+    pub allowed_extractors: Vec<String>,
+    pub denied_extractors: Vec<String>,
+}
+
+/// Steps run on audio files after they are downloaded, with the settings of the individual steps.
+#[derive(Debug, Clone)]
+pub struct PostProcessingSettings {
+    pub steps: Vec<PostProcessingStep>,
+    pub preferred_audio_format: AudioFormat,
+    pub loudness_target: f64,
+}
+
+/// Whether the API requires logging in or an API token. Users and tokens are stored in the data directory.
+#[derive(Debug, Clone)]
+pub struct AuthSettings {
+    pub enabled: bool,
+    /// Origins that can call the API from browsers while authentication is enabled.
+    pub cors_origins: Vec<String>,
+    /// Whether the session cookie is only sent over HTTPS.
+    pub secure_cookie: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct LoggingSettings {
     pub level: Level,
 }
 
-pub fn cli_to_config(
+/// Config of the running server. Reloading replaces the whole config at once, so readers always see a consistent snapshot.
+#[derive(Debug, Clone)]
+pub struct SharedConfig(Arc<RwLock<Arc<Config>>>);
+
+impl SharedConfig {
+    pub fn new(config: Config) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(config))))
+    }
+
+    /// Returns the current config. Long running tasks should keep the returned snapshot instead of reading the config again.
+    pub fn current(&self) -> Arc<Config> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn replace(&self, config: Config) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
+    }
+}
+
+/// Arguments the config was loaded from, kept to load the config again when the config file changes.
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    run_command: cli::RunCommand,
+    run_matches: ArgMatches,
+    is_verbose_logging_enabled: bool,
+}
+
+impl ConfigSource {
+    pub fn new(
+        run_command: cli::RunCommand,
+        run_matches: ArgMatches,
+        is_verbose_logging_enabled: bool,
+    ) -> Self {
+        Self {
+            run_command,
+            run_matches,
+            is_verbose_logging_enabled,
+        }
+    }
+
+    pub fn load(&self) -> anyhow::Result<Config> {
+        load_config(
+            &self.run_command,
+            &self.run_matches,
+            self.is_verbose_logging_enabled,
+        )
+    }
+}
+
+/// Settings read from the TOML config file. The sections mirror [`Config`] and all settings are optional.
+/// The same format is used to print the effective configuration.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    pub server: ServerFileSettings,
+    pub library: LibraryFileSettings,
+    pub audio_download: AudioDownloadFileSettings,
+    pub video_download: VideoDownloadFileSettings,
+    pub yt_dlp: YtDlpFileSettings,
+    pub url_policy: UrlPolicyFileSettings,
+    pub post_processing: PostProcessingFileSettings,
+    pub auth: AuthFileSettings,
+    pub logging: LoggingFileSettings,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_doh: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools_download_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup_self_test: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LibraryFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name_template: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioDownloadFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_direct_downloads: Option<bool>,
+    /// Maximum size in megabytes of audio files downloaded from direct links.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_direct_download_size: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VideoDownloadFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_dir: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct YtDlpFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sponsorblock_api_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cookies_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_request_proxy: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_rate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sleep_interval: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_sleep_interval: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sleep_requests: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UrlPolicyFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_domains: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub denied_domains: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_extractors: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub denied_extractors: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PostProcessingFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub steps: Option<Vec<PostProcessingStep>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_audio_format: Option<AudioFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loudness_target: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cors_origins: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secure_cookie: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingFileSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+/// Builds the config from the run command layered over the config file.
+/// Precedence from lowest to highest: defaults, config file, `FERROUS_BEATS_*` environment variables and flags.
+/// `run_matches` are the argument matches of the command the run command was parsed from, used to tell defaults from set values.
+pub fn load_config(
+    run_command: &cli::RunCommand,
+    run_matches: &ArgMatches,
+    is_verbose_logging_enabled: bool,
+) -> anyhow::Result<Config> {
+    let config_file = find_config_file(run_command.config.as_deref())?;
+    let file_config = match &config_file {
+        Some(config_file) => read_config_file(config_file)?,
+        None => FileConfig::default(),
+    };
+
+    let error_context = match &config_file {
+        Some(config_file) => format!("Invalid configuration in {}", config_file.display()),
+        None => "Invalid configuration".to_string(),
+    };
+
+    layer_config(
+        run_command,
+        run_matches,
+        config_file,
+        file_config,
+        is_verbose_logging_enabled,
+    )
+    .context(error_context)
+}
+
+/// Returns the given config file, or the first config file found in the XDG config directories.
+fn find_config_file(config_file: Option<&Path>) -> anyhow::Result<Option<PathBuf>> {
+    if let Some(config_file) = config_file {
+        if !config_file.is_file() {
+            anyhow::bail!("Config file does not exist: {}", config_file.display());
+        }
+        return Ok(Some(config_file.to_path_buf()));
+    }
+
+    Ok(get_config_dirs()
+        .into_iter()
+        .map(|config_dir| config_dir.join(CONFIG_FILE_PATH))
+        .find(|config_file| config_file.is_file()))
+}
+
+/// Config directories in the order of the XDG Base Directory Specification, with the user directory first.
+fn get_config_dirs() -> Vec<PathBuf> {
+    let mut config_dirs = Vec::new();
+
+    match std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+        Some(config_home) if config_home.is_absolute() => config_dirs.push(config_home),
+        _ => {
+            if let Some(home) = std::env::var_os("HOME") {
+                config_dirs.push(PathBuf::from(home).join(".config"));
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    if let Some(app_data) = std::env::var_os("APPDATA") {
+        config_dirs.push(PathBuf::from(app_data));
+    }
+
+    let system_config_dirs = std::env::var("XDG_CONFIG_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| DEFAULT_XDG_CONFIG_DIRS.to_string());
+    config_dirs.extend(
+        system_config_dirs
+            .split(':')
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute()),
+    );
+
+    config_dirs
+}
+
+fn read_config_file(config_file: &Path) -> anyhow::Result<FileConfig> {
+    let content = std::fs::read_to_string(config_file).context(format!(
+        "Failed to read config file {}",
+        config_file.display()
+    ))?;
+
+    // TOML errors include the line, column and key of the invalid setting
+    toml::from_str(&content).context(format!(
+        "Failed to parse config file {}",
+        config_file.display()
+    ))
+}
+
+fn layer_config(
     run_command: &cli::RunCommand,
+    run_matches: &ArgMatches,
+    config_file: Option<PathBuf>,
+    file_config: FileConfig,
     is_verbose_logging_enabled: bool,
 ) -> anyhow::Result<Config> {
+    let layer = ConfigLayer { run_matches };
+
+    let file_log_level = parse_file_setting("logging.level", file_config.logging.level, |level| {
+        cli::parse_log_level(&level)
+    })?;
     let logging_level = if is_verbose_logging_enabled {
         Level::DEBUG
     } else {
-        Level::INFO
+        layer.value("log_level", run_command.log_level, file_log_level)
     };
     let logging_settings = LoggingSettings {
         level: logging_level,
     };
 
+    let server = file_config.server;
     let server_settings = ServerSettings {
-        port: run_command.port,
-        host: run_command.host.clone(),
-        disable_doh: run_command.disable_doh,
-        tools_download_dir: run_command.tools_download_dir.clone(),
+        port: layer.value("port", run_command.port, server.port),
+        host: layer.value("host", run_command.host.clone(), server.host),
+        disable_doh: layer.value("disable_doh", run_command.disable_doh, server.disable_doh),
+        tools_download_dir: layer.value(
+            "tools_download_dir",
+            run_command.tools_download_dir.clone(),
+            server.tools_download_dir,
+        ),
+        startup_self_test: layer.value(
+            "startup_self_test",
+            run_command.startup_self_test,
+            server.startup_self_test,
+        ),
+        data_dir: layer.value("data_dir", run_command.data_dir.clone(), server.data_dir),
     };
 
+    let library = file_config.library;
+    let library_dir = layer.value("library_dir", run_command.library_dir.clone(), library.dir);
+    let file_file_name_template = parse_file_setting(
+        "library.file_name_template",
+        library.file_name_template,
+        FileNameTemplate::try_from,
+    )?;
     let library_settings = LibrarySettings {
-        dir: run_command.library_dir.clone(),
+        videos_dir: Path::new(&library_dir)
+            .join(VIDEOS_LIBRARY_SUBDIR)
+            .to_string_lossy()
+            .to_string(),
+        dir: library_dir,
+        file_name_template: layer.value(
+            "file_name_template",
+            run_command.file_name_template.clone(),
+            file_file_name_template,
+        ),
     };
 
+    let audio_download = file_config.audio_download;
+    let file_max_direct_download_size = parse_file_setting(
+        "audio_download.max_direct_download_size",
+        audio_download.max_direct_download_size,
+        |size| match size {
+            0 => Err("Size must be at least 1 megabyte".to_string()),
+            size => Ok(size),
+        },
+    )?;
     let audio_download_settings = AudioDownloadSettings {
-        download_dir: run_command.audio_download_dir.clone(),
+        download_dir: layer.value(
+            "audio_download_dir",
+            run_command.audio_download_dir.clone(),
+            audio_download.download_dir,
+        ),
+        direct_downloads_enabled: !layer.value(
+            "disable_direct_downloads",
+            run_command.disable_direct_downloads,
+            audio_download.disable_direct_downloads,
+        ),
+        max_direct_download_bytes: layer
+            .value(
+                "max_direct_download_size",
+                run_command.max_direct_download_size,
+                file_max_direct_download_size,
+            )
+            .saturating_mul(BYTES_IN_MEGABYTE),
     };
 
     let video_download_settings = VideoDownloadSettings {
-        download_dir: run_command.video_download_dir.clone(),
+        download_dir: layer.value(
+            "video_download_dir",
+            run_command.video_download_dir.clone(),
+            file_config.video_download.download_dir,
+        ),
+    };
+
+    let yt_dlp = file_config.yt_dlp;
+    let yt_dlp_settings = YtDlpSettings {
+        sponsorblock_api_url: layer.option(
+            "sponsorblock_api_url",
+            run_command.sponsorblock_api_url.clone(),
+            parse_file_setting(
+                "yt_dlp.sponsorblock_api_url",
+                yt_dlp.sponsorblock_api_url,
+                |url| cli::parse_http_url(&url),
+            )?,
+        ),
+        cookies_file: layer.option(
+            "cookies_file",
+            run_command.cookies_file.clone(),
+            yt_dlp.cookies_file,
+        ),
+        proxy: layer.option(
+            "proxy",
+            run_command.proxy.clone(),
+            parse_file_setting("yt_dlp.proxy", yt_dlp.proxy, ProxyUrl::try_from)?,
+        ),
+        allow_request_proxy: layer.value(
+            "allow_request_proxy",
+            run_command.allow_request_proxy,
+            yt_dlp.allow_request_proxy,
+        ),
+        limit_rate: layer.option(
+            "limit_rate",
+            run_command.limit_rate.clone(),
+            parse_file_setting("yt_dlp.limit_rate", yt_dlp.limit_rate, RateLimit::try_from)?,
+        ),
+        sleep_interval: layer.option(
+            "sleep_interval",
+            run_command.sleep_interval,
+            parse_file_setting(
+                "yt_dlp.sleep_interval",
+                yt_dlp.sleep_interval,
+                SleepInterval::try_from,
+            )?,
+        ),
+        max_sleep_interval: layer.option(
+            "max_sleep_interval",
+            run_command.max_sleep_interval,
+            parse_file_setting(
+                "yt_dlp.max_sleep_interval",
+                yt_dlp.max_sleep_interval,
+                SleepInterval::try_from,
+            )?,
+        ),
+        sleep_requests: layer.option(
+            "sleep_requests",
+            run_command.sleep_requests,
+            parse_file_setting(
+                "yt_dlp.sleep_requests",
+                yt_dlp.sleep_requests,
+                SleepInterval::try_from,
+            )?,
+        ),
+        retries: layer.option("retries", run_command.retries, yt_dlp.retries),
+    };
+
+    if let Some(cookies_file) = &yt_dlp_settings.cookies_file {
+        if !Path::new(cookies_file).is_file() {
+            anyhow::bail!("Cookies file does not exist: {}", cookies_file);
+        }
+    }
+    if yt_dlp_settings.max_sleep_interval.is_some() && yt_dlp_settings.sleep_interval.is_none() {
+        anyhow::bail!("The maximum sleep interval requires the sleep interval to be set");
+    }
+
+    let url_policy = file_config.url_policy;
+    let url_policy_settings = UrlPolicySettings {
+        allowed_domains: normalize_domains(&layer.value(
+            "allowed_domains",
+            run_command.allowed_domains.clone(),
+            url_policy.allowed_domains,
+        )),
+        denied_domains: normalize_domains(&layer.value(
+            "denied_domains",
+            run_command.denied_domains.clone(),
+            url_policy.denied_domains,
+        )),
+        allowed_extractors: normalize_extractors(&layer.value(
+            "allowed_extractors",
+            run_command.allowed_extractors.clone(),
+            url_policy.allowed_extractors,
+        )),
+        denied_extractors: normalize_extractors(&layer.value(
+            "denied_extractors",
+            run_command.denied_extractors.clone(),
+            url_policy.denied_extractors,
+        )),
+    };
+
+    let post_processing = file_config.post_processing;
+    let post_processing_settings = PostProcessingSettings {
+        steps: layer.value(
+            "post_processing_steps",
+            run_command.post_processing_steps.clone(),
+            post_processing.steps,
+        ),
+        preferred_audio_format: layer.value(
+            "preferred_audio_format",
+            run_command.preferred_audio_format,
+            post_processing.preferred_audio_format,
+        ),
+        loudness_target: layer.value(
+            "loudness_target",
+            run_command.loudness_target,
+            parse_file_setting(
+                "post_processing.loudness_target",
+                post_processing.loudness_target,
+                cli::check_loudness_target,
+            )?,
+        ),
+    };
+
+    let auth = file_config.auth;
+    let auth_settings = AuthSettings {
+        enabled: layer.value("enable_auth", run_command.enable_auth, auth.enabled),
+        cors_origins: layer.value(
+            "cors_origins",
+            run_command.cors_origins.clone(),
+            parse_file_setting("auth.cors_origins", auth.cors_origins, |cors_origins| {
+                cors_origins
+                    .iter()
+                    .map(|cors_origin| cli::parse_http_url(cors_origin))
+                    .collect::<Result<Vec<_>, _>>()
+            })?,
+        ),
+        secure_cookie: layer.value(
+            "secure_cookie",
+            run_command.secure_cookie,
+            auth.secure_cookie,
+        ),
     };
 
     Ok(Config {
+        config_file,
         server_settings,
         library_settings,
         audio_download_settings,
         video_download_settings,
+        yt_dlp_settings,
+        url_policy_settings,
+        post_processing_settings,
+        auth_settings,
         logging_settings,
     })
 }
+
+/// Picks between a run command value and a config file value based on where the run command value came from.
+struct ConfigLayer<'a> {
+    run_matches: &'a ArgMatches,
+}
+
+impl ConfigLayer<'_> {
+    /// Returns whether the argument was set using a flag or an environment variable rather than left at its default.
+    fn is_set(&self, arg_id: &str) -> bool {
+        matches!(
+            self.run_matches.value_source(arg_id),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        )
+    }
+
+    fn value<T>(&self, arg_id: &str, run_command_value: T, file_value: Option<T>) -> T {
+        match file_value {
+            Some(file_value) if !self.is_set(arg_id) => file_value,
+            _ => run_command_value,
+        }
+    }
+
+    fn option<T>(
+        &self,
+        arg_id: &str,
+        run_command_value: Option<T>,
+        file_value: Option<T>,
+    ) -> Option<T> {
+        self.value(arg_id, run_command_value, file_value.map(Some))
+    }
+}
+
+/// Validates a config file setting that is not checked while parsing the TOML, naming the setting in the error.
+fn parse_file_setting<T, U>(
+    key: &str,
+    value: Option<T>,
+    parse: impl FnOnce(T) -> Result<U, String>,
+) -> anyhow::Result<Option<U>> {
+    value
+        .map(parse)
+        .transpose()
+        .map_err(|err| anyhow::anyhow!("Invalid value of {}: {}", key, err))
+}
+
+impl From<&Config> for FileConfig {
+    fn from(config: &Config) -> Self {
+        let yt_dlp_settings = &config.yt_dlp_settings;
+        let url_policy_settings = &config.url_policy_settings;
+        let post_processing_settings = &config.post_processing_settings;
+
+        FileConfig {
+            server: ServerFileSettings {
+                port: Some(config.server_settings.port),
+                host: Some(config.server_settings.host.clone()),
+                disable_doh: Some(config.server_settings.disable_doh),
+                tools_download_dir: Some(config.server_settings.tools_download_dir.clone()),
+                data_dir: Some(config.server_settings.data_dir.clone()),
+                startup_self_test: Some(config.server_settings.startup_self_test),
+            },
+            library: LibraryFileSettings {
+                dir: Some(config.library_settings.dir.clone()),
+                file_name_template: Some(String::from(
+                    config.library_settings.file_name_template.clone(),
+                )),
+            },
+            audio_download: AudioDownloadFileSettings {
+                download_dir: Some(config.audio_download_settings.download_dir.clone()),
+                disable_direct_downloads: Some(
+                    !config.audio_download_settings.direct_downloads_enabled,
+                ),
+                max_direct_download_size: Some(
+                    config.audio_download_settings.max_direct_download_bytes / BYTES_IN_MEGABYTE,
+                ),
+            },
+            video_download: VideoDownloadFileSettings {
+                download_dir: Some(config.video_download_settings.download_dir.clone()),
+            },
+            yt_dlp: YtDlpFileSettings {
+                sponsorblock_api_url: yt_dlp_settings.sponsorblock_api_url.clone(),
+                cookies_file: yt_dlp_settings.cookies_file.clone(),
+                proxy: yt_dlp_settings.proxy.as_ref().map(redact_proxy_url),
+                allow_request_proxy: Some(yt_dlp_settings.allow_request_proxy),
+                limit_rate: yt_dlp_settings
+                    .limit_rate
+                    .as_ref()
+                    .map(|limit_rate| limit_rate.as_str().to_string()),
+                sleep_interval: yt_dlp_settings.sleep_interval.map(f64::from),
+                max_sleep_interval: yt_dlp_settings.max_sleep_interval.map(f64::from),
+                sleep_requests: yt_dlp_settings.sleep_requests.map(f64::from),
+                retries: yt_dlp_settings.retries,
+            },
+            url_policy: UrlPolicyFileSettings {
+                allowed_domains: Some(url_policy_settings.allowed_domains.clone()),
+                denied_domains: Some(url_policy_settings.denied_domains.clone()),
+                allowed_extractors: Some(url_policy_settings.allowed_extractors.clone()),
+                denied_extractors: Some(url_policy_settings.denied_extractors.clone()),
+            },
+            post_processing: PostProcessingFileSettings {
+                steps: Some(post_processing_settings.steps.clone()),
+                preferred_audio_format: Some(post_processing_settings.preferred_audio_format),
+                loudness_target: Some(post_processing_settings.loudness_target),
+            },
+            auth: AuthFileSettings {
+                enabled: Some(config.auth_settings.enabled),
+                cors_origins: Some(config.auth_settings.cors_origins.clone()),
+                secure_cookie: Some(config.auth_settings.secure_cookie),
+            },
+            logging: LoggingFileSettings {
+                level: Some(config.logging_settings.level.as_str().to_ascii_lowercase()),
+            },
+        }
+    }
+}
+
+/// Returns the settings that differ between the configs, named as in the config file, for example `library.dir`.
+pub fn get_changed_settings(old_config: &Config, new_config: &Config) -> Vec<String> {
+    let old_settings = get_flat_settings(old_config);
+    let new_settings = get_flat_settings(new_config);
+
+    old_settings
+        .keys()
+        .chain(new_settings.keys())
+        .filter(|key| old_settings.get(*key) != new_settings.get(*key))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Returns the changed settings that only take effect after a restart.
+pub fn get_restart_required_settings(changed_settings: &[String]) -> Vec<String> {
+    changed_settings
+        .iter()
+        .filter(|setting| RESTART_REQUIRED_SETTINGS.contains(&setting.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Copies the settings that are only applied on startup from the running config, so the config reflects what is in effect.
+pub fn keep_restart_required_settings(new_config: &mut Config, running_config: &Config) {
+    new_config.server_settings.port = running_config.server_settings.port;
+    new_config.server_settings.host = running_config.server_settings.host.clone();
+    new_config.server_settings.disable_doh = running_config.server_settings.disable_doh;
+    new_config.server_settings.startup_self_test = running_config.server_settings.startup_self_test;
+    new_config.logging_settings = running_config.logging_settings.clone();
+}
+
+/// Flattens the config into settings keyed by `section.setting` using the config file format.
+fn get_flat_settings(config: &Config) -> BTreeMap<String, toml::Value> {
+    let Ok(toml::Value::Table(sections)) = toml::Value::try_from(FileConfig::from(config)) else {
+        return BTreeMap::new();
+    };
+
+    sections
+        .into_iter()
+        .flat_map(|(section_name, section)| match section {
+            toml::Value::Table(settings) => settings
+                .into_iter()
+                .map(|(setting_name, value)| (format!("{}.{}", section_name, setting_name), value))
+                .collect::<Vec<_>>(),
+            value => vec![(section_name, value)],
+        })
+        .collect()
+}
+
+/// Hides proxy URLs with credentials, which are the part of the URL before `@`.
+fn redact_proxy_url(proxy: &ProxyUrl) -> String {
+    if proxy.as_str().contains('@') {
+        REDACTED_PROXY_URL.to_string()
+    } else {
+        proxy.as_str().to_string()
+    }
+}
+
+/// Lowercases domains and removes wildcard prefixes, so `*.Example.com` matches `example.com` and its subdomains.
+fn normalize_domains(domains: &[String]) -> Vec<String> {
+    domains
+        .iter()
+        .map(|domain| {
+            domain
+                .trim()
+                .trim_start_matches("*.")
+                .trim_matches('.')
+                .to_ascii_lowercase()
+        })
+        .filter(|domain| !domain.is_empty())
+        .collect()
+}
+
+fn normalize_extractors(extractors: &[String]) -> Vec<String> {
+    extractors
+        .iter()
+        .map(|extractor| extractor.trim().to_ascii_lowercase())
+        .filter(|extractor| !extractor.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    fn get_test_config(args: &[&str], file_config: &str) -> Config {
+        let run_matches = cli::RunCommand::command()
+            .try_get_matches_from(std::iter::once("run").chain(args.iter().copied()))
+            .unwrap();
+        let run_command = cli::RunCommand::from_arg_matches(&run_matches).unwrap();
+
+        layer_config(
+            &run_command,
+            &run_matches,
+            None,
+            toml::from_str(file_config).unwrap(),
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn file_settings_override_defaults() {
+        let config = get_test_config(
+            &[],
+            r#"
+[server]
+port = 9000
+host = "0.0.0.0"
+
+[logging]
+level = "warn"
+"#,
+        );
+
+        assert_eq!(config.server_settings.port, 9000);
+        assert_eq!(config.server_settings.host, "0.0.0.0");
+        assert_eq!(config.logging_settings.level, Level::WARN);
+    }
+
+    #[test]
+    fn flags_override_file_settings() {
+        let config = get_test_config(
+            &[
+                "--port",
+                "9001",
+                "--denied-domains",
+                "Example.com",
+                "--disable-direct-downloads",
+            ],
+            r#"
+[server]
+port = 9000
+host = "0.0.0.0"
+
+[url_policy]
+denied_domains = ["example.org"]
+
+[audio_download]
+disable_direct_downloads = false
+"#,
+        );
+
+        assert_eq!(config.server_settings.port, 9001);
+        assert_eq!(config.server_settings.host, "0.0.0.0");
+        assert_eq!(config.url_policy_settings.denied_domains, ["example.com"]);
+        assert!(!config.audio_download_settings.direct_downloads_enabled);
+    }
+
+    #[test]
+    fn flags_set_to_their_default_values_override_file_settings() {
+        let default_port = get_test_config(&[], "").server_settings.port;
+        let config = get_test_config(
+            &["--port", &default_port.to_string()],
+            &format!("[server]\nport = {}", default_port + 1),
+        );
+
+        assert_eq!(config.server_settings.port, default_port);
+    }
+
+    #[test]
+    fn rejects_invalid_file_settings() {
+        let run_matches = cli::RunCommand::command()
+            .try_get_matches_from(["run"])
+            .unwrap();
+        let run_command = cli::RunCommand::from_arg_matches(&run_matches).unwrap();
+        let file_config = toml::from_str("[logging]\nlevel = \"loud\"").unwrap();
+
+        assert!(layer_config(&run_command, &run_matches, None, file_config, false).is_err());
+        assert!(toml::from_str::<FileConfig>("[server]\nunknown = 1").is_err());
+    }
+
+    #[test]
+    fn reports_changed_settings_by_file_name() {
+        let old_config = get_test_config(&[], "");
+        let new_config = get_test_config(
+            &[
+                "--port",
+                "9001",
+                "--disable-direct-downloads",
+                "--allowed-domains",
+                "youtube.com",
+            ],
+            "",
+        );
+
+        assert!(get_changed_settings(&old_config, &old_config).is_empty());
+        assert_eq!(
+            get_changed_settings(&old_config, &new_config),
+            [
+                "audio_download.disable_direct_downloads",
+                "server.port",
+                "url_policy.allowed_domains"
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_settings_that_require_a_restart() {
+        let running_config = get_test_config(&[], "");
+        let mut new_config = get_test_config(
+            &[
+                "--port",
+                "9001",
+                "--host",
+                "0.0.0.0",
+                "--disable-direct-downloads",
+            ],
+            "",
+        );
+
+        let changed_settings = get_changed_settings(&running_config, &new_config);
+        assert_eq!(
+            get_restart_required_settings(&changed_settings),
+            ["server.host", "server.port"]
+        );
+
+        keep_restart_required_settings(&mut new_config, &running_config);
+        assert_eq!(
+            get_changed_settings(&running_config, &new_config),
+            ["audio_download.disable_direct_downloads"]
+        );
+    }
+
+    #[test]
+    fn normalizes_domains() {
+        let domains = [
+            " YouTube.com ",
+            "*.soundcloud.com",
+            ".bandcamp.com.",
+            "",
+            "  ",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            normalize_domains(&domains),
+            ["youtube.com", "soundcloud.com", "bandcamp.com"]
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::reload::reload_config;
+use crate::handlers::shared::model::reload::ConfigReloadResponse;
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    post,
+    path = "/admin/reload",
+    operation_id = "config_reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Config reloaded", body = ConfigReloadResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_config_reload(
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<ConfigReloadResponse>), ServerError> {
+    debug!("Handling config reload");
+
+    let config_reload_response = reload_config(&app_state).await?;
+
+    Ok((StatusCode::OK, Json(config_reload_response)))
+}
@@ -0,0 +1,49 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::auth::{get_session_cookie, log_in};
+use crate::handlers::shared::model::auth::{AuthSessionResponse, LoginRequest, TokenScope};
+use crate::AppState;
+use axum::extract::State;
+use axum::http::header::SET_COOKIE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    operation_id = "login",
+    tag = "auth",
+    security(()),
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in. The session cookie is set", body = AuthSessionResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state, payload), fields(username = %payload.username))]
+pub async fn handle_login(
+    State(app_state): State<AppState>,
+    Json(payload): Json<LoginRequest>,
+) -> Result<(StatusCode, HeaderMap, Json<AuthSessionResponse>), ServerError> {
+    debug!("Handling login");
+
+    let (session_id, session) = log_in(&app_state, &payload.username, payload.password).await?;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        SET_COOKIE,
+        get_session_cookie(&session_id, app_state.config().auth_settings.secure_cookie)?,
+    );
+
+    Ok((
+        StatusCode::OK,
+        response_headers,
+        Json(AuthSessionResponse {
+            auth_enabled: app_state.config().auth_settings.enabled,
+            username: Some(session.username),
+            token_name: None,
+            scopes: TokenScope::ALL.to_vec(),
+            expires_at: Some(session.expires_at),
+        }),
+    ))
+}
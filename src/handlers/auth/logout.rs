@@ -0,0 +1,48 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::auth::{get_expired_session_cookie, log_out};
+use crate::handlers::shared::model::auth::AuthSessionResponse;
+use crate::AppState;
+use axum::extract::State;
+use axum::http::header::SET_COOKIE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    operation_id = "logout",
+    tag = "auth",
+    security(()),
+    responses(
+        (status = 200, description = "Logged out. The session cookie is removed", body = AuthSessionResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state, headers))]
+pub async fn handle_logout(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap, Json<AuthSessionResponse>), ServerError> {
+    debug!("Handling logout");
+
+    log_out(&app_state, &headers).await;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        SET_COOKIE,
+        get_expired_session_cookie(app_state.config().auth_settings.secure_cookie)?,
+    );
+
+    Ok((
+        StatusCode::OK,
+        response_headers,
+        Json(AuthSessionResponse {
+            auth_enabled: app_state.config().auth_settings.enabled,
+            username: None,
+            token_name: None,
+            scopes: Vec::new(),
+            expires_at: None,
+        }),
+    ))
+}
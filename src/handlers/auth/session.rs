@@ -0,0 +1,56 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::auth::get_session;
+use crate::handlers::shared::model::auth::{AuthSessionResponse, Principal, TokenScope};
+use crate::AppState;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Extension, Json};
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    get,
+    path = "/auth/session",
+    operation_id = "auth_session",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Who the request is authenticated as", body = AuthSessionResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state, headers))]
+pub async fn handle_auth_session(
+    State(app_state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<AuthSessionResponse>), ServerError> {
+    debug!("Handling checking of the authentication session");
+
+    let auth_session_response = match principal {
+        Some(Extension(Principal::User { username })) => AuthSessionResponse {
+            auth_enabled: true,
+            username: Some(username),
+            token_name: None,
+            scopes: TokenScope::ALL.to_vec(),
+            expires_at: get_session(&app_state, &headers)
+                .await
+                .map(|session| session.expires_at),
+        },
+        Some(Extension(Principal::Token { name, scopes })) => AuthSessionResponse {
+            auth_enabled: true,
+            username: None,
+            token_name: Some(name),
+            scopes,
+            expires_at: None,
+        },
+        // The authentication middleware only lets requests without a caller through when authentication is disabled
+        None => AuthSessionResponse {
+            auth_enabled: false,
+            username: None,
+            token_name: None,
+            scopes: TokenScope::ALL.to_vec(),
+            expires_at: None,
+        },
+    };
+
+    Ok((StatusCode::OK, Json(auth_session_response)))
+}
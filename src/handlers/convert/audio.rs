@@ -1,18 +1,35 @@
-use crate::handlers::errors::ServerError;
+use crate::handlers::errors::{ErrorCode, ErrorResponses, RequestError, ServerError};
+use crate::handlers::shared::functions::convert::convert_audio_file;
+use crate::handlers::shared::functions::files::check_file_exists;
 use crate::handlers::shared::model::commands::CommandExecutionResults;
+use crate::handlers::shared::model::media::AudioFormat;
 use crate::AppState;
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::Json;
 use serde::Deserialize;
-use tracing::{debug, instrument};
+use std::path::Path;
+use tracing::{debug, error, info, instrument};
+use utoipa::ToSchema;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ConvertAudioRequest {
     audio_file_path: String,
-    output_format: String,
+    output_format: AudioFormat,
 }
 
+#[utoipa::path(
+    post,
+    path = "/convert/audio",
+    operation_id = "audio_conversion",
+    tag = "convert",
+    request_body = ConvertAudioRequest,
+    responses(
+        (status = 200, description = "Audio file converted", body = CommandExecutionResults),
+        (status = 400, description = "ffmpeg failed to convert the audio file", body = CommandExecutionResults),
+        ErrorResponses
+    )
+)]
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn handle_audio_conversion(
     State(app_state): State<AppState>,
@@ -20,5 +37,34 @@ pub async fn handle_audio_conversion(
 ) -> Result<(StatusCode, Json<CommandExecutionResults>), ServerError> {
     debug!("Handling audio conversion");
 
-    todo!()
+    if payload.output_format == AudioFormat::BestOriginal {
+        return Err(RequestError::new(
+            ErrorCode::InvalidRequest,
+            format!(
+                "Output format must be a specific format, not {}",
+                AudioFormat::BestOriginal
+            ),
+        )
+        .into());
+    }
+
+    check_file_exists(Path::new(&payload.audio_file_path)).await?;
+
+    let (converted_file_path, command_execution_results) = convert_audio_file(
+        &app_state,
+        Path::new(&payload.audio_file_path),
+        payload.output_format,
+    )
+    .await?;
+
+    match converted_file_path {
+        Some(converted_file_path) => {
+            info!("Converted audio file to {}", converted_file_path.display());
+            Ok((StatusCode::OK, Json(command_execution_results)))
+        }
+        None => {
+            error!("Failed to convert audio file using ffmpeg");
+            Ok((StatusCode::BAD_REQUEST, Json(command_execution_results)))
+        }
+    }
 }
@@ -0,0 +1,37 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::doctor::run_doctor_checks;
+use crate::handlers::shared::model::doctor::DoctorResponse;
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    get,
+    path = "/doctor",
+    operation_id = "doctor",
+    tag = "server",
+    responses(
+        (status = 200, description = "All checks passed", body = DoctorResponse),
+        (status = 503, description = "One or more checks failed", body = DoctorResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_doctor(
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<DoctorResponse>), ServerError> {
+    debug!("Handling doctor health check");
+
+    let doctor_response = run_doctor_checks(&app_state).await;
+
+    Ok((
+        if doctor_response.healthy {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        },
+        Json(doctor_response),
+    ))
+}
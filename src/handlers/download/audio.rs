@@ -1,8 +1,20 @@
-use crate::handlers::errors::ServerError;
-use crate::handlers::shared::functions::commands::run_command;
-use crate::handlers::shared::functions::files::search_and_move_media_file;
-use crate::handlers::shared::functions::tools::get_yt_dlp_executable_path;
-use crate::handlers::shared::model::media::MediaDownloadResponse;
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::direct::{
+    describe_direct_audio_source, detect_direct_audio_source, download_direct_audio,
+    DIRECT_DOWNLOAD_EXTRACTOR,
+};
+use crate::handlers::shared::functions::library::{
+    find_library_index_entry_by_source, move_downloaded_media_to_library,
+};
+use crate::handlers::shared::functions::postprocessing::run_post_processing;
+use crate::handlers::shared::functions::urls::validate_media_url;
+use crate::handlers::shared::functions::yt_dlp::{
+    download_audio_with_yt_dlp, fetch_media_source_id,
+};
+use crate::handlers::shared::model::media::{
+    AudioDownloadOptions, DownloadMethod, MediaDownloadResponse, YtDlpNetworkOptions,
+};
+use crate::handlers::shared::model::postprocessing::PostProcessingStep;
 use crate::AppState;
 use anyhow::Context;
 use axum::extract::State;
@@ -12,13 +24,36 @@ use serde::Deserialize;
 use std::path::Path;
 use tokio::fs::create_dir_all;
 use tracing::{debug, error, info, instrument};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct DownloadAudioRequest {
     audio_url: String,
+    /// Download the media even if it is already in the library.
+    #[serde(default)]
+    force: bool,
+    #[serde(flatten)]
+    audio_download_options: AudioDownloadOptions,
+    #[serde(flatten)]
+    network_options: YtDlpNetworkOptions,
+    /// Configured post-processing steps that are not run for this download.
+    #[serde(default)]
+    skip_post_processing: Vec<PostProcessingStep>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/download/audio",
+    operation_id = "audio_download",
+    tag = "download",
+    request_body = DownloadAudioRequest,
+    responses(
+        (status = 200, description = "Audio downloaded to the library", body = MediaDownloadResponse),
+        (status = 400, description = "The download failed", body = MediaDownloadResponse),
+        ErrorResponses
+    )
+)]
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn handle_audio_download(
     State(app_state): State<AppState>,
@@ -26,80 +61,146 @@ pub async fn handle_audio_download(
 ) -> Result<(StatusCode, Json<MediaDownloadResponse>), ServerError> {
     debug!("Handling audio download");
 
+    let config = app_state.config();
+    let audio_url = validate_media_url(&config.url_policy_settings, &payload.audio_url)?;
     let download_id = Uuid::new_v4();
 
-    let audio_download_dir = Path::new(&app_state.config.audio_download_settings.download_dir);
+    let audio_download_dir = Path::new(&config.audio_download_settings.download_dir);
     create_dir_all(audio_download_dir)
         .await
         .context("Failed to create download directory for audio")?;
 
-    let yt_dlp_executable_path = get_yt_dlp_executable_path(&app_state)
+    let library_dir = Path::new(&config.library_settings.dir);
+    create_dir_all(library_dir)
         .await
-        .context("Failed to get yt-dlp executable path")?;
+        .context("Failed to create library directory")?;
 
-    info!(
-        "Downloading audio using yt-dlp. Download ID: {}",
-        download_id
-    );
+    // Direct downloads keep the file as served, so options that need post-processing are left to yt-dlp
+    let direct_audio_source = if payload.audio_download_options.requires_post_processing() {
+        None
+    } else {
+        detect_direct_audio_source(&app_state, &audio_url, &payload.network_options).await?
+    };
+    let download_method = if direct_audio_source.is_some() {
+        DownloadMethod::Direct
+    } else {
+        DownloadMethod::YtDlp
+    };
 
-    let output_path = audio_download_dir
-        .canonicalize()
-        .context("Failed to canonicalize audio output path")?;
-    let output_path_str = output_path
-        .join(format!("{}%(title)s.%(ext)s", download_id))
-        .to_string_lossy()
-        .to_string();
+    if !payload.force {
+        let (command_execution_results, source_id) = match &direct_audio_source {
+            Some(direct_audio_source) => (
+                describe_direct_audio_source(direct_audio_source),
+                Some((
+                    DIRECT_DOWNLOAD_EXTRACTOR.to_string(),
+                    direct_audio_source.url.clone(),
+                )),
+            ),
+            None => fetch_media_source_id(&app_state, &audio_url, &payload.network_options).await?,
+        };
 
-    info!("Using output path: {:#?}", output_path_str);
+        if let Some((extractor, media_id)) = source_id {
+            if let Some(existing_library_entry) =
+                find_library_index_entry_by_source(library_dir, &extractor, &media_id).await?
+            {
+                info!(
+                    "Media is already in the library as {}, skipping download",
+                    existing_library_entry.file_name
+                );
+                return Ok((
+                    StatusCode::OK,
+                    Json(MediaDownloadResponse {
+                        download_id: download_id.to_string(),
+                        library_dir: library_dir.to_string_lossy().to_string(),
+                        requested_url: audio_url.clone(),
+                        download_method,
+                        library_files: Vec::new(),
+                        already_in_library: true,
+                        existing_library_entry: Some(existing_library_entry),
+                        command_execution_results,
+                        post_processing: Vec::new(),
+                    }),
+                ));
+            }
+        }
+    }
 
-    let command_execution_results = run_command(
-        &yt_dlp_executable_path,
-        &[
-            "-x",
-            &payload.audio_url,
-            "-o",
-            &output_path_str,
-            "--no-progress",
-        ],
-    )
-    .await
-    .context("Failed to download audio using yt-dlp")?;
-
-    let library_dir = Path::new(&app_state.config.library_settings.dir);
-    create_dir_all(library_dir)
-        .await
-        .context("Failed to create library directory")?;
+    let command_execution_results = match &direct_audio_source {
+        Some(direct_audio_source) => {
+            info!(
+                "Downloading direct link to audio file over HTTP. Download ID: {}",
+                download_id
+            );
+            download_direct_audio(
+                &app_state,
+                direct_audio_source,
+                audio_download_dir,
+                &download_id.to_string(),
+            )
+            .await?
+        }
+        None => {
+            info!(
+                "Downloading audio using yt-dlp. Download ID: {}",
+                download_id
+            );
+            download_audio_with_yt_dlp(
+                &app_state,
+                &audio_url,
+                &download_id.to_string(),
+                &payload.audio_download_options,
+                &payload.network_options,
+            )
+            .await?
+        }
+    };
 
     if !command_execution_results.command_completed_successfully {
-        error!("Failed to download audio using yt-dlp");
+        error!("Failed to download audio");
         return Ok((
             StatusCode::BAD_REQUEST,
             Json(MediaDownloadResponse {
                 download_id: download_id.to_string(),
                 library_dir: library_dir.to_string_lossy().to_string(),
-                requested_url: payload.audio_url,
+                requested_url: audio_url.clone(),
+                download_method,
+                library_files: Vec::new(),
+                already_in_library: false,
+                existing_library_entry: None,
                 command_execution_results,
+                post_processing: Vec::new(),
             }),
         ));
     }
 
-    info!("Moving downloaded file to the library");
-    search_and_move_media_file(
-        &audio_download_dir.to_path_buf(),
-        &library_dir.to_path_buf(),
+    let library_files = move_downloaded_media_to_library(
+        &app_state,
+        audio_download_dir,
+        library_dir,
         &download_id.to_string(),
-        true,
     )
-    .await
-    .context("Failed to find and move media file")?;
+    .await?;
+
+    let (library_files, post_processing) = run_post_processing(
+        &app_state,
+        library_dir,
+        library_files,
+        &payload.skip_post_processing,
+    )
+    .await;
 
     Ok((
         StatusCode::OK,
         Json(MediaDownloadResponse {
             download_id: download_id.to_string(),
             library_dir: library_dir.to_string_lossy().to_string(),
-            requested_url: payload.audio_url,
+            requested_url: audio_url,
+            download_method,
+            library_files,
+            already_in_library: false,
+            existing_library_entry: None,
             command_execution_results,
+            post_processing,
         }),
     ))
 }
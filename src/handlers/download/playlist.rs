@@ -0,0 +1,103 @@
+use crate::handlers::errors::{ErrorCode, ErrorResponses, RequestError, ServerError};
+use crate::handlers::shared::functions::playlists::{download_playlist, start_playlist_download};
+use crate::handlers::shared::functions::urls::validate_media_url;
+use crate::handlers::shared::model::media::{
+    PlaylistDownloadJob, PlaylistDownloadOptions, YtDlpNetworkOptions,
+};
+use crate::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use tracing::{debug, error, instrument, Instrument};
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DownloadPlaylistRequest {
+    playlist_url: String,
+    #[serde(flatten)]
+    playlist_download_options: PlaylistDownloadOptions,
+    #[serde(flatten)]
+    network_options: YtDlpNetworkOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/download/playlist",
+    operation_id = "playlist_download",
+    tag = "download",
+    request_body = DownloadPlaylistRequest,
+    responses(
+        (status = 202, description = "Playlist download started. Its progress can be checked with the playlist download status", body = PlaylistDownloadJob),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_playlist_download(
+    State(app_state): State<AppState>,
+    Json(payload): Json<DownloadPlaylistRequest>,
+) -> Result<(StatusCode, Json<PlaylistDownloadJob>), ServerError> {
+    debug!("Handling playlist download");
+
+    let playlist_url = validate_media_url(
+        &app_state.config().url_policy_settings,
+        &payload.playlist_url,
+    )?;
+    let job = start_playlist_download(&app_state, &playlist_url).await?;
+
+    // Playlists can take hours to download, so the job runs in the background and its state is kept in the registry
+    let download_job = job.clone();
+    tokio::spawn(
+        async move {
+            if let Err(err) = download_playlist(
+                &app_state,
+                download_job,
+                &payload.playlist_download_options,
+                &payload.network_options,
+            )
+            .await
+            {
+                error!("Playlist download failed: {:#}", err);
+            }
+        }
+        .in_current_span(),
+    );
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/download/playlist/{download_id}",
+    operation_id = "playlist_download_status",
+    tag = "download",
+    params(
+        ("download_id" = String, Path, description = "ID of the playlist download. Finished downloads are kept for 24 hours")
+    ),
+    responses(
+        (status = 200, description = "State of the playlist download", body = PlaylistDownloadJob),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_playlist_download_status(
+    Path(download_id): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<PlaylistDownloadJob>), ServerError> {
+    debug!("Handling checking of playlist download status");
+
+    let job = app_state
+        .playlist_downloads
+        .read()
+        .await
+        .get(&download_id)
+        .map(|entry| entry.job.clone())
+        .ok_or_else(|| {
+            RequestError::new(
+                ErrorCode::NotFound,
+                format!("No playlist download with the ID {}", download_id),
+            )
+        })?;
+
+    Ok((StatusCode::OK, Json(job)))
+}
@@ -0,0 +1,97 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::urls::validate_search_source;
+use crate::handlers::shared::functions::yt_dlp::{
+    fetch_playlist_info, flatten_playlist_entries, get_yt_dlp_search_url,
+};
+use crate::handlers::shared::model::media::YtDlpNetworkOptions;
+use crate::handlers::shared::model::search::{
+    MediaSearchResponse, SearchCandidate, SearchQuery, SearchResultCount, SearchSource,
+};
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use tracing::{debug, error, info, instrument};
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SearchMediaRequest {
+    query: SearchQuery,
+    #[serde(default)]
+    source: SearchSource,
+    #[serde(default)]
+    max_results: SearchResultCount,
+    #[serde(flatten)]
+    network_options: YtDlpNetworkOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/download/search",
+    operation_id = "media_search",
+    tag = "download",
+    request_body = SearchMediaRequest,
+    responses(
+        (status = 200, description = "Search results", body = MediaSearchResponse),
+        (status = 400, description = "The search failed", body = MediaSearchResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_media_search(
+    State(app_state): State<AppState>,
+    Json(payload): Json<SearchMediaRequest>,
+) -> Result<(StatusCode, Json<MediaSearchResponse>), ServerError> {
+    debug!("Handling media search");
+
+    validate_search_source(&app_state.config().url_policy_settings, payload.source)?;
+
+    let search_url = get_yt_dlp_search_url(payload.source, payload.max_results, &payload.query);
+
+    info!("Searching media using yt-dlp");
+    let (command_execution_results, search_info) =
+        fetch_playlist_info(&app_state, &search_url, None, &payload.network_options).await?;
+
+    let Some(search_info) = search_info else {
+        error!("Failed to search media using yt-dlp");
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(MediaSearchResponse {
+                query: payload.query,
+                source: payload.source,
+                candidates: Vec::new(),
+                command_execution_results,
+            }),
+        ));
+    };
+
+    let candidates = flatten_playlist_entries(search_info.entries)
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| SearchCandidate {
+            index: index + 1,
+            id: entry.id,
+            title: entry.title,
+            uploader: entry.uploader.or(entry.channel),
+            duration: entry.duration,
+            thumbnail: entry
+                .thumbnails
+                .into_iter()
+                .last()
+                .map(|thumbnail| thumbnail.url),
+            url: entry.url,
+            extractor: entry.ie_key.map(|ie_key| ie_key.to_lowercase()),
+        })
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(MediaSearchResponse {
+            query: payload.query,
+            source: payload.source,
+            candidates,
+            command_execution_results,
+        }),
+    ))
+}
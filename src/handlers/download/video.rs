@@ -0,0 +1,119 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::files::search_and_move_media_files;
+use crate::handlers::shared::functions::urls::validate_media_url;
+use crate::handlers::shared::functions::yt_dlp::download_video_with_yt_dlp;
+use crate::handlers::shared::model::media::{
+    DownloadMethod, MediaDownloadResponse, VideoDownloadOptions, YtDlpNetworkOptions,
+};
+use crate::AppState;
+use anyhow::Context;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use std::path::Path;
+use tokio::fs::create_dir_all;
+use tracing::{debug, error, info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DownloadVideoRequest {
+    video_url: String,
+    #[serde(flatten)]
+    video_download_options: VideoDownloadOptions,
+    #[serde(flatten)]
+    network_options: YtDlpNetworkOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/download/video",
+    operation_id = "video_download",
+    tag = "download",
+    request_body = DownloadVideoRequest,
+    responses(
+        (status = 200, description = "Video downloaded to the videos library", body = MediaDownloadResponse),
+        (status = 400, description = "The download failed", body = MediaDownloadResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_video_download(
+    State(app_state): State<AppState>,
+    Json(payload): Json<DownloadVideoRequest>,
+) -> Result<(StatusCode, Json<MediaDownloadResponse>), ServerError> {
+    debug!("Handling video download");
+
+    let config = app_state.config();
+    let video_url = validate_media_url(&config.url_policy_settings, &payload.video_url)?;
+
+    let download_id = Uuid::new_v4();
+
+    let video_download_dir = Path::new(&config.video_download_settings.download_dir);
+    create_dir_all(video_download_dir)
+        .await
+        .context("Failed to create download directory for video")?;
+
+    info!(
+        "Downloading video using yt-dlp. Download ID: {}",
+        download_id
+    );
+
+    let command_execution_results = download_video_with_yt_dlp(
+        &app_state,
+        &video_url,
+        &download_id.to_string(),
+        &payload.video_download_options,
+        &payload.network_options,
+    )
+    .await?;
+
+    let videos_library_dir = Path::new(&config.library_settings.videos_dir);
+    create_dir_all(videos_library_dir)
+        .await
+        .context("Failed to create videos library directory")?;
+
+    if !command_execution_results.command_completed_successfully {
+        error!("Failed to download video using yt-dlp");
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(MediaDownloadResponse {
+                download_id: download_id.to_string(),
+                library_dir: videos_library_dir.to_string_lossy().to_string(),
+                requested_url: video_url,
+                download_method: DownloadMethod::YtDlp,
+                library_files: Vec::new(),
+                already_in_library: false,
+                existing_library_entry: None,
+                command_execution_results,
+                post_processing: Vec::new(),
+            }),
+        ));
+    }
+
+    info!("Moving downloaded files to the videos library");
+    let library_files = search_and_move_media_files(
+        &video_download_dir.to_path_buf(),
+        &videos_library_dir.to_path_buf(),
+        &download_id.to_string(),
+        true,
+    )
+    .await
+    .context("Failed to find and move media files")?;
+
+    Ok((
+        StatusCode::OK,
+        Json(MediaDownloadResponse {
+            download_id: download_id.to_string(),
+            library_dir: videos_library_dir.to_string_lossy().to_string(),
+            requested_url: video_url,
+            download_method: DownloadMethod::YtDlp,
+            library_files,
+            already_in_library: false,
+            existing_library_entry: None,
+            command_execution_results,
+            post_processing: Vec::new(),
+        }),
+    ))
+}
@@ -1,9 +1,245 @@
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::body::{to_bytes, Body};
+use axum::extract::Request;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
+use tower_http::request_id::RequestId;
+use tracing::{error, warn};
+use utoipa::openapi::content::ContentBuilder;
+use utoipa::openapi::response::ResponseBuilder;
+use utoipa::openapi::RefOr;
+use utoipa::{IntoResponses, PartialSchema, ToSchema};
+
+/// Media type of error responses, see RFC 9457.
+const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+/// Sent with `401 Unauthorized` responses. API tokens are sent as bearer tokens, the UI logs in with a session cookie instead.
+const AUTHENTICATE_CHALLENGE: &str = "Bearer realm=\"Ferrous Beats\"";
+/// Longest body of an error response created outside of handlers, such as an extractor rejection, that is kept as the error message.
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+/// Message of internal errors sent to clients. The reason is only logged, together with the request ID.
+const INTERNAL_ERROR_DETAIL: &str =
+    "The server failed to process the request. The server logs contain the reason under the request ID";
 
 pub struct ServerError(anyhow::Error);
 
+/// Context chain of an internal error. It is attached to the response, so it is logged with the request ID instead of being sent to the client.
+#[derive(Clone)]
+struct InternalErrorChain(String);
+
+/// Stable, machine-readable code of an API error. Clients can rely on the codes, while messages can change between versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request is invalid, for example it has a malformed body or a file name outside of the library.
+    InvalidRequest,
+    /// The URL is malformed or not allowed by the URL policy.
+    UrlNotAllowed,
+    /// The file to download is larger than the configured maximum size.
+    DownloadTooLarge,
+    /// The request body is larger than the server accepts.
+    PayloadTooLarge,
+    /// The request body has an unsupported content type.
+    UnsupportedMediaType,
+    /// The endpoint does not support the request method.
+    MethodNotAllowed,
+    /// The file does not exist.
+    FileNotFound,
+    /// The endpoint or resource, such as a subscription or a playlist download, does not exist.
+    NotFound,
+    /// The request is not authenticated with a session cookie or an API token, or the API token is invalid.
+    AuthenticationRequired,
+    /// The username or password is wrong.
+    InvalidCredentials,
+    /// The API token does not have the scope required by the endpoint.
+    InsufficientScope,
+    /// The same download, or an install, update or import of the same tool, is already in progress.
+    DownloadInProgress,
+    /// The request is valid, but the media cannot be processed, for example a file without chapters is split.
+    UnprocessableMedia,
+    /// A tool required by the endpoint, such as ffmpeg, is not installed. It can be installed using the tools endpoints.
+    ToolNotInstalled,
+    /// An upstream API or the server of a download failed or returned an invalid response.
+    UpstreamFailure,
+    /// The request failed because of an unexpected error. The reason is only logged, under the request ID of the response.
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest | ErrorCode::UrlNotAllowed | ErrorCode::DownloadTooLarge => {
+                StatusCode::BAD_REQUEST
+            }
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ErrorCode::AuthenticationRequired | ErrorCode::InvalidCredentials => {
+                StatusCode::UNAUTHORIZED
+            }
+            ErrorCode::InsufficientScope => StatusCode::FORBIDDEN,
+            ErrorCode::FileNotFound | ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::DownloadInProgress => StatusCode::CONFLICT,
+            ErrorCode::UnprocessableMedia => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::ToolNotInstalled => StatusCode::FAILED_DEPENDENCY,
+            ErrorCode::UpstreamFailure => StatusCode::BAD_GATEWAY,
+            ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Returns the code of an error response that was not created by a handler, such as an extractor rejection.
+    fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorCode::UnsupportedMediaType,
+            StatusCode::METHOD_NOT_ALLOWED => ErrorCode::MethodNotAllowed,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            status if status.is_client_error() => ErrorCode::InvalidRequest,
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+/// Error caused by the request rather than by the server, such as invalid request data or a missing tool.
+/// It is returned with the status of its code instead of `500 Internal Server Error`, and with its message as the reason.
+#[derive(Debug)]
+pub struct RequestError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl RequestError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+/// Body of error responses, following the problem details format of RFC 9457.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    /// Short summary of the status, such as `Not Found`.
+    #[schema(example = "Failed Dependency")]
+    title: String,
+    #[schema(example = 424)]
+    status: u16,
+    code: ErrorCode,
+    /// Reason of the error, which can be shown to users.
+    #[schema(example = "ffmpeg is not installed. Install it using the tools endpoints")]
+    detail: String,
+    /// ID of the request, also returned in the `x-request-id` header. It is logged with the request, so it can be used to find the server logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+impl ProblemDetails {
+    fn new(status: StatusCode, code: ErrorCode, detail: String) -> Self {
+        Self {
+            title: status
+                .canonical_reason()
+                .unwrap_or("Unknown Error")
+                .to_string(),
+            status: status.as_u16(),
+            code,
+            detail,
+            request_id: None,
+        }
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (
+            status,
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(PROBLEM_JSON_CONTENT_TYPE),
+            )],
+            Json(self.clone()),
+        )
+            .into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(AUTHENTICATE_CHALLENGE),
+            );
+        }
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+/// Error responses of [`ServerError`], documented for all endpoints that can fail.
+pub struct ErrorResponses;
+
+impl IntoResponses for ErrorResponses {
+    fn responses() -> BTreeMap<String, RefOr<utoipa::openapi::response::Response>> {
+        [
+            (
+                StatusCode::BAD_REQUEST,
+                "The request is invalid, for example a file name outside of the library",
+            ),
+            (
+                StatusCode::UNAUTHORIZED,
+                "Authentication is enabled and the request is not logged in or has an invalid API token",
+            ),
+            (
+                StatusCode::FORBIDDEN,
+                "The API token does not have the scope required by the endpoint",
+            ),
+            (StatusCode::NOT_FOUND, "The file or resource does not exist"),
+            (
+                StatusCode::CONFLICT,
+                "The same download or tool install is already in progress",
+            ),
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The media cannot be processed, or the request body does not match its schema",
+            ),
+            (
+                StatusCode::FAILED_DEPENDENCY,
+                "A tool required by the endpoint is not installed",
+            ),
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "The request failed because of an unexpected error, which is logged under the request ID",
+            ),
+            (
+                StatusCode::BAD_GATEWAY,
+                "An upstream API or download server failed",
+            ),
+        ]
+        .into_iter()
+        .map(|(status, description)| {
+            let response = ResponseBuilder::new()
+                .description(description)
+                .content(
+                    PROBLEM_JSON_CONTENT_TYPE,
+                    ContentBuilder::new()
+                        .schema(Some(ProblemDetails::schema()))
+                        .build(),
+                )
+                .build();
+            (status.as_str().to_string(), RefOr::T(response))
+        })
+        .collect()
+    }
+}
+
 impl<E> From<E> for ServerError
 where
     E: Into<anyhow::Error>,
@@ -12,13 +248,49 @@ where
         Self(err.into())
     }
 }
+
+impl ServerError {
+    /// Maps the error to its code and message. Request errors keep their own message and upstream failures are described by their whole context chain.
+    /// Internal errors get a generic message, as their context chain can contain paths and other server details.
+    fn to_problem_details(&self) -> ProblemDetails {
+        if let Some(request_error) = self
+            .0
+            .chain()
+            .find_map(|err| err.downcast_ref::<RequestError>())
+        {
+            return ProblemDetails::new(
+                request_error.code.status(),
+                request_error.code,
+                request_error.message.clone(),
+            );
+        }
+
+        if self
+            .0
+            .chain()
+            .any(|err| err.downcast_ref::<reqwest::Error>().is_some())
+        {
+            let code = ErrorCode::UpstreamFailure;
+            return ProblemDetails::new(code.status(), code, format!("{:#}", self.0));
+        }
+
+        let code = ErrorCode::InternalError;
+        ProblemDetails::new(code.status(), code, INTERNAL_ERROR_DETAIL.to_string())
+    }
+}
+
 impl IntoResponse for ServerError {
-    fn into_response(self) -> axum::response::Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {:#}\n", self.0),
-        )
-            .into_response()
+    fn into_response(self) -> Response {
+        let problem_details = self.to_problem_details();
+        let is_internal_error = problem_details.code == ErrorCode::InternalError;
+
+        let mut response = problem_details.into_response();
+        if is_internal_error {
+            response
+                .extensions_mut()
+                .insert(InternalErrorChain(format!("{:#}", self.0)));
+        }
+        response
     }
 }
 
@@ -27,3 +299,190 @@ impl Display for ServerError {
         write!(f, "{:#}", self.0)
     }
 }
+
+/// Adds the request ID to error responses of the API and turns errors created outside of handlers,
+/// such as rejected request bodies or unsupported methods, into problem details as well.
+pub async fn render_problem_details(request: Request, next: Next) -> Response {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|request_id| request_id.header_value().to_str().ok())
+        .map(str::to_string);
+
+    let response = next.run(request).await;
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+
+    if let Some(InternalErrorChain(error_chain)) = response.extensions().get::<InternalErrorChain>()
+    {
+        error!(
+            request_id = request_id.as_deref().unwrap_or_default(),
+            "Request failed: {}", error_chain
+        );
+    }
+
+    let mut problem_details = match response.extensions().get::<ProblemDetails>() {
+        Some(problem_details) => problem_details.clone(),
+        None if is_plain_text_response(&response) => {
+            let body = match to_bytes(response.into_body(), MAX_ERROR_BODY_BYTES).await {
+                Ok(body) => String::from_utf8_lossy(&body).trim().to_string(),
+                Err(err) => {
+                    warn!("Failed to read body of error response: {}", err);
+                    String::new()
+                }
+            };
+            let detail = if body.is_empty() {
+                status
+                    .canonical_reason()
+                    .unwrap_or("Unknown Error")
+                    .to_string()
+            } else {
+                body
+            };
+            ProblemDetails::new(status, ErrorCode::from_status(status), detail)
+        }
+        // Endpoints that return a typed body with an error status, such as failed commands, are left as they are
+        None => return response,
+    };
+
+    problem_details.request_id = request_id;
+    problem_details.into_response()
+}
+
+fn is_plain_text_response(response: &Response<Body>) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_none_or(|content_type| content_type.starts_with("text/plain"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_ERROR_CODES: [ErrorCode; 16] = [
+        ErrorCode::InvalidRequest,
+        ErrorCode::UrlNotAllowed,
+        ErrorCode::DownloadTooLarge,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::UnsupportedMediaType,
+        ErrorCode::MethodNotAllowed,
+        ErrorCode::FileNotFound,
+        ErrorCode::NotFound,
+        ErrorCode::AuthenticationRequired,
+        ErrorCode::InvalidCredentials,
+        ErrorCode::InsufficientScope,
+        ErrorCode::DownloadInProgress,
+        ErrorCode::UnprocessableMedia,
+        ErrorCode::ToolNotInstalled,
+        ErrorCode::UpstreamFailure,
+        ErrorCode::InternalError,
+    ];
+
+    #[test]
+    fn maps_error_codes_to_statuses() {
+        assert_eq!(ErrorCode::UrlNotAllowed.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            ErrorCode::InvalidCredentials.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(ErrorCode::InsufficientScope.status(), StatusCode::FORBIDDEN);
+        assert_eq!(ErrorCode::FileNotFound.status(), StatusCode::NOT_FOUND);
+        assert_eq!(ErrorCode::DownloadInProgress.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            ErrorCode::ToolNotInstalled.status(),
+            StatusCode::FAILED_DEPENDENCY
+        );
+        assert_eq!(ErrorCode::UpstreamFailure.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(
+            ErrorCode::InternalError.status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        for code in ALL_ERROR_CODES {
+            let status = code.status();
+            assert!(
+                status.is_client_error() || status.is_server_error(),
+                "{:?} has a non-error status",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn documents_the_status_of_every_error_code() {
+        let responses = ErrorResponses::responses();
+
+        for code in ALL_ERROR_CODES {
+            let status = code.status();
+            // Statuses produced outside of handlers are not part of every endpoint
+            if matches!(
+                code,
+                ErrorCode::PayloadTooLarge
+                    | ErrorCode::UnsupportedMediaType
+                    | ErrorCode::MethodNotAllowed
+            ) {
+                continue;
+            }
+            assert!(
+                responses.contains_key(status.as_str()),
+                "{} of {:?} is not documented",
+                status,
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn maps_statuses_of_other_responses_to_error_codes() {
+        for code in [
+            ErrorCode::PayloadTooLarge,
+            ErrorCode::UnsupportedMediaType,
+            ErrorCode::MethodNotAllowed,
+            ErrorCode::NotFound,
+            ErrorCode::InternalError,
+        ] {
+            assert_eq!(ErrorCode::from_status(code.status()), code);
+        }
+        assert_eq!(
+            ErrorCode::from_status(StatusCode::IM_A_TEAPOT),
+            ErrorCode::InvalidRequest
+        );
+    }
+
+    #[test]
+    fn keeps_the_message_of_request_errors_in_the_context_chain() {
+        let err = anyhow::Error::new(RequestError::new(
+            ErrorCode::ToolNotInstalled,
+            "ffmpeg is not installed",
+        ))
+        .context("Failed to convert audio");
+
+        let problem_details = ServerError::from(err).to_problem_details();
+
+        assert_eq!(problem_details.code, ErrorCode::ToolNotInstalled);
+        assert_eq!(problem_details.status, 424);
+        assert_eq!(problem_details.detail, "ffmpeg is not installed");
+    }
+
+    #[test]
+    fn hides_the_reason_of_internal_errors() {
+        let err = anyhow::anyhow!("Failed to read /srv/secret/file").context("Failed to list");
+
+        let response = ServerError::from(err).into_response();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let problem_details = response.extensions().get::<ProblemDetails>().unwrap();
+        assert_eq!(problem_details.detail, INTERNAL_ERROR_DETAIL);
+        assert_eq!(
+            response
+                .extensions()
+                .get::<InternalErrorChain>()
+                .map(|InternalErrorChain(error_chain)| error_chain.as_str()),
+            Some("Failed to list: Failed to read /srv/secret/file")
+        );
+    }
+}
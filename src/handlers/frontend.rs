@@ -0,0 +1,121 @@
+use axum::http::{header, HeaderValue, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use tracing::{debug, instrument};
+
+include!(concat!(env!("OUT_DIR"), "/frontend_assets.rs"));
+
+/// Page served for client side routes that have no exported page.
+const INDEX_PAGE: &str = "index.html";
+/// Assets under this path have content hashes in their names, so they never change and can be cached forever.
+const HASHED_ASSETS_PREFIX: &str = "_next/static/";
+
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+/// Pages and other assets keep their names between builds, so browsers revalidate them.
+const REVALIDATED_CACHE_CONTROL: &str = "no-cache";
+
+/// Content Security Policy of the UI. Next.js inlines its bootstrap scripts and styles in static exports, so inline scripts and styles are allowed.
+/// Audio is played from the API on the same origin, which Howler may load into blobs.
+pub const FRONTEND_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; script-src 'self' 'unsafe-inline'; style-src 'self' 'unsafe-inline'; img-src 'self' data: blob:; font-src 'self' data:; media-src 'self' blob:; connect-src 'self'; object-src 'none'; base-uri 'self'; form-action 'self'; frame-ancestors 'none'";
+
+/// Serves the embedded frontend. Paths are resolved to exported files and pages, for example `/downloader` to `downloader.html`.
+/// Unknown paths without a file extension fall back to the index page, so client side routes work when opened directly.
+#[instrument(ret(level = "debug"))]
+pub async fn handle_frontend(uri: Uri) -> Response {
+    debug!("Handling frontend request");
+
+    let path = uri.path().trim_matches('/');
+
+    let asset = get_asset_candidates(path).iter().find_map(|candidate| {
+        get_frontend_asset(candidate).map(|content| (candidate.clone(), content))
+    });
+
+    let (asset_path, content) =
+        match asset {
+            Some(asset) => asset,
+            None if !is_asset_path(path) => match get_frontend_asset(INDEX_PAGE) {
+                Some(content) => (INDEX_PAGE.to_string(), content),
+                None => return (
+                    StatusCode::NOT_FOUND,
+                    "The UI is not included in this build. The API is available under /api/v1\n",
+                )
+                    .into_response(),
+            },
+            None => return (StatusCode::NOT_FOUND, "Not found\n").into_response(),
+        };
+
+    let cache_control = if asset_path.starts_with(HASHED_ASSETS_PREFIX) {
+        IMMUTABLE_CACHE_CONTROL
+    } else {
+        REVALIDATED_CACHE_CONTROL
+    };
+
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(get_content_type(&asset_path)),
+            ),
+            (
+                header::CACHE_CONTROL,
+                HeaderValue::from_static(cache_control),
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+/// Returns the files a request path can refer to in a Next.js static export, in the order they are tried.
+fn get_asset_candidates(path: &str) -> Vec<String> {
+    if path.is_empty() {
+        return vec![INDEX_PAGE.to_string()];
+    }
+
+    vec![
+        path.to_string(),
+        format!("{}.html", path),
+        format!("{}/{}", path, INDEX_PAGE),
+    ]
+}
+
+fn get_frontend_asset(asset_path: &str) -> Option<&'static [u8]> {
+    FRONTEND_ASSETS
+        .iter()
+        .find(|(path, _)| *path == asset_path)
+        .map(|(_, content)| *content)
+}
+
+/// Returns whether the path refers to a file rather than a page, so missing files are not answered with the index page.
+fn is_asset_path(path: &str) -> bool {
+    path.starts_with("_next/")
+        || path
+            .rsplit('/')
+            .next()
+            .is_some_and(|file_name| file_name.contains('.'))
+}
+
+fn get_content_type(asset_path: &str) -> &'static str {
+    let extension = asset_path
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "html" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "webmanifest" => "application/manifest+json",
+        _ => "application/octet-stream",
+    }
+}
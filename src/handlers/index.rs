@@ -1,13 +1,25 @@
+use crate::handlers::errors::{ErrorCode, RequestError, ServerError};
+use axum::extract::OriginalUri;
 use axum::http::StatusCode;
 use axum::Json;
 use serde::Serialize;
 use tracing::instrument;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct HelloResponse {
     message: &'static str,
 }
 
+#[utoipa::path(
+    get,
+    path = "/",
+    operation_id = "api_hello",
+    tag = "server",
+    responses(
+        (status = 200, description = "The API is running", body = HelloResponse)
+    )
+)]
 #[instrument(ret(level = "debug"))]
 pub async fn handle_api_hello() -> (StatusCode, Json<HelloResponse>) {
     let response = HelloResponse {
@@ -16,3 +28,34 @@ pub async fn handle_api_hello() -> (StatusCode, Json<HelloResponse>) {
 
     (StatusCode::OK, Json(response))
 }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthResponse {
+    status: &'static str,
+}
+
+/// Health check for load balancers and container orchestrators. It never requires authentication.
+#[utoipa::path(
+    get,
+    path = "/health",
+    operation_id = "health",
+    tag = "server",
+    security(()),
+    responses(
+        (status = 200, description = "The server is running", body = HealthResponse)
+    )
+)]
+#[instrument(ret(level = "debug"))]
+pub async fn handle_health() -> (StatusCode, Json<HealthResponse>) {
+    (StatusCode::OK, Json(HealthResponse { status: "ok" }))
+}
+
+/// Answers requests to unknown API paths, so they are not served the frontend.
+#[instrument]
+pub async fn handle_api_not_found(OriginalUri(uri): OriginalUri) -> ServerError {
+    RequestError::new(
+        ErrorCode::NotFound,
+        format!("The API has no endpoint at {}", uri.path()),
+    )
+    .into()
+}
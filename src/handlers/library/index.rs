@@ -0,0 +1,31 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::library::read_library_index;
+use crate::handlers::shared::model::library::LibraryIndex;
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use std::path::Path;
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    get,
+    path = "/library/index",
+    operation_id = "library_index",
+    tag = "library",
+    responses(
+        (status = 200, description = "Index of downloaded library files", body = LibraryIndex),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_library_index(
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<LibraryIndex>), ServerError> {
+    debug!("Handling reading of library index");
+
+    let library_index =
+        read_library_index(Path::new(&app_state.config().library_settings.dir)).await?;
+
+    Ok((StatusCode::OK, Json(library_index)))
+}
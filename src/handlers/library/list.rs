@@ -1,4 +1,5 @@
-use crate::handlers::errors::ServerError;
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::files::to_library_file_name;
 use crate::AppState;
 use anyhow::Context;
 use axum::extract::State;
@@ -8,19 +9,69 @@ use serde::Serialize;
 use std::path::Path;
 use tokio::fs::read_dir;
 use tracing::{debug, error, instrument};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct LibraryListResponse {
     pub library_dir: String,
     pub files: Vec<String>,
 }
+#[utoipa::path(
+    get,
+    path = "/library/list",
+    operation_id = "list_library_files",
+    tag = "library",
+    responses(
+        (status = 200, description = "Audio files in the library", body = LibraryListResponse),
+        (status = 400, description = "The library directory cannot be read", body = LibraryListResponse),
+        ErrorResponses
+    )
+)]
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn handle_list_library_files(
     State(app_state): State<AppState>,
 ) -> Result<(StatusCode, Json<LibraryListResponse>), ServerError> {
     debug!("Handling listing library contents");
 
-    let library_dir = Path::new(&app_state.config.library_settings.dir);
+    // Videos are stored in a subdirectory of the library and listed separately
+    list_library_dir(
+        Path::new(&app_state.config().library_settings.dir),
+        Some(Path::new(&app_state.config().library_settings.videos_dir)),
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/library/videos/list",
+    operation_id = "list_library_videos",
+    tag = "library",
+    responses(
+        (status = 200, description = "Video files in the videos library", body = LibraryListResponse),
+        (status = 400, description = "The videos library directory cannot be read", body = LibraryListResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_list_library_videos(
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<LibraryListResponse>), ServerError> {
+    debug!("Handling listing videos library contents");
+
+    list_library_dir(
+        Path::new(&app_state.config().library_settings.videos_dir),
+        None,
+    )
+    .await
+}
+
+/// Lists files in the library directory and its subdirectories, except for `skipped_dir`.
+/// File names are relative to the library directory and use `/` as the separator.
+#[instrument(err, ret(level = "debug"))]
+async fn list_library_dir(
+    library_dir: &Path,
+    skipped_dir: Option<&Path>,
+) -> Result<(StatusCode, Json<LibraryListResponse>), ServerError> {
     if !library_dir.exists() {
         error!(
             "Cannot list library, directory does not exist: {}",
@@ -35,20 +86,40 @@ pub async fn handle_list_library_files(
         ));
     }
 
-    // TODO: For now flat structure is assumed, Change this to support nested directories
+    // Hidden files and directories such as the library index are skipped
     let mut library_files: Vec<String> = Vec::new();
-    let mut entries = read_dir(library_dir)
-        .await
-        .context("Failed to read directory entries")?;
-
-    while let Some(entry) = entries
-        .next_entry()
-        .await
-        .context("Failed to read next entry")?
-    {
-        let file_name = entry.file_name().to_string_lossy().to_string();
-        library_files.push(file_name);
+    let mut pending_dirs = vec![library_dir.to_path_buf()];
+    while let Some(dir) = pending_dirs.pop() {
+        let mut entries = read_dir(&dir)
+            .await
+            .context("Failed to read directory entries")?;
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .context("Failed to read next entry")?
+        {
+            let file_type = entry
+                .file_type()
+                .await
+                .context("Failed to get file type of directory entry")?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            let entry_path = entry.path();
+            if file_type.is_dir() {
+                if skipped_dir != Some(entry_path.as_path()) {
+                    pending_dirs.push(entry_path);
+                }
+                continue;
+            }
+
+            library_files.push(to_library_file_name(library_dir, &entry_path)?);
+        }
     }
+    library_files.sort();
 
     Ok((
         StatusCode::OK,
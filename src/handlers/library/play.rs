@@ -1,15 +1,30 @@
-use crate::handlers::errors::ServerError;
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::files::{check_file_exists, get_library_file_path};
 use crate::AppState;
 use anyhow::Context;
-use axum::extract::State;
+use axum::extract::{Request, State};
 use axum::http::header::CONTENT_TYPE;
-use axum::http::StatusCode;
-use axum::response::Response;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::{body::Body, extract::Path};
-use tokio::fs::{metadata, File};
+use tokio::fs::File;
 use tokio_util::io::ReaderStream;
+use tower_http::services::ServeFile;
 use tracing::{debug, instrument};
 
+#[utoipa::path(
+    get,
+    path = "/library/play/{library_file_name}",
+    operation_id = "play_audio",
+    tag = "library",
+    params(
+        ("library_file_name" = String, Path, description = "Name of the audio file relative to the library directory")
+    ),
+    responses(
+        (status = 200, description = "The audio file", content_type = "application/octet-stream"),
+        ErrorResponses
+    )
+)]
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn handle_play_audio(
     Path(library_file_name): Path<String>,
@@ -17,12 +32,11 @@ pub async fn handle_play_audio(
 ) -> Result<(StatusCode, Response<Body>), ServerError> {
     debug!("Handling playing of music track");
 
-    let library_dir = std::path::Path::new(&app_state.config.library_settings.dir);
-    let audio_file_path = library_dir.join(&library_file_name);
+    let config = app_state.config();
+    let library_dir = std::path::Path::new(&config.library_settings.dir);
+    let audio_file_path = get_library_file_path(library_dir, &library_file_name)?;
 
-    metadata(&audio_file_path)
-        .await
-        .context("Failed to get metadata of the audio file. Most likely the path is wrong or the file does not exist")?;
+    check_file_exists(&audio_file_path).await?;
 
     let file = File::open(&audio_file_path).await.context(format!(
         "Failed to open the audio file: {}",
@@ -42,6 +56,10 @@ pub async fn handle_play_audio(
         Some("mp3") => "audio/mpeg",
         Some("ogg") => "audio/ogg",
         Some("opus") => "audio/opus",
+        Some("m4a") => "audio/mp4",
+        Some("flac") => "audio/flac",
+        Some("wav") => "audio/wav",
+        Some("webm") => "audio/webm",
         _ => "application/octet-stream", // fallback
     };
 
@@ -53,3 +71,57 @@ pub async fn handle_play_audio(
 
     Ok((StatusCode::OK, response))
 }
+
+/// Streams a video from the videos library. Range requests are supported so players can seek.
+#[utoipa::path(
+    get,
+    path = "/library/videos/play/{library_file_name}",
+    operation_id = "play_video",
+    tag = "library",
+    params(
+        ("library_file_name" = String, Path, description = "Name of the video file relative to the videos library directory")
+    ),
+    responses(
+        (status = 200, description = "The video file", content_type = "application/octet-stream"),
+        (status = 206, description = "The requested range of the video file", content_type = "application/octet-stream"),
+        ErrorResponses
+    )
+)]
+#[instrument(err, skip(app_state, request))]
+pub async fn handle_play_video(
+    Path(library_file_name): Path<String>,
+    State(app_state): State<AppState>,
+    request: Request,
+) -> Result<Response, ServerError> {
+    debug!("Handling playing of video");
+
+    let config = app_state.config();
+    let videos_library_dir = std::path::Path::new(&config.library_settings.videos_dir);
+    let video_file_path = get_library_file_path(videos_library_dir, &library_file_name)?;
+
+    check_file_exists(&video_file_path).await?;
+
+    let content_type = match video_file_path
+        .extension()
+        .context("Failed to get video file extension")?
+        .to_str()
+    {
+        Some("mp4") => "video/mp4",
+        Some("mkv") => "video/x-matroska",
+        Some("webm") => "video/webm",
+        Some("vtt") => "text/vtt",
+        Some("srt") => "application/x-subrip",
+        _ => "application/octet-stream", // fallback
+    };
+
+    let mut response = ServeFile::new(&video_file_path)
+        .try_call(request)
+        .await
+        .context("Failed to serve the video file")?
+        .into_response();
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+
+    Ok(response)
+}
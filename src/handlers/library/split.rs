@@ -0,0 +1,128 @@
+use crate::handlers::errors::{ErrorCode, ErrorResponses, RequestError, ServerError};
+use crate::handlers::shared::functions::files::{check_file_exists, get_library_file_path};
+use crate::handlers::shared::functions::library::{add_library_index_entries, read_library_index};
+use crate::handlers::shared::functions::split::{
+    chapters_to_track_segments, parse_cue_sheet, probe_embedded_chapters, split_audio_file,
+};
+use crate::handlers::shared::model::library::LibraryIndexEntry;
+use crate::handlers::shared::model::split::{LibrarySplitResponse, SplitSource};
+use crate::AppState;
+use anyhow::Context;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use std::path::Path;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SplitLibraryFileRequest {
+    library_file_name: String,
+    /// Contents of a cue sheet. When not set, the chapters of the file are used.
+    cue_sheet: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/library/split",
+    operation_id = "split_library_file",
+    tag = "library",
+    request_body = SplitLibraryFileRequest,
+    responses(
+        (status = 200, description = "File split into tracks", body = LibrarySplitResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_split_library_file(
+    State(app_state): State<AppState>,
+    Json(payload): Json<SplitLibraryFileRequest>,
+) -> Result<(StatusCode, Json<LibrarySplitResponse>), ServerError> {
+    debug!("Handling splitting of library file");
+
+    let config = app_state.config();
+    let library_dir = Path::new(&config.library_settings.dir);
+    let audio_file_path = get_library_file_path(library_dir, &payload.library_file_name)?;
+    check_file_exists(&audio_file_path).await?;
+
+    let library_index = read_library_index(library_dir).await?;
+    let source_entry = library_index
+        .entries
+        .get(&payload.library_file_name)
+        .cloned();
+
+    let (source, segments) = match &payload.cue_sheet {
+        Some(cue_sheet) => (
+            SplitSource::CueSheet,
+            parse_cue_sheet(cue_sheet).context("Failed to parse cue sheet")?,
+        ),
+        None => {
+            let indexed_chapters = source_entry
+                .as_ref()
+                .map(|entry| chapters_to_track_segments(&entry.chapters))
+                .unwrap_or_default();
+            let segments = if indexed_chapters.is_empty() {
+                info!("No chapters in the library index, reading chapters embedded in the file");
+                probe_embedded_chapters(&app_state, &audio_file_path).await?
+            } else {
+                indexed_chapters
+            };
+            (SplitSource::Chapters, segments)
+        }
+    };
+
+    if segments.is_empty() {
+        return Err(RequestError::new(
+            ErrorCode::UnprocessableMedia,
+            "The file has no chapters, supply a cue sheet to split it",
+        )
+        .into());
+    }
+
+    info!(
+        "Splitting {} into {} tracks",
+        payload.library_file_name,
+        segments.len()
+    );
+
+    let (tracks, command_execution_results) =
+        split_audio_file(&app_state, &audio_file_path, &segments, library_dir).await?;
+
+    let index_entries = tracks
+        .iter()
+        .map(|track| LibraryIndexEntry {
+            file_name: track.file_name.clone(),
+            download_id: source_entry
+                .as_ref()
+                .map(|entry| entry.download_id.clone())
+                .unwrap_or_default(),
+            extractor: None,
+            media_id: None,
+            title: Some(track.title.clone()),
+            uploader: source_entry
+                .as_ref()
+                .and_then(|entry| entry.uploader.clone()),
+            upload_date: source_entry
+                .as_ref()
+                .and_then(|entry| entry.upload_date.clone()),
+            source_url: source_entry
+                .as_ref()
+                .and_then(|entry| entry.source_url.clone()),
+            duration: track.end_time.map(|end_time| end_time - track.start_time),
+            chapters: Vec::new(),
+            identification: None,
+        })
+        .collect();
+    add_library_index_entries(&app_state, library_dir, index_entries).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(LibrarySplitResponse {
+            library_file_name: payload.library_file_name,
+            source,
+            tracks,
+            command_execution_results,
+        }),
+    ))
+}
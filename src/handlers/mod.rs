@@ -1,9 +1,22 @@
+pub mod admin {
+    pub mod reload;
+}
+
+pub mod auth {
+    pub mod login;
+    pub mod logout;
+    pub mod session;
+}
+
 pub mod convert {
     pub mod audio;
 }
 
 pub mod download {
     pub mod audio;
+    pub mod playlist;
+    pub mod search;
+    pub mod video;
 }
 
 pub mod identify {
@@ -12,34 +25,74 @@ pub mod identify {
 
 pub mod shared {
     pub mod functions {
+        pub mod auth;
         pub mod commands;
+        pub mod convert;
+        pub mod direct;
+        pub mod doctor;
+        pub mod downloads;
         pub mod files;
+        pub mod identify;
+        pub mod install;
+        pub mod library;
+        pub mod naming;
+        pub mod playlists;
+        pub mod postprocessing;
+        pub mod reload;
+        pub mod schedule;
+        pub mod split;
+        pub mod subscriptions;
         pub mod tools;
+        pub mod urls;
+        pub mod yt_dlp;
     }
 
     pub mod model {
         pub mod acoustid;
+        pub mod auth;
         pub mod commands;
+        pub mod direct;
+        pub mod doctor;
+        pub mod downloads;
+        pub mod identify;
+        pub mod library;
         pub mod media;
         pub mod musicbrainz;
+        pub mod naming;
+        pub mod postprocessing;
+        pub mod reload;
+        pub mod search;
+        pub mod split;
+        pub mod subscriptions;
         pub mod tools;
+        pub mod yt_dlp;
     }
 }
 
 pub mod library {
+    pub mod index;
     pub mod list;
     pub mod play;
+    pub mod split;
+}
+
+pub mod subscriptions {
+    pub mod create;
+    pub mod delete;
+    pub mod list;
 }
 
 pub mod tools {
     pub mod chromaprint {
         pub mod download;
         pub mod status;
+        pub mod update;
     }
 
     pub mod ffmpeg {
         pub mod download;
         pub mod status;
+        pub mod update;
     }
 
     pub mod yt_dlp {
@@ -47,7 +100,14 @@ pub mod tools {
         pub mod status;
         pub mod update;
     }
+
+    pub mod import;
+    pub mod progress;
+    pub mod rollback;
 }
 
+pub mod doctor;
 pub mod errors;
+pub mod frontend;
 pub mod index;
+pub mod openapi;
@@ -0,0 +1,148 @@
+use crate::handlers::shared::functions::auth::SESSION_COOKIE_NAME;
+use crate::handlers::{
+    admin, auth, convert, doctor, download, identify, index, library, subscriptions, tools,
+};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::IntoResponse;
+use axum::Json;
+use once_cell::sync::Lazy;
+use tracing::{debug, instrument};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// Content Security Policy of the docs page, which loads its script and styles from the API and calls the API.
+const DOCS_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; script-src 'self'; style-src 'self'; connect-src 'self'; img-src 'self' data:; base-uri 'none'; form-action 'none'; frame-ancestors 'none'";
+
+const DOCS_PAGE: &str = include_str!("../../assets/api-docs/index.html");
+const DOCS_SCRIPT: &str = include_str!("../../assets/api-docs/docs.js");
+const DOCS_STYLESHEET: &str = include_str!("../../assets/api-docs/docs.css");
+
+/// OpenAPI document of the API. Schemas are generated from the request and response types of the listed handlers.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Ferrous Beats API",
+        description = "API of the Ferrous Beats music downloader and player. Endpoints that fail return problem details (RFC 9457) with a stable error code, a reason and the request ID."
+    ),
+    servers((url = "/api/v1", description = "This server")),
+    modifiers(&SecuritySchemes),
+    security(("api_token" = []), ("session_cookie" = [])),
+    paths(
+        index::handle_api_hello,
+        index::handle_health,
+        doctor::handle_doctor,
+        auth::login::handle_login,
+        auth::logout::handle_logout,
+        auth::session::handle_auth_session,
+        admin::reload::handle_config_reload,
+        library::list::handle_list_library_files,
+        library::index::handle_library_index,
+        library::split::handle_split_library_file,
+        library::play::handle_play_audio,
+        library::list::handle_list_library_videos,
+        library::play::handle_play_video,
+        download::audio::handle_audio_download,
+        download::playlist::handle_playlist_download,
+        download::search::handle_media_search,
+        download::video::handle_video_download,
+        download::playlist::handle_playlist_download_status,
+        identify::audio::handle_audio_identification,
+        convert::audio::handle_audio_conversion,
+        subscriptions::list::handle_list_subscriptions,
+        subscriptions::create::handle_create_subscription,
+        subscriptions::delete::handle_delete_subscription,
+        tools::yt_dlp::download::handle_yt_dlp_download,
+        tools::yt_dlp::status::handle_yt_dlp_status,
+        tools::yt_dlp::update::handle_yt_dlp_update,
+        tools::ffmpeg::download::handle_ffmpeg_download,
+        tools::ffmpeg::status::handle_ffmpeg_status,
+        tools::ffmpeg::update::handle_ffmpeg_update,
+        tools::chromaprint::download::handle_chromaprint_download,
+        tools::chromaprint::status::handle_chromaprint_fpcalc_status,
+        tools::chromaprint::update::handle_chromaprint_update,
+        tools::progress::handle_tool_download_progress,
+        tools::rollback::handle_tool_rollback,
+        tools::import::handle_tool_import,
+    ),
+    tags(
+        (name = "server", description = "Server status and health checks"),
+        (name = "auth", description = "Login of users and checking who a request is authenticated as"),
+        (name = "admin", description = "Server administration"),
+        (name = "library", description = "Files in the library"),
+        (name = "download", description = "Downloads of audio, video and playlists"),
+        (name = "identify", description = "Identification of audio files using AcoustID and MusicBrainz"),
+        (name = "convert", description = "Conversion of audio files using ffmpeg"),
+        (name = "subscriptions", description = "Channels and playlists synced on a schedule"),
+        (name = "tools", description = "Installation and updates of yt-dlp, ffmpeg and chromaprint"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Ways to authenticate requests when authentication is enabled. Without it, requests need no credentials.
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "API token created with `ferrous-beats admin create-token`. Tokens can only be used for the endpoints of their scopes",
+                    ))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "session_cookie",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                SESSION_COOKIE_NAME,
+                "Session cookie set by the login endpoint",
+            ))),
+        );
+    }
+}
+
+/// The document never changes while the server runs, so it is generated once.
+static OPENAPI_DOCUMENT: Lazy<utoipa::openapi::OpenApi> = Lazy::new(ApiDoc::openapi);
+
+#[instrument]
+pub async fn handle_openapi_document() -> (StatusCode, Json<&'static utoipa::openapi::OpenApi>) {
+    debug!("Handling OpenAPI document");
+
+    (StatusCode::OK, Json(&OPENAPI_DOCUMENT))
+}
+
+/// Interactive docs page that lists the operations of the OpenAPI document and sends requests to them.
+#[instrument]
+pub async fn handle_docs_page() -> impl IntoResponse {
+    debug!("Handling API docs page");
+
+    docs_response("text/html; charset=utf-8", DOCS_PAGE)
+}
+
+#[instrument]
+pub async fn handle_docs_script() -> impl IntoResponse {
+    docs_response("text/javascript; charset=utf-8", DOCS_SCRIPT)
+}
+
+#[instrument]
+pub async fn handle_docs_stylesheet() -> impl IntoResponse {
+    docs_response("text/css; charset=utf-8", DOCS_STYLESHEET)
+}
+
+fn docs_response(content_type: &'static str, content: &'static str) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                HeaderValue::from_static(DOCS_CONTENT_SECURITY_POLICY),
+            ),
+        ],
+        content,
+    )
+}
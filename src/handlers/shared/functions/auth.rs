@@ -0,0 +1,527 @@
+use crate::handlers::errors::{ErrorCode, RequestError, ServerError};
+use crate::handlers::shared::functions::reload::get_file_state;
+use crate::handlers::shared::model::auth::{
+    AuthStore, Principal, Session, SharedAuthStore, StoredToken, StoredUser, TokenScope,
+};
+use crate::AppState;
+use anyhow::Context;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::extract::{Request, State};
+use axum::http::header::{AUTHORIZATION, COOKIE};
+use axum::http::{HeaderMap, HeaderValue, Method};
+use axum::middleware::Next;
+use axum::response::Response;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use time::{Duration, OffsetDateTime};
+use tokio::fs::{create_dir_all, read_to_string, rename, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::task::spawn_blocking;
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+pub const AUTH_STORE_FILE_NAME: &str = "auth.json";
+pub const SESSION_COOKIE_NAME: &str = "ferrous_beats_session";
+/// API tokens start with this prefix, so they are easy to recognize, for example by secret scanners.
+pub const API_TOKEN_PREFIX: &str = "fbt_";
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+const SESSION_LIFETIME: Duration = Duration::days(7);
+/// Number of random bytes in session IDs and API tokens.
+const SECRET_BYTES: usize = 32;
+/// API paths that can be used without authentication. The frontend is served outside of the API and is always public, as it contains no data.
+const PUBLIC_API_PATHS: &[&str] = &["/health", "/auth/login", "/auth/logout"];
+/// How often the users and API tokens file is checked for changes.
+const AUTH_STORE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Reads the users and API tokens stored in the data directory. Returns an empty store if there are none yet.
+#[instrument(err, skip_all)]
+pub async fn read_auth_store(data_dir: &Path) -> Result<AuthStore, anyhow::Error> {
+    let auth_store_path = data_dir.join(AUTH_STORE_FILE_NAME);
+    if !auth_store_path.exists() {
+        return Ok(AuthStore::default());
+    }
+
+    let auth_store_contents = read_to_string(&auth_store_path)
+        .await
+        .context("Failed to read users and API tokens")?;
+
+    serde_json::from_str(&auth_store_contents)
+        .context("Failed to parse users and API tokens as JSON")
+}
+
+/// Writes the store to a temporary file that is only readable by the current user and replaces the store with it.
+#[instrument(err, skip_all)]
+async fn write_auth_store(data_dir: &Path, auth_store: &AuthStore) -> Result<(), anyhow::Error> {
+    create_dir_all(data_dir)
+        .await
+        .context("Failed to create data directory")?;
+
+    let auth_store_path = data_dir.join(AUTH_STORE_FILE_NAME);
+    let mut temp_auth_store_path = auth_store_path.as_os_str().to_owned();
+    temp_auth_store_path.push(".tmp");
+    let temp_auth_store_path = PathBuf::from(temp_auth_store_path);
+
+    let auth_store_contents = serde_json::to_string_pretty(auth_store)
+        .context("Failed to serialize users and API tokens")?;
+
+    let mut open_options = OpenOptions::new();
+    open_options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    open_options.mode(0o600);
+    let mut temp_auth_store_file = open_options
+        .open(&temp_auth_store_path)
+        .await
+        .context("Failed to create temporary users and API tokens file")?;
+    temp_auth_store_file
+        .write_all(auth_store_contents.as_bytes())
+        .await
+        .context("Failed to write temporary users and API tokens file")?;
+    temp_auth_store_file
+        .sync_all()
+        .await
+        .context("Failed to write temporary users and API tokens file")?;
+
+    rename(&temp_auth_store_path, &auth_store_path)
+        .await
+        .context("Failed to replace users and API tokens file")?;
+
+    Ok(())
+}
+
+/// Adds a user who can log in with the password.
+#[instrument(err, skip(shared_auth_store, password))]
+pub async fn create_user(
+    data_dir: &Path,
+    shared_auth_store: &SharedAuthStore,
+    username: &str,
+    password: String,
+) -> Result<(), anyhow::Error> {
+    if username.is_empty()
+        || username
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control())
+    {
+        anyhow::bail!("Username must not be empty or contain whitespace");
+    }
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        anyhow::bail!(
+            "Password must be at least {} characters long",
+            MIN_PASSWORD_LENGTH
+        );
+    }
+
+    let mut auth_store = shared_auth_store.write().await;
+    if auth_store
+        .users
+        .iter()
+        .any(|user| user.username == username)
+    {
+        anyhow::bail!("User {} already exists", username);
+    }
+
+    let password_hash = spawn_blocking(move || hash_password(&password))
+        .await
+        .context("Password hashing task failed")??;
+    let mut updated_auth_store = auth_store.clone();
+    updated_auth_store.users.push(StoredUser {
+        username: username.to_string(),
+        password_hash,
+        created_at: OffsetDateTime::now_utc(),
+    });
+
+    write_auth_store(data_dir, &updated_auth_store).await?;
+    *auth_store = updated_auth_store;
+
+    Ok(())
+}
+
+/// Removes the user. Returns `false` if there was no such user. Sessions of the user stop working immediately.
+#[instrument(err, skip(shared_auth_store))]
+pub async fn delete_user(
+    data_dir: &Path,
+    shared_auth_store: &SharedAuthStore,
+    username: &str,
+) -> Result<bool, anyhow::Error> {
+    let mut auth_store = shared_auth_store.write().await;
+    let mut updated_auth_store = auth_store.clone();
+    updated_auth_store
+        .users
+        .retain(|user| user.username != username);
+    if updated_auth_store.users.len() == auth_store.users.len() {
+        return Ok(false);
+    }
+
+    write_auth_store(data_dir, &updated_auth_store).await?;
+    *auth_store = updated_auth_store;
+
+    Ok(true)
+}
+
+/// Creates an API token with the scopes. Returns the stored token and the token itself, which is not stored and cannot be shown again.
+#[instrument(err, skip(shared_auth_store))]
+pub async fn create_token(
+    data_dir: &Path,
+    shared_auth_store: &SharedAuthStore,
+    name: &str,
+    scopes: Vec<TokenScope>,
+) -> Result<(StoredToken, String), anyhow::Error> {
+    if scopes.is_empty() {
+        anyhow::bail!("API token needs at least one scope");
+    }
+
+    let mut auth_store = shared_auth_store.write().await;
+
+    let token = format!("{}{}", API_TOKEN_PREFIX, generate_secret());
+    let stored_token = StoredToken {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        token_hash: hash_token(&token),
+        scopes,
+        created_at: OffsetDateTime::now_utc(),
+    };
+    let mut updated_auth_store = auth_store.clone();
+    updated_auth_store.tokens.push(stored_token.clone());
+
+    write_auth_store(data_dir, &updated_auth_store).await?;
+    *auth_store = updated_auth_store;
+
+    Ok((stored_token, token))
+}
+
+/// Removes the API token. Returns `false` if there was no token with the ID.
+#[instrument(err, skip(shared_auth_store))]
+pub async fn revoke_token(
+    data_dir: &Path,
+    shared_auth_store: &SharedAuthStore,
+    token_id: &str,
+) -> Result<bool, anyhow::Error> {
+    let mut auth_store = shared_auth_store.write().await;
+    let mut updated_auth_store = auth_store.clone();
+    updated_auth_store
+        .tokens
+        .retain(|token| token.id != token_id);
+    if updated_auth_store.tokens.len() == auth_store.tokens.len() {
+        return Ok(false);
+    }
+
+    write_auth_store(data_dir, &updated_auth_store).await?;
+    *auth_store = updated_auth_store;
+
+    Ok(true)
+}
+
+/// Reloads the users and API tokens when the file in the data directory changes, so changes made with the admin command apply without a restart.
+/// The file is polled like the config file. The loaded users and tokens are kept if the file is removed or invalid.
+pub fn spawn_auth_store_reloader(app_state: AppState) {
+    tokio::spawn(async move {
+        let mut last_data_dir = PathBuf::from(&app_state.config().server_settings.data_dir);
+        let mut last_auth_store_file_state =
+            get_file_state(&last_data_dir.join(AUTH_STORE_FILE_NAME));
+        loop {
+            tokio::time::sleep(AUTH_STORE_POLL_INTERVAL).await;
+
+            // The data directory can change when the config is reloaded
+            let data_dir = PathBuf::from(&app_state.config().server_settings.data_dir);
+            let auth_store_path = data_dir.join(AUTH_STORE_FILE_NAME);
+            let auth_store_file_state = get_file_state(&auth_store_path);
+            if data_dir == last_data_dir && auth_store_file_state == last_auth_store_file_state {
+                continue;
+            }
+            last_data_dir = data_dir;
+            last_auth_store_file_state = auth_store_file_state;
+
+            if last_auth_store_file_state.is_none() {
+                warn!(
+                    "Users and API tokens file {} does not exist, keeping the loaded users and API tokens",
+                    auth_store_path.display()
+                );
+                continue;
+            }
+
+            match read_auth_store(&last_data_dir).await {
+                Ok(auth_store) => {
+                    *app_state.auth_store.write().await = auth_store;
+                    info!(
+                        "Reloaded users and API tokens from {}",
+                        auth_store_path.display()
+                    );
+                }
+                Err(err) => error!("Failed to reload users and API tokens: {:#}", err),
+            }
+        }
+    });
+}
+
+/// Checks the credentials and starts a session. Returns the session ID, which is sent to the browser in the session cookie.
+#[instrument(err, skip(app_state, password))]
+pub async fn log_in(
+    app_state: &AppState,
+    username: &str,
+    password: String,
+) -> Result<(String, Session), anyhow::Error> {
+    let password_hash = app_state
+        .auth_store
+        .read()
+        .await
+        .users
+        .iter()
+        .find(|user| user.username == username)
+        .map(|user| user.password_hash.clone());
+
+    let is_password_valid = spawn_blocking(move || match password_hash {
+        Some(password_hash) => verify_password(&password, &password_hash),
+        // The password is hashed anyway, so the response time does not reveal whether the user exists
+        None => hash_password(&password).map(|_| false),
+    })
+    .await
+    .context("Password verification task failed")??;
+
+    if !is_password_valid {
+        return Err(RequestError::new(
+            ErrorCode::InvalidCredentials,
+            "Invalid username or password",
+        )
+        .into());
+    }
+
+    let session_id = generate_secret();
+    let session = Session {
+        username: username.to_string(),
+        expires_at: OffsetDateTime::now_utc() + SESSION_LIFETIME,
+    };
+
+    let mut sessions = app_state.sessions.write().await;
+    let now = OffsetDateTime::now_utc();
+    sessions.retain(|_, session| session.expires_at > now);
+    sessions.insert(session_id.clone(), session.clone());
+    info!("User {} logged in", username);
+
+    Ok((session_id, session))
+}
+
+/// Ends the session of the session cookie, if there is one.
+pub async fn log_out(app_state: &AppState, headers: &HeaderMap) {
+    if let Some(session_id) = get_session_id(headers) {
+        if let Some(session) = app_state.sessions.write().await.remove(session_id) {
+            info!("User {} logged out", session.username);
+        }
+    }
+}
+
+/// Returns the session of the session cookie, if it has not expired. Expired sessions are removed.
+pub async fn get_session(app_state: &AppState, headers: &HeaderMap) -> Option<Session> {
+    let session_id = get_session_id(headers)?;
+    let session = app_state.sessions.read().await.get(session_id).cloned()?;
+    if session.expires_at <= OffsetDateTime::now_utc() {
+        app_state.sessions.write().await.remove(session_id);
+        return None;
+    }
+
+    Some(session)
+}
+
+/// Rejects requests to the API that are not authenticated with a session cookie or an API token, if authentication is enabled.
+/// API tokens can only be used for the endpoints of their scopes. The caller is added to the request extensions as a [`Principal`].
+pub async fn require_authentication(
+    State(app_state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ServerError> {
+    if !app_state.config().auth_settings.enabled {
+        return Ok(next.run(request).await);
+    }
+
+    let path = request.uri().path();
+    if PUBLIC_API_PATHS.contains(&path) {
+        return Ok(next.run(request).await);
+    }
+
+    let principal = authenticate(&app_state, request.headers()).await?;
+    let required_scope = get_required_scope(request.method(), path);
+    if let Principal::Token { name, scopes } = &principal {
+        if !scopes.contains(&required_scope) {
+            return Err(RequestError::new(
+                ErrorCode::InsufficientScope,
+                format!(
+                    "API token {} does not have the {} scope required by this endpoint",
+                    name, required_scope
+                ),
+            )
+            .into());
+        }
+    }
+
+    debug!("Request authenticated as {:?}", principal);
+    request.extensions_mut().insert(principal);
+
+    Ok(next.run(request).await)
+}
+
+/// Returns the caller of the request. API tokens in the `Authorization` header take precedence over the session cookie.
+async fn authenticate(
+    app_state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Principal, anyhow::Error> {
+    let auth_store = app_state.auth_store.read().await;
+
+    if let Some(authorization) = headers.get(AUTHORIZATION) {
+        let token = authorization
+            .to_str()
+            .ok()
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .ok_or_else(|| {
+                RequestError::new(
+                    ErrorCode::AuthenticationRequired,
+                    "The Authorization header must contain an API token: Bearer <token>",
+                )
+            })?;
+        let token_hash = hash_token(token.trim());
+
+        return auth_store
+            .tokens
+            .iter()
+            .find(|stored_token| stored_token.token_hash == token_hash)
+            .map(|stored_token| Principal::Token {
+                name: stored_token.name.clone(),
+                scopes: stored_token.scopes.clone(),
+            })
+            .ok_or_else(|| {
+                RequestError::new(ErrorCode::AuthenticationRequired, "Invalid API token").into()
+            });
+    }
+
+    // Sessions of deleted users are not accepted, so deleting a user logs them out
+    get_session(app_state, headers)
+        .await
+        .filter(|session| {
+            auth_store
+                .users
+                .iter()
+                .any(|user| user.username == session.username)
+        })
+        .map(|session| Principal::User {
+            username: session.username,
+        })
+        .ok_or_else(|| {
+            RequestError::new(
+                ErrorCode::AuthenticationRequired,
+                "Log in or send an API token in the Authorization header",
+            )
+            .into()
+        })
+}
+
+/// Returns the scope an API token needs for the request. `path` is relative to the API prefix.
+fn get_required_scope(method: &Method, path: &str) -> TokenScope {
+    if path.starts_with("/admin/") {
+        TokenScope::Admin
+    } else if matches!(*method, Method::GET | Method::HEAD) {
+        TokenScope::Read
+    } else if path.starts_with("/tools/") {
+        TokenScope::Tools
+    } else {
+        TokenScope::Write
+    }
+}
+
+/// Returns the `Set-Cookie` value storing the session ID. The cookie is not readable by scripts and not sent with requests from other sites.
+/// Secure cookies are only sent over HTTPS.
+pub fn get_session_cookie(session_id: &str, is_secure: bool) -> Result<HeaderValue, anyhow::Error> {
+    create_session_cookie(session_id, SESSION_LIFETIME, is_secure)
+}
+
+/// Returns the `Set-Cookie` value removing the session cookie.
+pub fn get_expired_session_cookie(is_secure: bool) -> Result<HeaderValue, anyhow::Error> {
+    create_session_cookie("", Duration::ZERO, is_secure)
+}
+
+fn create_session_cookie(
+    session_id: &str,
+    max_age: Duration,
+    is_secure: bool,
+) -> Result<HeaderValue, anyhow::Error> {
+    HeaderValue::from_str(&format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+        SESSION_COOKIE_NAME,
+        session_id,
+        max_age.whole_seconds(),
+        if is_secure { "; Secure" } else { "" }
+    ))
+    .context("Failed to create session cookie")
+}
+
+fn get_session_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok())
+        .flat_map(|cookie| cookie.split(';'))
+        .find_map(|cookie| {
+            let (name, value) = cookie.trim().split_once('=')?;
+            (name == SESSION_COOKIE_NAME).then_some(value)
+        })
+}
+
+fn hash_password(password: &str) -> Result<String, anyhow::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow::anyhow!("Failed to hash password: {}", err))?;
+
+    Ok(password_hash.to_string())
+}
+
+fn verify_password(password: &str, password_hash: &str) -> Result<bool, anyhow::Error> {
+    let password_hash = PasswordHash::new(password_hash)
+        .map_err(|err| anyhow::anyhow!("Invalid stored password hash: {}", err))?;
+
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &password_hash)
+        .is_ok())
+}
+
+fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut secret);
+
+    to_hex(&secret)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+/// Logs a warning if the API can be used by anyone who can reach the server, or if nobody can log in.
+#[instrument(skip(app_state))]
+pub async fn warn_about_auth_settings(app_state: &AppState) {
+    let config = app_state.config();
+    if !config.auth_settings.enabled {
+        let is_loopback_host = config
+            .server_settings
+            .host
+            .parse::<IpAddr>()
+            .is_ok_and(|host| host.is_loopback())
+            || config.server_settings.host == "localhost";
+        if !is_loopback_host {
+            warn!("Authentication is disabled, so anyone who can reach the server can use the API. Enable it with --enable-auth");
+        }
+        return;
+    }
+
+    let auth_store = app_state.auth_store.read().await;
+    if auth_store.users.is_empty() && auth_store.tokens.is_empty() {
+        warn!("Authentication is enabled, but there are no users or API tokens. Create them with the admin command");
+    }
+}
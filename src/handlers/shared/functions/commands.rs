@@ -3,10 +3,22 @@ use std::path::PathBuf;
 use tokio::process::Command;
 use tracing::{error, info, instrument};
 
-#[instrument(err, ret(level = "debug"))]
+/// Replaces secrets, such as proxy credentials, in logged arguments and returned command output.
+const REDACTED_SECRET: &str = "<redacted>";
+
 pub async fn run_command(
     executable_path: &PathBuf,
     args: &[&str],
+) -> Result<CommandExecutionResults, anyhow::Error> {
+    run_command_with_secrets(executable_path, args, &[]).await
+}
+
+/// Runs a command and replaces every secret in the logged arguments and in the returned output.
+#[instrument(err, ret(level = "debug"), skip(args, secrets), fields(args = ?redact_secrets(args, secrets)))]
+pub async fn run_command_with_secrets(
+    executable_path: &PathBuf,
+    args: &[&str],
+    secrets: &[String],
 ) -> Result<CommandExecutionResults, anyhow::Error> {
     info!("Running command");
 
@@ -15,7 +27,7 @@ pub async fn run_command(
     let stdout = String::from_utf8_lossy(&command_output.stdout).to_string();
     let stderr = String::from_utf8_lossy(&command_output.stderr).to_string();
     let exit_code = command_output.status.code();
-    let command_completed_successfully = exit_code.map_or(false, |code| code == 0);
+    let command_completed_successfully = exit_code == Some(0);
 
     if command_completed_successfully {
         info!("Command completed successfully");
@@ -29,12 +41,26 @@ pub async fn run_command(
         stdout: if stdout.is_empty() {
             None
         } else {
-            Some(stdout)
+            Some(redact_secret(stdout, secrets))
         },
         stderr: if stderr.is_empty() {
             None
         } else {
-            Some(stderr)
+            Some(redact_secret(stderr, secrets))
         },
     })
 }
+
+fn redact_secrets(args: &[&str], secrets: &[String]) -> Vec<String> {
+    args.iter()
+        .map(|arg| redact_secret(arg.to_string(), secrets))
+        .collect()
+}
+
+fn redact_secret(mut value: String, secrets: &[String]) -> String {
+    for secret in secrets.iter().filter(|secret| !secret.is_empty()) {
+        value = value.replace(secret.as_str(), REDACTED_SECRET);
+    }
+
+    value
+}
@@ -0,0 +1,185 @@
+use crate::handlers::shared::functions::commands::run_command;
+use crate::handlers::shared::functions::files::reserve_free_file_path;
+use crate::handlers::shared::functions::tools::get_ffmpeg_executable_path;
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use crate::handlers::shared::model::media::AudioFormat;
+use crate::AppState;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use tokio::fs::{remove_file, rename};
+use tracing::{info, instrument};
+
+/// Formats that can store a cover image as an attached picture stream.
+const COVER_IMAGE_EXTENSIONS: &[&str] = &["mp3", "m4a", "flac"];
+/// Suffix of the temporary file ffmpeg writes to when a file is rewritten in place.
+const REWRITE_FILE_SUFFIX: &str = ".rewrite";
+
+/// Returns the file extension of the audio format, or `None` for formats that are kept as served.
+pub fn get_audio_format_extension(audio_format: AudioFormat) -> Option<&'static str> {
+    match audio_format {
+        AudioFormat::Mp3 => Some("mp3"),
+        AudioFormat::Opus => Some("opus"),
+        AudioFormat::M4a => Some("m4a"),
+        AudioFormat::Flac => Some("flac"),
+        AudioFormat::BestOriginal => None,
+    }
+}
+
+/// Returns the ffmpeg arguments encoding audio into the format of the file extension.
+pub fn get_audio_codec_args(extension: &str) -> Result<Vec<String>, anyhow::Error> {
+    let codec_args: &[&str] = match extension.to_ascii_lowercase().as_str() {
+        "mp3" => &["-c:a", "libmp3lame", "-q:a", "2"],
+        "m4a" | "aac" => &["-c:a", "aac", "-b:a", "256k"],
+        "opus" | "webm" => &["-c:a", "libopus", "-b:a", "160k"],
+        "ogg" => &["-c:a", "libvorbis", "-q:a", "6"],
+        "flac" => &["-c:a", "flac"],
+        "wav" => &["-c:a", "pcm_s16le"],
+        _ => anyhow::bail!(
+            "Encoding audio files with extension {} is not supported",
+            extension
+        ),
+    };
+
+    Ok(codec_args.iter().map(|arg| arg.to_string()).collect())
+}
+
+/// Returns the ffmpeg arguments selecting the streams written to a file with the extension.
+/// The audio is always kept, the cover image only if the format can store it.
+pub fn get_stream_args(extension: &str) -> Vec<String> {
+    let mut stream_args = vec!["-map".to_string(), "0:a".to_string()];
+    if COVER_IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()) {
+        stream_args.extend([
+            "-map".to_string(),
+            "0:v?".to_string(),
+            "-c:v".to_string(),
+            "copy".to_string(),
+            "-disposition:v".to_string(),
+            "attached_pic".to_string(),
+        ]);
+    }
+
+    stream_args
+}
+
+/// Converts the audio file to `audio_format` using ffmpeg and writes it next to the source, which is kept.
+/// Existing files are never overwritten. Returns the path of the converted file if ffmpeg succeeded, with the ffmpeg results.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn convert_audio_file(
+    app_state: &AppState,
+    audio_file_path: &Path,
+    audio_format: AudioFormat,
+) -> Result<(Option<PathBuf>, CommandExecutionResults), anyhow::Error> {
+    let extension =
+        get_audio_format_extension(audio_format).context("Target audio format is not set")?;
+    let codec_args = get_audio_codec_args(extension)?;
+
+    let ffmpeg_executable_path = get_ffmpeg_executable_path(app_state)
+        .await
+        .context("Failed to get ffmpeg executable path")?;
+
+    let output_path = reserve_free_file_path(&audio_file_path.with_extension(extension)).await?;
+
+    info!(
+        "Converting {} to {}",
+        audio_file_path.display(),
+        output_path.display()
+    );
+
+    let mut args = vec![
+        "-hide_banner".to_string(),
+        "-nostdin".to_string(),
+        // The output path is reserved with an empty file
+        "-y".to_string(),
+        "-i".to_string(),
+        audio_file_path.to_string_lossy().to_string(),
+    ];
+    args.extend(get_stream_args(extension));
+    args.extend(codec_args);
+    args.push(output_path.to_string_lossy().to_string());
+
+    let command_execution_results = run_ffmpeg(&ffmpeg_executable_path, &args, &output_path)
+        .await
+        .context("Failed to convert audio file using ffmpeg")?;
+    if !command_execution_results.command_completed_successfully {
+        let _ = remove_file(&output_path).await;
+        return Ok((None, command_execution_results));
+    }
+
+    Ok((Some(output_path), command_execution_results))
+}
+
+/// Rewrites the audio file in place using ffmpeg with `output_args` applied, for example to change tags or filter the audio.
+/// ffmpeg writes to a temporary file next to the source, which replaces the source only if ffmpeg succeeded.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn rewrite_audio_file(
+    app_state: &AppState,
+    audio_file_path: &Path,
+    output_args: &[String],
+) -> Result<CommandExecutionResults, anyhow::Error> {
+    let ffmpeg_executable_path = get_ffmpeg_executable_path(app_state)
+        .await
+        .context("Failed to get ffmpeg executable path")?;
+
+    let file_name = audio_file_path
+        .file_name()
+        .context("Failed to get audio file name")?
+        .to_string_lossy()
+        .to_string();
+    let extension = audio_file_path
+        .extension()
+        .context("Failed to get audio file extension")?
+        .to_string_lossy()
+        .to_string();
+    // The leading dot keeps the temporary file out of library listings, the extension tells ffmpeg the output format
+    let temp_file_path = reserve_free_file_path(&audio_file_path.with_file_name(format!(
+        ".{}{}.{}",
+        file_name, REWRITE_FILE_SUFFIX, extension
+    )))
+    .await?;
+
+    let mut args = vec![
+        "-hide_banner".to_string(),
+        "-nostdin".to_string(),
+        // The temporary path is reserved with an empty file
+        "-y".to_string(),
+        "-i".to_string(),
+        audio_file_path.to_string_lossy().to_string(),
+    ];
+    args.extend(output_args.iter().cloned());
+    args.push(temp_file_path.to_string_lossy().to_string());
+
+    let command_execution_results = run_ffmpeg(&ffmpeg_executable_path, &args, &temp_file_path)
+        .await
+        .context("Failed to rewrite audio file using ffmpeg")?;
+    if !command_execution_results.command_completed_successfully {
+        let _ = remove_file(&temp_file_path).await;
+        return Ok(command_execution_results);
+    }
+
+    if let Err(err) = rename(&temp_file_path, audio_file_path).await {
+        let _ = remove_file(&temp_file_path).await;
+        return Err(err).context("Failed to replace audio file with the rewritten file");
+    }
+
+    Ok(command_execution_results)
+}
+
+/// Runs ffmpeg and removes the reserved output file if the command could not be run.
+async fn run_ffmpeg(
+    ffmpeg_executable_path: &PathBuf,
+    args: &[String],
+    output_path: &Path,
+) -> Result<CommandExecutionResults, anyhow::Error> {
+    match run_command(
+        ffmpeg_executable_path,
+        &args.iter().map(String::as_str).collect::<Vec<_>>(),
+    )
+    .await
+    {
+        Ok(command_execution_results) => Ok(command_execution_results),
+        Err(err) => {
+            let _ = remove_file(output_path).await;
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use crate::handlers::errors::{ErrorCode, RequestError};
+use crate::handlers::shared::functions::downloads::download_file_with_resume;
+use crate::handlers::shared::functions::library::INFO_JSON_SUFFIX;
+use crate::handlers::shared::functions::urls::{get_denied_redirect_error, is_extractor_allowed};
+use crate::handlers::shared::functions::yt_dlp::DOWNLOADED_MEDIA_FILE_STEM;
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use crate::handlers::shared::model::direct::DirectAudioSource;
+use crate::handlers::shared::model::downloads::DownloadState;
+use crate::handlers::shared::model::media::YtDlpNetworkOptions;
+use crate::AppState;
+use anyhow::Context;
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::Url;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+use tokio::fs::{rename, write};
+use tokio::time::sleep;
+use tracing::{debug, info, instrument, warn};
+
+/// Extractor recorded for direct downloads. yt-dlp handles the same URLs with its generic extractor,
+/// so the extractor allow and deny lists apply to direct downloads the same way.
+pub const DIRECT_DOWNLOAD_EXTRACTOR: &str = "generic";
+
+const HEAD_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const DIRECT_DOWNLOAD_ATTEMPTS: u32 = 3;
+const DIRECT_DOWNLOAD_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Content types of audio files with the extension of the downloaded file.
+const AUDIO_CONTENT_TYPES: &[(&str, &str)] = &[
+    ("audio/mpeg", "mp3"),
+    ("audio/mp3", "mp3"),
+    ("audio/flac", "flac"),
+    ("audio/x-flac", "flac"),
+    ("audio/ogg", "ogg"),
+    ("application/ogg", "ogg"),
+    ("audio/opus", "opus"),
+    ("audio/mp4", "m4a"),
+    ("audio/m4a", "m4a"),
+    ("audio/x-m4a", "m4a"),
+    ("audio/aac", "aac"),
+    ("audio/wav", "wav"),
+    ("audio/wave", "wav"),
+    ("audio/x-wav", "wav"),
+    ("audio/webm", "webm"),
+];
+
+/// Content types servers use for any binary file. Such files are only downloaded directly if the URL has an audio file extension.
+const GENERIC_CONTENT_TYPES: &[&str] = &["application/octet-stream", "binary/octet-stream"];
+
+/// Checks with a HEAD request whether `media_url` links directly to an audio file that can be downloaded without yt-dlp.
+///
+/// Returns `None` for web pages and other content, when the HEAD request fails, or when the download needs settings
+/// only yt-dlp supports, such as a proxy or request cookies. Such URLs are downloaded with yt-dlp as before.
+/// Files larger than the configured maximum size are rejected with a [`RequestError`].
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn detect_direct_audio_source(
+    app_state: &AppState,
+    media_url: &str,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<Option<DirectAudioSource>, anyhow::Error> {
+    let audio_download_settings = &app_state.config().audio_download_settings;
+    if !audio_download_settings.direct_downloads_enabled {
+        return Ok(None);
+    }
+
+    if !media_url.starts_with("http://") && !media_url.starts_with("https://") {
+        debug!("URL is a search, not checking for a direct download");
+        return Ok(None);
+    }
+
+    if !is_extractor_allowed(
+        &app_state.config().url_policy_settings,
+        DIRECT_DOWNLOAD_EXTRACTOR,
+    ) {
+        debug!("Generic extractor is not allowed, not checking for a direct download");
+        return Ok(None);
+    }
+
+    // The HTTP client does not use the proxy and cookies of yt-dlp, so such downloads are left to yt-dlp
+    let uses_proxy = network_options
+        .proxy
+        .as_ref()
+        .or(app_state.config().yt_dlp_settings.proxy.as_ref())
+        .is_some_and(|proxy| !proxy.as_str().is_empty());
+    if uses_proxy || network_options.cookies.is_some() {
+        debug!("Download uses a proxy or request cookies, not checking for a direct download");
+        return Ok(None);
+    }
+
+    // The media HTTP client checks redirects against the URL policy, so an allowed URL cannot lead to a denied host
+    let resp = match app_state
+        .media_http_client
+        .head(media_url)
+        .timeout(HEAD_REQUEST_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) => {
+            debug!("HEAD request returned status {}", resp.status());
+            return Ok(None);
+        }
+        Err(err) => {
+            if let Some(request_error) = get_denied_redirect_error(&err) {
+                return Err(request_error.into());
+            }
+            debug!("HEAD request failed: {}", err);
+            return Ok(None);
+        }
+    };
+
+    let url = resp.url().to_string();
+    let headers = resp.headers();
+
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|content_type| content_type.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    let url_file_name = get_url_file_name(resp.url());
+    let url_file_ext = url_file_name
+        .as_deref()
+        .and_then(|file_name| Path::new(file_name).extension())
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .filter(|ext| {
+            AUDIO_CONTENT_TYPES
+                .iter()
+                .any(|(_, audio_ext)| audio_ext == ext)
+        });
+
+    let file_ext = match AUDIO_CONTENT_TYPES
+        .iter()
+        .find(|(audio_content_type, _)| *audio_content_type == content_type)
+    {
+        Some((_, audio_ext)) => audio_ext.to_string(),
+        None if content_type.starts_with("audio/")
+            || GENERIC_CONTENT_TYPES.contains(&content_type.as_str()) =>
+        {
+            match url_file_ext {
+                Some(url_file_ext) => url_file_ext,
+                None => {
+                    debug!("Content type {} has no known audio extension", content_type);
+                    return Ok(None);
+                }
+            }
+        }
+        None => {
+            debug!("Content type {} is not audio", content_type);
+            return Ok(None);
+        }
+    };
+
+    // `Response::content_length` reports the size of the empty HEAD body, so the header is read instead
+    let content_length = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|content_length| content_length.to_str().ok())
+        .and_then(|content_length| content_length.parse::<u64>().ok());
+    let max_bytes = audio_download_settings.max_direct_download_bytes;
+    if content_length.is_some_and(|content_length| content_length > max_bytes) {
+        return Err(RequestError::new(
+            ErrorCode::DownloadTooLarge,
+            format!(
+                "Audio file is larger than the maximum allowed size of {} bytes",
+                max_bytes
+            ),
+        )
+        .into());
+    }
+
+    let title = headers
+        .get(CONTENT_DISPOSITION)
+        .and_then(|content_disposition| content_disposition.to_str().ok())
+        .and_then(get_content_disposition_file_name)
+        .or(url_file_name)
+        .and_then(|file_name| {
+            Path::new(&file_name)
+                .file_stem()
+                .map(|file_stem| file_stem.to_string_lossy().trim().to_string())
+        })
+        .filter(|title| !title.is_empty());
+
+    Ok(Some(DirectAudioSource {
+        url,
+        content_type,
+        file_ext,
+        content_length,
+        title,
+    }))
+}
+
+/// Downloads a direct audio file over HTTP to `download_dir`, where it is picked up like files downloaded by yt-dlp.
+///
+/// The file is streamed to a partial file named after the URL, so failed attempts and later requests for the same URL
+/// resume the download. Once complete, the file gets the download ID prefix and an info JSON with the metadata known
+/// from the URL, which is used to name it in the library. Returns the results in the same shape as yt-dlp command results.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn download_direct_audio(
+    app_state: &AppState,
+    direct_audio_source: &DirectAudioSource,
+    download_dir: &Path,
+    download_id: &str,
+) -> Result<CommandExecutionResults, anyhow::Error> {
+    let resume_key = get_resume_key(&direct_audio_source.url);
+    // The leading dot keeps the file apart from the files with a download ID prefix
+    let partial_download_path =
+        download_dir.join(format!(".{}.{}", resume_key, direct_audio_source.file_ext));
+
+    info!(
+        "Downloading {} of {:?} bytes from {}",
+        direct_audio_source.content_type,
+        direct_audio_source.content_length,
+        direct_audio_source.url
+    );
+    // Drops entries left behind by cancelled downloads
+    app_state
+        .direct_download_progress
+        .write()
+        .await
+        .retain(|_, progress| progress.state == DownloadState::InProgress);
+
+    let config = app_state.config();
+    let mut attempt = 1;
+    let download_result = loop {
+        let download_result = download_file_with_resume(
+            &app_state.media_http_client,
+            &direct_audio_source.url,
+            &partial_download_path,
+            &app_state.direct_download_progress,
+            &resume_key,
+            Some(config.audio_download_settings.max_direct_download_bytes),
+        )
+        .await;
+
+        match download_result {
+            Err(err)
+                if attempt < DIRECT_DOWNLOAD_ATTEMPTS
+                    && err.downcast_ref::<RequestError>().is_none() =>
+            {
+                warn!(
+                    "Direct download attempt {} failed, retrying: {:#}",
+                    attempt, err
+                );
+                attempt += 1;
+                sleep(DIRECT_DOWNLOAD_RETRY_DELAY).await;
+            }
+            download_result => break download_result,
+        }
+    };
+    app_state
+        .direct_download_progress
+        .write()
+        .await
+        .remove(&resume_key);
+
+    let downloaded_bytes = match download_result {
+        Ok(downloaded_bytes) => downloaded_bytes,
+        Err(err) if err.downcast_ref::<RequestError>().is_some() => return Err(err),
+        Err(err) => {
+            return Ok(CommandExecutionResults {
+                command_completed_successfully: false,
+                exit_code: None,
+                stdout: None,
+                stderr: Some(format!("{:#}", err)),
+            });
+        }
+    };
+
+    let media_file_stem = format!("{}{}", download_id, DOWNLOADED_MEDIA_FILE_STEM);
+    rename(
+        &partial_download_path,
+        download_dir.join(format!(
+            "{}.{}",
+            media_file_stem, direct_audio_source.file_ext
+        )),
+    )
+    .await
+    .context("Failed to rename direct download")?;
+
+    let media_info = json!({
+        "id": direct_audio_source.url,
+        "title": direct_audio_source.title,
+        "extractor": DIRECT_DOWNLOAD_EXTRACTOR,
+        "webpage_url": direct_audio_source.url,
+    });
+    write(
+        download_dir.join(format!("{}{}", media_file_stem, INFO_JSON_SUFFIX)),
+        media_info.to_string(),
+    )
+    .await
+    .context("Failed to write info JSON of direct download")?;
+
+    info!(
+        "Direct download completed, {} bytes of {}",
+        downloaded_bytes, direct_audio_source.content_type
+    );
+
+    Ok(CommandExecutionResults {
+        command_completed_successfully: true,
+        exit_code: None,
+        stdout: Some(format!(
+            "Downloaded {} bytes of {} from {}",
+            downloaded_bytes, direct_audio_source.content_type, direct_audio_source.url
+        )),
+        stderr: None,
+    })
+}
+
+/// Describes a detected direct audio file in the shape of command results, for responses that skip the download.
+pub fn describe_direct_audio_source(
+    direct_audio_source: &DirectAudioSource,
+) -> CommandExecutionResults {
+    CommandExecutionResults {
+        command_completed_successfully: true,
+        exit_code: None,
+        stdout: Some(format!(
+            "{} {} ({})",
+            DIRECT_DOWNLOAD_EXTRACTOR, direct_audio_source.url, direct_audio_source.content_type
+        )),
+        stderr: None,
+    }
+}
+
+/// Derives the name of the partial file and the progress key from the SHA-256 hash of the URL,
+/// so the same URL maps to the same partial file across restarts and Rust versions.
+fn get_resume_key(url: &str) -> String {
+    let url_hash = Sha256::digest(url.as_bytes());
+    let url_hash_hex = url_hash.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    });
+
+    format!("direct-{}", url_hash_hex)
+}
+
+fn get_url_file_name(url: &Url) -> Option<String> {
+    url.path_segments()?
+        .next_back()
+        .filter(|segment| !segment.is_empty())
+        .map(percent_decode)
+}
+
+/// Returns the file name from a `Content-Disposition` header, preferring the encoded `filename*` parameter.
+fn get_content_disposition_file_name(content_disposition: &str) -> Option<String> {
+    let parameters: Vec<(String, &str)> = content_disposition
+        .split(';')
+        .filter_map(|parameter| parameter.split_once('='))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+        .collect();
+
+    let encoded_file_name = parameters
+        .iter()
+        .find(|(name, _)| name == "filename*")
+        .and_then(|(_, value)| value.split_once("''"))
+        .map(|(_, file_name)| percent_decode(file_name));
+    let file_name = parameters
+        .iter()
+        .find(|(name, _)| name == "filename")
+        .map(|(_, value)| value.trim_matches('"').to_string());
+
+    encoded_file_name
+        .or(file_name)
+        .filter(|file_name| !file_name.is_empty())
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            if let Some(byte) = value
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
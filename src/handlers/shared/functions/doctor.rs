@@ -0,0 +1,365 @@
+use crate::doh::CloudflareDoHResolver;
+use crate::handlers::shared::functions::commands::run_command;
+use crate::handlers::shared::functions::identify::{ACOUSTID_API_URL, MUSICBRAINZ_API_URL};
+use crate::handlers::shared::functions::tools::{
+    get_tool_executable_path, get_tool_version_args, parse_tool_version_for_install,
+};
+use crate::handlers::shared::model::doctor::{
+    DirectoryDoctorReport, DoctorResponse, NetworkDoctorReport, ToolDoctorReport,
+};
+use crate::handlers::shared::model::tools::Tool;
+use crate::AppState;
+use anyhow::Context;
+use reqwest::dns::{Name, Resolve};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::fs::{metadata, remove_file, File};
+use tokio::io::AsyncReadExt;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+const DOH_CHECK_HOSTNAME: &str = "api.acoustid.org";
+const GITHUB_URL: &str = "https://github.com";
+const NETWORK_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs all health checks: tools, directories, free disk space, DoH resolution and outbound API reachability.
+#[instrument(ret(level = "debug"), skip(app_state))]
+pub async fn run_doctor_checks(app_state: &AppState) -> DoctorResponse {
+    info!("Running doctor checks");
+
+    let mut tools = Vec::new();
+    for tool in [Tool::YtDlp, Tool::Ffmpeg, Tool::Chromaprint] {
+        tools.push(check_tool(app_state, tool).await);
+    }
+
+    let config = app_state.config();
+    let mut directories = Vec::new();
+    for (name, dir) in [
+        ("library_dir", &config.library_settings.dir),
+        (
+            "tools_download_dir",
+            &config.server_settings.tools_download_dir,
+        ),
+        (
+            "audio_download_dir",
+            &config.audio_download_settings.download_dir,
+        ),
+        (
+            "video_download_dir",
+            &config.video_download_settings.download_dir,
+        ),
+    ] {
+        directories.push(check_directory(name, dir).await);
+    }
+
+    let (doh_report, acoustid_report, musicbrainz_report, github_report) = tokio::join!(
+        check_doh_resolution(app_state),
+        check_url_reachability(app_state, "acoustid_api", ACOUSTID_API_URL),
+        check_url_reachability(app_state, "musicbrainz_api", MUSICBRAINZ_API_URL),
+        check_url_reachability(app_state, "github", GITHUB_URL),
+    );
+    let network = vec![
+        doh_report,
+        acoustid_report,
+        musicbrainz_report,
+        github_report,
+    ];
+
+    let healthy = tools.iter().all(|tool| tool.runnable)
+        && directories
+            .iter()
+            .all(|directory| directory.exists && directory.writable)
+        && network.iter().all(|check| check.reachable != Some(false));
+
+    DoctorResponse {
+        healthy,
+        host_arch: std::env::consts::ARCH.to_string(),
+        host_os: std::env::consts::OS.to_string(),
+        tools,
+        directories,
+        network,
+    }
+}
+
+/// Logs a warning for each failed doctor check.
+#[instrument(skip(doctor_response))]
+pub fn log_failed_doctor_checks(doctor_response: &DoctorResponse) {
+    if doctor_response.healthy {
+        info!("All doctor checks passed");
+        return;
+    }
+
+    for tool in doctor_response.tools.iter().filter(|tool| !tool.runnable) {
+        warn!(
+            "Doctor check failed for tool {}: {}",
+            tool.tool,
+            tool.error.as_deref().unwrap_or("tool is not runnable")
+        );
+    }
+
+    for directory in doctor_response
+        .directories
+        .iter()
+        .filter(|directory| !directory.exists || !directory.writable)
+    {
+        warn!(
+            "Doctor check failed for directory {} ({}): exists: {}, writable: {}, error: {}",
+            directory.name,
+            directory.path,
+            directory.exists,
+            directory.writable,
+            directory.error.as_deref().unwrap_or("none")
+        );
+    }
+
+    for check in doctor_response
+        .network
+        .iter()
+        .filter(|check| check.reachable == Some(false))
+    {
+        warn!(
+            "Doctor check failed for {} ({}): {}",
+            check.name,
+            check.target,
+            check.error.as_deref().unwrap_or("unreachable")
+        );
+    }
+}
+
+#[instrument(ret(level = "debug"), skip(app_state))]
+async fn check_tool(app_state: &AppState, tool: Tool) -> ToolDoctorReport {
+    let executable_path = match get_tool_executable_path(app_state, tool).await {
+        Ok(executable_path) => executable_path,
+        Err(err) => {
+            return ToolDoctorReport {
+                tool,
+                present: false,
+                runnable: false,
+                path: None,
+                version: None,
+                arch: None,
+                error: Some(format!("{:#}", err)),
+            }
+        }
+    };
+
+    let arch = detect_executable_arch(&executable_path).await.ok();
+    let (runnable, version, error) =
+        match run_command(&executable_path, get_tool_version_args(tool)).await {
+            Ok(results) if results.command_completed_successfully => (
+                true,
+                results
+                    .stdout
+                    .as_deref()
+                    .and_then(|stdout| parse_tool_version_for_install(tool, stdout)),
+                None,
+            ),
+            Ok(results) => (
+                false,
+                None,
+                Some(format!(
+                    "Version command failed with exit code: {:?}",
+                    results.exit_code
+                )),
+            ),
+            Err(err) => (false, None, Some(format!("{:#}", err))),
+        };
+
+    ToolDoctorReport {
+        tool,
+        present: true,
+        runnable,
+        path: Some(executable_path.to_string_lossy().to_string()),
+        version,
+        arch,
+        error,
+    }
+}
+
+#[instrument(ret(level = "debug"))]
+async fn check_directory(name: &str, dir: &str) -> DirectoryDoctorReport {
+    let path = Path::new(dir);
+    let mut report = DirectoryDoctorReport {
+        name: name.to_string(),
+        path: dir.to_string(),
+        exists: false,
+        writable: false,
+        free_space_bytes: None,
+        error: None,
+    };
+
+    match metadata(path).await {
+        Ok(dir_metadata) if dir_metadata.is_dir() => report.exists = true,
+        Ok(_) => {
+            report.error = Some("Path exists but is not a directory".to_string());
+            return report;
+        }
+        Err(err) => {
+            report.error = Some(format!("Directory does not exist: {}", err));
+            return report;
+        }
+    }
+
+    match check_directory_writable(path).await {
+        Ok(()) => report.writable = true,
+        Err(err) => report.error = Some(format!("{:#}", err)),
+    }
+
+    report.free_space_bytes = fs2::available_space(path).ok();
+
+    report
+}
+
+#[instrument(err, ret(level = "debug"))]
+async fn check_directory_writable(path: &Path) -> Result<(), anyhow::Error> {
+    let probe_file_path = path.join(format!(".ferrous-beats-doctor-{}", Uuid::new_v4()));
+    File::create(&probe_file_path)
+        .await
+        .context("Failed to create file in directory")?;
+    remove_file(&probe_file_path)
+        .await
+        .context("Failed to remove file from directory")?;
+
+    Ok(())
+}
+
+#[instrument(ret(level = "debug"), skip(app_state))]
+async fn check_doh_resolution(app_state: &AppState) -> NetworkDoctorReport {
+    let mut report = NetworkDoctorReport {
+        name: "doh_resolver".to_string(),
+        target: DOH_CHECK_HOSTNAME.to_string(),
+        reachable: None,
+        details: None,
+        error: None,
+    };
+
+    if app_state.config().server_settings.disable_doh {
+        report.details = Some("DNS over HTTPS is disabled".to_string());
+        return report;
+    }
+
+    let name = match Name::from_str(DOH_CHECK_HOSTNAME) {
+        Ok(name) => name,
+        Err(err) => {
+            report.reachable = Some(false);
+            report.error = Some(err.to_string());
+            return report;
+        }
+    };
+
+    match tokio::time::timeout(
+        NETWORK_CHECK_TIMEOUT,
+        CloudflareDoHResolver::default().resolve(name),
+    )
+    .await
+    {
+        Ok(Ok(addrs)) => {
+            let resolved_addresses = addrs.map(|addr| addr.ip().to_string()).collect::<Vec<_>>();
+            report.reachable = Some(!resolved_addresses.is_empty());
+            report.details = Some(format!("Resolved to: {}", resolved_addresses.join(", ")));
+        }
+        Ok(Err(err)) => {
+            report.reachable = Some(false);
+            report.error = Some(err.to_string());
+        }
+        Err(_) => {
+            report.reachable = Some(false);
+            report.error = Some("DoH resolution timed out".to_string());
+        }
+    }
+
+    report
+}
+
+#[instrument(ret(level = "debug"), skip(app_state))]
+async fn check_url_reachability(
+    app_state: &AppState,
+    name: &str,
+    url: &str,
+) -> NetworkDoctorReport {
+    let mut report = NetworkDoctorReport {
+        name: name.to_string(),
+        target: url.to_string(),
+        reachable: None,
+        details: None,
+        error: None,
+    };
+
+    // Any HTTP response means the API is reachable, even if the request itself is not valid for the API
+    match app_state
+        .http_client
+        .head(url)
+        .timeout(NETWORK_CHECK_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(resp) => {
+            report.reachable = Some(true);
+            report.details = Some(format!("HTTP status: {}", resp.status()));
+        }
+        Err(err) => {
+            report.reachable = Some(false);
+            report.error = Some(format!("{:#}", anyhow::Error::from(err)));
+        }
+    }
+
+    report
+}
+
+/// Detects the architecture an executable was built for by reading its ELF, PE or Mach-O header.
+#[instrument(err, ret(level = "debug"))]
+async fn detect_executable_arch(executable_path: &PathBuf) -> Result<String, anyhow::Error> {
+    let mut file = File::open(executable_path)
+        .await
+        .context("Failed to open executable")?;
+    let mut header = vec![0u8; 4096];
+    let read_bytes = file
+        .read(&mut header)
+        .await
+        .context("Failed to read executable header")?;
+    header.truncate(read_bytes);
+
+    let read_u16_le = |offset: usize| -> Option<u16> {
+        Some(u16::from_le_bytes(
+            header.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let read_u32_le = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            header.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    let arch = if header.starts_with(b"#!") {
+        "script"
+    } else if header.starts_with(b"\x7fELF") {
+        match read_u16_le(18) {
+            Some(0x03) => "x86",
+            Some(0x28) => "arm",
+            Some(0x3e) => "x86_64",
+            Some(0xb7) => "aarch64",
+            _ => "unknown",
+        }
+    } else if header.starts_with(b"MZ") {
+        let pe_header_offset = read_u32_le(0x3c).context("Invalid PE header")? as usize;
+        match read_u16_le(pe_header_offset + 4) {
+            Some(0x014c) => "x86",
+            Some(0x8664) => "x86_64",
+            Some(0xaa64) => "aarch64",
+            _ => "unknown",
+        }
+    } else if header.starts_with(&[0xca, 0xfe, 0xba, 0xbe]) {
+        "universal"
+    } else if header.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]) {
+        match read_u32_le(4) {
+            Some(0x0100_0007) => "x86_64",
+            Some(0x0100_000c) => "aarch64",
+            _ => "unknown",
+        }
+    } else {
+        anyhow::bail!("Unrecognized executable format")
+    };
+
+    Ok(arch.to_string())
+}
@@ -0,0 +1,371 @@
+use crate::handlers::errors::{ErrorCode, RequestError};
+use crate::handlers::shared::functions::urls::get_denied_redirect_error;
+use crate::handlers::shared::model::downloads::{
+    DownloadProgress, DownloadProgressRegistry, DownloadState,
+};
+use anyhow::Context;
+use reqwest::header::{ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::{Client, Response, StatusCode};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tokio::fs::{metadata, read_to_string, remove_file, rename, write, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, instrument, warn, Instrument};
+
+/// Streams the file at `download_url` to `destination_path`.
+///
+/// Data is written in chunks to a `.part` file next to the destination, which is atomically renamed
+/// once the download completes. A partial file left behind by an interrupted download is resumed
+/// using an HTTP Range request, guarded by the ETag or Last-Modified validator stored next to it.
+/// Progress is reported in `progress_registry` under `progress_key`.
+/// Downloads larger than `max_bytes` fail with a [`RequestError`] and their partial file is removed.
+/// A redirect stopped by the redirect policy of `http_client` fails with the [`RequestError`] of the policy.
+#[instrument(err, ret(level = "debug"), skip(http_client, progress_registry))]
+pub async fn download_file_with_resume(
+    http_client: &Client,
+    download_url: &str,
+    destination_path: &PathBuf,
+    progress_registry: &DownloadProgressRegistry,
+    progress_key: &str,
+    max_bytes: Option<u64>,
+) -> Result<u64, anyhow::Error> {
+    {
+        let mut registry = progress_registry.write().await;
+        if registry
+            .get(progress_key)
+            .is_some_and(|progress| progress.state == DownloadState::InProgress)
+        {
+            return Err(RequestError::new(
+                ErrorCode::DownloadInProgress,
+                format!("Download is already in progress: {}", progress_key),
+            )
+            .into());
+        }
+        registry.insert(
+            progress_key.to_string(),
+            DownloadProgress {
+                download_url: download_url.to_string(),
+                state: DownloadState::InProgress,
+                downloaded_bytes: 0,
+                total_bytes: None,
+                resumed_from_bytes: 0,
+                error: None,
+            },
+        );
+    }
+
+    // The download runs in its own task, so it still finishes and leaves the in progress state
+    // when the request that started it is cancelled, for example because the client disconnected
+    let download_task = tokio::spawn(
+        track_download(
+            http_client.clone(),
+            download_url.to_string(),
+            destination_path.clone(),
+            progress_registry.clone(),
+            progress_key.to_string(),
+            max_bytes,
+        )
+        .in_current_span(),
+    );
+
+    download_task.await.context("Download task failed")?
+}
+
+/// Streams the file and records the outcome of the download in its progress entry.
+async fn track_download(
+    http_client: Client,
+    download_url: String,
+    destination_path: PathBuf,
+    progress_registry: DownloadProgressRegistry,
+    progress_key: String,
+    max_bytes: Option<u64>,
+) -> Result<u64, anyhow::Error> {
+    let download_result = stream_to_file(
+        &http_client,
+        &download_url,
+        &destination_path,
+        &progress_registry,
+        &progress_key,
+        max_bytes,
+    )
+    .await;
+
+    let mut registry = progress_registry.write().await;
+    if let Some(progress) = registry.get_mut(&progress_key) {
+        match &download_result {
+            Ok(_) => progress.state = DownloadState::Completed,
+            Err(err) => {
+                progress.state = DownloadState::Failed;
+                progress.error = Some(format!("{:#}", err));
+            }
+        }
+    }
+
+    download_result
+}
+
+#[instrument(err, ret(level = "debug"), skip(http_client, progress_registry))]
+async fn stream_to_file(
+    http_client: &Client,
+    download_url: &str,
+    destination_path: &PathBuf,
+    progress_registry: &DownloadProgressRegistry,
+    progress_key: &str,
+    max_bytes: Option<u64>,
+) -> Result<u64, anyhow::Error> {
+    let partial_file_path = get_partial_file_path(destination_path);
+    let validator_file_path = get_validator_file_path(&partial_file_path);
+
+    let partial_file_bytes = match metadata(&partial_file_path).await {
+        Ok(partial_file_metadata) => partial_file_metadata.len(),
+        Err(_) => 0,
+    };
+    // Without the validator of the partial file there is no way to tell whether the file on the server
+    // is still the same, for example for latest builds, so the download starts over
+    let resume_validator = match partial_file_bytes {
+        0 => None,
+        _ => read_to_string(&validator_file_path)
+            .await
+            .ok()
+            .filter(|validator| !validator.is_empty()),
+    };
+    let mut resume_from_bytes = match resume_validator {
+        Some(_) => partial_file_bytes,
+        None => {
+            if partial_file_bytes > 0 {
+                warn!("Partial download has no validator, starting from the beginning");
+            }
+            0
+        }
+    };
+
+    let mut resp = send_download_request(
+        http_client,
+        download_url,
+        resume_from_bytes,
+        resume_validator.as_deref(),
+    )
+    .await?;
+
+    if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        warn!("Server rejected the resume request, discarding partial file and starting over");
+        remove_file(&partial_file_path)
+            .await
+            .context("Failed to remove partial download file")?;
+        resume_from_bytes = 0;
+        resp = send_download_request(http_client, download_url, resume_from_bytes, None).await?;
+    }
+
+    let mut resp = resp
+        .error_for_status()
+        .context("Server returned an error status for the download request")?;
+
+    let is_resumed = resume_from_bytes > 0 && resp.status() == StatusCode::PARTIAL_CONTENT;
+    if resume_from_bytes > 0 && !is_resumed {
+        warn!("File changed on the server or the server does not support resuming downloads, starting from the beginning");
+        resume_from_bytes = 0;
+    }
+    if !is_resumed {
+        write_resume_validator(&validator_file_path, &resp).await?;
+    }
+
+    let total_bytes = resp
+        .content_length()
+        .map(|content_length| content_length + resume_from_bytes);
+    if let (Some(total_bytes), Some(max_bytes)) = (total_bytes, max_bytes) {
+        if total_bytes > max_bytes {
+            return Err(discard_oversized_download(&partial_file_path, max_bytes).await);
+        }
+    }
+
+    info!(
+        "Streaming download to {}, resuming from byte {}, total bytes: {:?}",
+        partial_file_path.display(),
+        resume_from_bytes,
+        total_bytes
+    );
+
+    let mut open_options = OpenOptions::new();
+    open_options.create(true);
+    if is_resumed {
+        open_options.append(true);
+    } else {
+        open_options.write(true).truncate(true);
+    }
+    let mut file = open_options
+        .open(&partial_file_path)
+        .await
+        .context("Failed to open partial download file")?;
+
+    let mut downloaded_bytes = resume_from_bytes;
+    update_download_progress(progress_registry, progress_key, |progress| {
+        progress.downloaded_bytes = downloaded_bytes;
+        progress.total_bytes = total_bytes;
+        progress.resumed_from_bytes = resume_from_bytes;
+    })
+    .await;
+
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .context("Failed to read chunk of the response body")?
+    {
+        downloaded_bytes += chunk.len() as u64;
+        if let Some(max_bytes) = max_bytes.filter(|max_bytes| downloaded_bytes > *max_bytes) {
+            drop(file);
+            return Err(discard_oversized_download(&partial_file_path, max_bytes).await);
+        }
+        file.write_all(&chunk)
+            .await
+            .context("Failed to write chunk to partial download file")?;
+        update_download_progress(progress_registry, progress_key, |progress| {
+            progress.downloaded_bytes = downloaded_bytes;
+        })
+        .await;
+    }
+
+    file.flush()
+        .await
+        .context("Failed to flush partial download file")?;
+    file.sync_all()
+        .await
+        .context("Failed to sync partial download file to disk")?;
+    drop(file);
+
+    if let Some(total_bytes) = total_bytes {
+        if downloaded_bytes != total_bytes {
+            anyhow::bail!(
+                "Download incomplete, expected {} bytes but got {}. The partial file is kept so the download can be resumed",
+                total_bytes,
+                downloaded_bytes
+            );
+        }
+    }
+
+    debug!(
+        "Renaming partial download file {} -> {}",
+        partial_file_path.display(),
+        destination_path.display()
+    );
+    rename(&partial_file_path, destination_path)
+        .await
+        .context("Failed to move completed download into place")?;
+    remove_resume_validator(&validator_file_path).await;
+
+    info!("Download completed, {} bytes written", downloaded_bytes);
+
+    Ok(downloaded_bytes)
+}
+
+#[instrument(err, skip(http_client))]
+async fn send_download_request(
+    http_client: &Client,
+    download_url: &str,
+    resume_from_bytes: u64,
+    resume_validator: Option<&str>,
+) -> Result<Response, anyhow::Error> {
+    let mut request = http_client.get(download_url);
+    if let Some(resume_validator) = resume_validator.filter(|_| resume_from_bytes > 0) {
+        info!(
+            "Found partial download, requesting remaining bytes from byte {}",
+            resume_from_bytes
+        );
+        // With If-Range the server sends the whole file instead of the range if the file changed since the partial download
+        request = request
+            .header(RANGE, format!("bytes={}-", resume_from_bytes))
+            .header(IF_RANGE, resume_validator);
+    }
+
+    request
+        .send()
+        .await
+        .map_err(|err| match get_denied_redirect_error(&err) {
+            Some(request_error) => request_error.into(),
+            None => anyhow::Error::new(err).context(format!(
+                "Error sending download request to {}",
+                download_url
+            )),
+        })
+}
+
+/// Removes the partial file of a download that exceeded the size limit, so it is not resumed later.
+async fn discard_oversized_download(partial_file_path: &Path, max_bytes: u64) -> anyhow::Error {
+    if let Err(err) = remove_file(partial_file_path).await {
+        warn!(
+            "Failed to remove partial file of oversized download: {}",
+            err
+        );
+    }
+    remove_resume_validator(&get_validator_file_path(partial_file_path)).await;
+
+    RequestError::new(
+        ErrorCode::DownloadTooLarge,
+        format!(
+            "File is larger than the maximum allowed size of {} bytes",
+            max_bytes
+        ),
+    )
+    .into()
+}
+
+async fn update_download_progress(
+    progress_registry: &DownloadProgressRegistry,
+    progress_key: &str,
+    update: impl FnOnce(&mut DownloadProgress),
+) {
+    if let Some(progress) = progress_registry.write().await.get_mut(progress_key) {
+        update(progress);
+    }
+}
+
+/// Stores the validator of the downloaded file next to the partial file, so a resumed download can be checked
+/// to continue the same file. Strong ETags are preferred, weak ETags cannot be used with If-Range.
+async fn write_resume_validator(
+    validator_file_path: &Path,
+    resp: &Response,
+) -> Result<(), anyhow::Error> {
+    let resume_validator = resp
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| {
+            resp.headers()
+                .get(LAST_MODIFIED)
+                .and_then(|last_modified| last_modified.to_str().ok())
+        });
+
+    match resume_validator {
+        Some(resume_validator) => write(validator_file_path, resume_validator)
+            .await
+            .context("Failed to write validator of partial download file"),
+        None => {
+            debug!("Server did not send a validator, an interrupted download will start over");
+            remove_resume_validator(validator_file_path).await;
+            Ok(())
+        }
+    }
+}
+
+async fn remove_resume_validator(validator_file_path: &Path) {
+    if let Err(err) = remove_file(validator_file_path).await {
+        if err.kind() != ErrorKind::NotFound {
+            warn!(
+                "Failed to remove validator of partial download file: {}",
+                err
+            );
+        }
+    }
+}
+
+fn get_validator_file_path(partial_file_path: &Path) -> PathBuf {
+    let mut validator_file_path = partial_file_path.as_os_str().to_owned();
+    validator_file_path.push(".validator");
+    PathBuf::from(validator_file_path)
+}
+
+fn get_partial_file_path(destination_path: &Path) -> PathBuf {
+    let mut partial_file_path = destination_path.as_os_str().to_owned();
+    partial_file_path.push(".part");
+    PathBuf::from(partial_file_path)
+}
@@ -1,9 +1,15 @@
+use crate::handlers::errors::{ErrorCode, RequestError};
 use anyhow::Context;
+use flate2::read::GzDecoder;
 use std::fs::File;
-use std::path::PathBuf;
-use tokio::fs::{read_dir, remove_dir_all, rename};
+use std::io::ErrorKind;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use tar::Archive;
+use tokio::fs::{copy, read_dir, remove_dir_all, remove_file, rename, OpenOptions};
 use tokio::task::spawn_blocking;
 use tracing::{info, instrument};
+use xz2::read::XzDecoder;
 use zip::ZipArchive;
 
 #[instrument(err, ret(level = "debug"))]
@@ -11,8 +17,8 @@ pub async fn decompress_file(
     input_path: &PathBuf,
     output_path: &PathBuf,
 ) -> Result<(), anyhow::Error> {
-    match input_path.extension().and_then(|ext| ext.to_str()) {
-        Some("zip") => {
+    match get_archive_kind(input_path) {
+        Some(ArchiveKind::Zip) => {
             info!("Decompressing file as a ZIP archive");
 
             unzip_file(input_path, output_path)
@@ -21,16 +27,93 @@ pub async fn decompress_file(
 
             Ok(())
         }
-        // TODO: Support more archive extensions
-        Some(ext) => {
-            anyhow::bail!("Unsupported file extension: {}", ext)
+        Some(ArchiveKind::SevenZip) => {
+            info!("Decompressing file as a 7z archive");
+
+            un7z_file(input_path, output_path)
+                .await
+                .context("Failed to extract 7z archive")?;
+
+            Ok(())
+        }
+        Some(archive_kind) => {
+            info!("Decompressing file as a {:?} archive", archive_kind);
+
+            untar_file(input_path, output_path, archive_kind)
+                .await
+                .context("Failed to extract TAR archive")?;
+
+            Ok(())
         }
         None => {
-            anyhow::bail!("Unable to extract archive. File has no extension")
+            anyhow::bail!("Unsupported archive format: {}", input_path.display())
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    /// Used by the macOS ffmpeg builds.
+    SevenZip,
+}
+
+/// Detects archive type based on the file name. Compound extensions like `.tar.xz` are taken into account.
+pub fn get_archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+
+    if file_name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if file_name.ends_with(".tar.xz") || file_name.ends_with(".txz") {
+        Some(ArchiveKind::TarXz)
+    } else if file_name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if file_name.ends_with(".7z") {
+        Some(ArchiveKind::SevenZip)
+    } else {
+        None
+    }
+}
+
+#[instrument(err, ret(level = "debug"))]
+async fn untar_file(
+    input_path: &PathBuf,
+    output_path: &PathBuf,
+    archive_kind: ArchiveKind,
+) -> Result<(), anyhow::Error> {
+    let input_path = input_path.clone();
+    let output_path = output_path.clone();
+
+    spawn_blocking(move || -> Result<(), anyhow::Error> {
+        info!("Opening TAR file for reading");
+        let tar_file = File::open(input_path).context("Unable to open TAR archive file")?;
+
+        let reader: Box<dyn Read> = match archive_kind {
+            ArchiveKind::TarGz => Box::new(GzDecoder::new(tar_file)),
+            ArchiveKind::TarXz => Box::new(XzDecoder::new(tar_file)),
+            _ => Box::new(tar_file),
+        };
+
+        let mut archive = Archive::new(reader);
+        archive.set_preserve_permissions(true);
+        archive
+            .unpack(output_path)
+            .context("Failed to extract TAR archive")?;
+
+        Ok(())
+    })
+    .await
+    .context("Error occurred while decompressing TAR archive")?
+    .context("Failed to execute TAR extraction task")?;
+
+    Ok(())
+}
+
 #[instrument(err, ret(level = "debug"))]
 async fn unzip_file(input_path: &PathBuf, output_path: &PathBuf) -> Result<(), anyhow::Error> {
     let input_path = input_path.clone();
@@ -54,6 +137,42 @@ async fn unzip_file(input_path: &PathBuf, output_path: &PathBuf) -> Result<(), a
     Ok(())
 }
 
+#[instrument(err, ret(level = "debug"))]
+async fn un7z_file(input_path: &PathBuf, output_path: &PathBuf) -> Result<(), anyhow::Error> {
+    let input_path = input_path.clone();
+    let output_path = output_path.clone();
+
+    spawn_blocking(move || -> Result<(), anyhow::Error> {
+        info!("Opening 7z file for reading");
+        sevenz_rust::decompress_file_with_extract_fn(
+            input_path,
+            output_path,
+            |entry, reader, entry_path| {
+                // Entry paths are joined to the output directory as they are, so paths leaving it are rejected
+                let is_enclosed = Path::new(entry.name())
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)));
+                if !is_enclosed {
+                    return Err(sevenz_rust::Error::other(format!(
+                        "Archive entry is outside of the output directory: {}",
+                        entry.name()
+                    )));
+                }
+
+                sevenz_rust::default_entry_extract_fn(entry, reader, entry_path)
+            },
+        )
+        .context("Failed to extract 7z archive")?;
+
+        Ok(())
+    })
+    .await
+    .context("Error occurred while decompressing 7z archive")?
+    .context("Failed to execute 7z extraction task")?;
+
+    Ok(())
+}
+
 #[instrument(err, ret(level = "debug"))]
 pub async fn search_and_move_binaries(
     current_dir: &PathBuf,
@@ -67,6 +186,65 @@ pub async fn search_and_move_binaries(
         minimum_moved_binaries_for_success
     );
 
+    let found_binaries = find_binaries(current_dir, binaries_to_move).await?;
+    check_found_binaries(
+        current_dir,
+        &found_binaries,
+        minimum_moved_binaries_for_success,
+    )?;
+
+    for source in found_binaries {
+        let file_name = source
+            .file_name()
+            .context("Failed to get binary file name")?;
+        rename(&source, destination.join(file_name))
+            .await
+            .context(format!("Failed to move binary: {}", source.display()))?;
+        info!("Binary {} moved successfully", source.display());
+    }
+
+    Ok(())
+}
+
+/// Same as `search_and_move_binaries`, but leaves the source directory untouched.
+#[instrument(err, ret(level = "debug"))]
+pub async fn search_and_copy_binaries(
+    current_dir: &PathBuf,
+    destination: &PathBuf,
+    binaries_to_copy: &[&str],
+    minimum_copied_binaries_for_success: usize,
+) -> Result<(), anyhow::Error> {
+    info!(
+        "Searching for binaries to copy: {}, required number of binaries to be copied: {}",
+        binaries_to_copy.join(", "),
+        minimum_copied_binaries_for_success
+    );
+
+    let found_binaries = find_binaries(current_dir, binaries_to_copy).await?;
+    check_found_binaries(
+        current_dir,
+        &found_binaries,
+        minimum_copied_binaries_for_success,
+    )?;
+
+    for source in found_binaries {
+        let file_name = source
+            .file_name()
+            .context("Failed to get binary file name")?;
+        copy(&source, destination.join(file_name))
+            .await
+            .context(format!("Failed to copy binary: {}", source.display()))?;
+        info!("Binary {} copied successfully", source.display());
+    }
+
+    Ok(())
+}
+
+#[instrument(err, ret(level = "debug"))]
+async fn find_binaries(
+    current_dir: &PathBuf,
+    binaries_to_find: &[&str],
+) -> Result<Vec<PathBuf>, anyhow::Error> {
     let mut dirs_to_search = vec![current_dir.clone()];
     let mut found_binaries = Vec::new();
 
@@ -88,45 +266,50 @@ pub async fn search_and_move_binaries(
             {
                 dirs_to_search.push(entry.path());
             } else if let Some(file_name) = entry.file_name().to_str() {
-                if binaries_to_move.contains(&file_name) {
-                    let source = entry.path();
-                    let dest = destination.join(file_name);
-                    rename(&source, &dest)
-                        .await
-                        .context(format!("Failed to move binary: {}", file_name))?;
-                    info!("Binary {} moved successfully", file_name);
-                    found_binaries.push(file_name.to_owned());
+                if binaries_to_find.contains(&file_name) {
+                    found_binaries.push(entry.path());
                 }
             }
         }
     }
 
+    Ok(found_binaries)
+}
+
+fn check_found_binaries(
+    current_dir: &Path,
+    found_binaries: &[PathBuf],
+    minimum_binaries_for_success: usize,
+) -> Result<(), anyhow::Error> {
+    let found_binary_names = found_binaries
+        .iter()
+        .map(|binary| binary.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
     if found_binaries.is_empty() {
         anyhow::bail!(
             "No binaries found in directory: {} and subdirectories",
             current_dir.display()
         );
-    } else if found_binaries.len() >= minimum_moved_binaries_for_success {
-        info!(
-            "Found and moved all required binaries: {}",
-            found_binaries.join(", ")
-        );
+    } else if found_binaries.len() >= minimum_binaries_for_success {
+        info!("Found all required binaries: {}", found_binary_names);
     } else {
-        anyhow::bail!("Moved some binaries but not all required: {}, required number of binaries moved: {}, actual moved: {}",found_binaries.join(", "), minimum_moved_binaries_for_success, found_binaries.len());
+        anyhow::bail!("Found some binaries but not all required: {}, required number of binaries: {}, actual found: {}", found_binary_names, minimum_binaries_for_success, found_binaries.len());
     }
 
     Ok(())
 }
 
 #[instrument(err, ret(level = "debug"))]
-pub async fn search_and_move_media_file(
+pub async fn search_and_move_media_files(
     source_dir: &PathBuf,
     destination_dir: &PathBuf,
     search_prefix: &str,
     strip_prefix: bool,
-) -> Result<(), anyhow::Error> {
+) -> Result<Vec<String>, anyhow::Error> {
     info!(
-        "Searching for media file to move with prefix: {}",
+        "Searching for media files to move with prefix: {}",
         search_prefix
     );
 
@@ -134,6 +317,7 @@ pub async fn search_and_move_media_file(
         .await
         .context("Failed to read directory entries")?;
 
+    let mut moved_file_names = Vec::new();
     while let Some(entry) = entries
         .next_entry()
         .await
@@ -149,24 +333,34 @@ pub async fn search_and_move_media_file(
             } else {
                 &file_name
             };
-            let dest_path = destination_dir.join(dest_file_name);
+            let dest_path = destination_dir.join(sanitize_file_name(dest_file_name));
             info!(
                 "Moving media file: {} -> {}",
                 source_path.display(),
                 dest_path.to_string_lossy().to_string()
             );
-            rename(&source_path, &dest_path)
+            let dest_path = move_file_without_overwriting(&source_path, &dest_path)
                 .await
                 .context(format!("Failed to move media file: {}", file_name))?;
             info!("Media file moved successfully");
-            return Ok(());
+            moved_file_names.push(
+                dest_path
+                    .file_name()
+                    .context("Moved file has no file name")?
+                    .to_string_lossy()
+                    .to_string(),
+            );
         }
     }
 
-    anyhow::bail!(
-        "No matching file found that starts with prefix: {}",
-        search_prefix
-    )
+    if moved_file_names.is_empty() {
+        anyhow::bail!(
+            "No matching file found that starts with prefix: {}",
+            search_prefix
+        )
+    }
+
+    Ok(moved_file_names)
 }
 
 #[instrument(err, ret(level = "debug"))]
@@ -209,20 +403,356 @@ pub async fn remove_subdirectories_with_prefix(
 
 #[cfg(not(target_os = "windows"))]
 #[instrument(err, ret(level = "debug"))]
-async fn set_executable_permissions(file: &File) -> Result<(), anyhow::Error> {
+pub async fn set_executable_permissions(file_path: &PathBuf) -> Result<(), anyhow::Error> {
     use std::os::unix::fs::PermissionsExt;
+    use tokio::fs::{metadata, set_permissions};
 
     info!("Setting executable permissions for the file");
 
-    let mut perms = file
-        .metadata()
+    let mut perms = metadata(file_path)
         .await
         .context("Failed to get file metadata")?
         .permissions();
     perms.set_mode(0o755);
-    file.set_permissions(perms)
+    set_permissions(file_path, perms)
         .await
         .context("Failed to set executable permissions")?;
 
     Ok(())
 }
+
+/// Joins a file name received from a request with a library directory. The name can be a relative path such as `Artist/Album/Title.mp3`.
+/// Rejects absolute paths and paths with `.` or `..` components so files outside the library cannot be accessed.
+#[instrument(err, ret(level = "debug"))]
+pub fn get_library_file_path(
+    library_dir: &Path,
+    library_file_name: &str,
+) -> Result<PathBuf, anyhow::Error> {
+    let mut library_file_path = library_dir.to_path_buf();
+    for component in Path::new(library_file_name).components() {
+        match component {
+            Component::Normal(name) => library_file_path.push(name),
+            _ => return Err(get_invalid_library_file_name_error(library_file_name).into()),
+        }
+    }
+
+    if library_file_path == library_dir {
+        return Err(get_invalid_library_file_name_error(library_file_name).into());
+    }
+
+    Ok(library_file_path)
+}
+
+/// Checks that a file received in a request exists, so requests for missing files fail with [`ErrorCode::FileNotFound`] instead of a server error.
+#[instrument(err)]
+pub async fn check_file_exists(file_path: &Path) -> Result<(), anyhow::Error> {
+    match tokio::fs::metadata(file_path).await {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Err(RequestError::new(
+            ErrorCode::FileNotFound,
+            format!("File does not exist: {}", file_path.display()),
+        )
+        .into()),
+        Err(err) => Err(anyhow::Error::new(err).context(format!(
+            "Failed to get metadata of the file: {}",
+            file_path.display()
+        ))),
+    }
+}
+
+fn get_invalid_library_file_name_error(library_file_name: &str) -> RequestError {
+    RequestError::new(
+        ErrorCode::InvalidRequest,
+        format!("Invalid library file name: {}", library_file_name),
+    )
+}
+
+/// Converts a path inside a library directory to the library file name used by the API and the library index, for example `Artist/Title.mp3`.
+pub fn to_library_file_name(library_dir: &Path, path: &Path) -> Result<String, anyhow::Error> {
+    let relative_path = path
+        .strip_prefix(library_dir)
+        .context("Path is outside the library directory")?;
+
+    Ok(relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Maximum length of a single file or directory name in bytes. Most file systems allow 255 bytes, some room is left for collision suffixes.
+pub const MAX_FILE_NAME_BYTES: usize = 200;
+/// Name used when nothing is left of a file name after sanitization.
+const EMPTY_FILE_NAME_REPLACEMENT: &str = "untitled";
+/// Names reserved by Windows regardless of the extension.
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Makes a single file or directory name safe on Linux, macOS and Windows.
+/// Path separators and characters reserved by Windows are replaced, emoji and control characters are removed,
+/// whitespace is collapsed and the name is limited to `MAX_FILE_NAME_BYTES` keeping the extension.
+pub fn sanitize_file_name(file_name: &str) -> String {
+    let mut sanitized_file_name = String::with_capacity(file_name.len());
+    for c in file_name.chars() {
+        match c {
+            '/' | '\\' | '|' => sanitized_file_name.push('-'),
+            ':' => sanitized_file_name.push_str(" -"),
+            '"' => sanitized_file_name.push('\''),
+            '*' | '?' | '<' | '>' => {}
+            c if c.is_control() || is_emoji(c) => {}
+            c if c.is_whitespace() => sanitized_file_name.push(' '),
+            c => sanitized_file_name.push(c),
+        }
+    }
+
+    let mut sanitized_file_name = sanitized_file_name
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c == '.' || c == ' ')
+        .to_string();
+
+    if sanitized_file_name.is_empty() {
+        sanitized_file_name = EMPTY_FILE_NAME_REPLACEMENT.to_string();
+    }
+
+    let stem = sanitized_file_name
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .trim_end();
+    if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved_name| reserved_name.eq_ignore_ascii_case(stem))
+    {
+        sanitized_file_name.insert(stem.len(), '_');
+    }
+
+    truncate_file_name(&sanitized_file_name, MAX_FILE_NAME_BYTES)
+}
+
+/// Shortens a file name to at most `max_bytes` bytes without splitting characters. The extension is kept if it is short.
+pub fn truncate_file_name(file_name: &str, max_bytes: usize) -> String {
+    if file_name.len() <= max_bytes {
+        return file_name.to_string();
+    }
+
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && extension.len() < max_bytes / 4 => {
+            (stem, format!(".{}", extension))
+        }
+        _ => (file_name, String::new()),
+    };
+
+    let mut truncated_stem_len = max_bytes - extension.len();
+    while !stem.is_char_boundary(truncated_stem_len) {
+        truncated_stem_len -= 1;
+    }
+
+    format!("{}{}", stem[..truncated_stem_len].trim_end(), extension)
+}
+
+/// Pictographs, symbols and the joiners used to combine them into a single emoji.
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0xFE00..=0xFE0F | 0x200D | 0xE0020..=0xE007F
+    )
+}
+
+/// Finds a path that does not exist yet by adding ` (1)`, ` (2)`, ... before the extension, and reserves it by creating an empty file.
+/// Reserving the path makes concurrent moves to the same name pick different paths.
+#[instrument(err, ret(level = "debug"))]
+pub async fn reserve_free_file_path(path: &Path) -> Result<PathBuf, anyhow::Error> {
+    let file_name = path
+        .file_name()
+        .context("Path has no file name")?
+        .to_string_lossy()
+        .to_string();
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => {
+            (stem.to_string(), format!(".{}", extension))
+        }
+        _ => (file_name.clone(), String::new()),
+    };
+
+    for collision_index in 0u32.. {
+        let candidate_path = if collision_index == 0 {
+            path.to_path_buf()
+        } else {
+            path.with_file_name(format!("{} ({}){}", stem, collision_index, extension))
+        };
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate_path)
+            .await
+        {
+            Ok(_) => return Ok(candidate_path),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(err).context(format!(
+                    "Failed to reserve file path: {}",
+                    candidate_path.display()
+                ))
+            }
+        }
+    }
+
+    unreachable!("Collision suffixes are unbounded")
+}
+
+/// Moves a file to `destination_path`, or to a suffixed path if the destination exists. Existing files are never overwritten.
+/// Returns the path the file was moved to.
+#[instrument(err, ret(level = "debug"))]
+pub async fn move_file_without_overwriting(
+    source_path: &Path,
+    destination_path: &Path,
+) -> Result<PathBuf, anyhow::Error> {
+    let destination_path = reserve_free_file_path(destination_path).await?;
+    if let Err(err) = rename(source_path, &destination_path).await {
+        let _ = remove_file(&destination_path).await;
+        return Err(err).context(format!(
+            "Failed to move file: {} -> {}",
+            source_path.display(),
+            destination_path.display()
+        ));
+    }
+
+    Ok(destination_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_archive_kind_from_compound_extensions() {
+        assert_eq!(
+            get_archive_kind(Path::new("ffmpeg.tar.xz")),
+            Some(ArchiveKind::TarXz)
+        );
+        assert_eq!(
+            get_archive_kind(Path::new("chromaprint.TGZ")),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(
+            get_archive_kind(Path::new("ffmpeg.7z")),
+            Some(ArchiveKind::SevenZip)
+        );
+        assert_eq!(get_archive_kind(Path::new("yt-dlp")), None);
+    }
+
+    #[test]
+    fn sanitizes_file_names() {
+        assert_eq!(sanitize_file_name("AC/DC: Live?"), "AC-DC - Live");
+        assert_eq!(sanitize_file_name("..\\windows\\path"), "-windows-path");
+        assert_eq!(sanitize_file_name("say \"hi\" <now> *"), "say 'hi' now");
+        assert_eq!(
+            sanitize_file_name("Song \u{1F3B5} Title.mp3"),
+            "Song Title.mp3"
+        );
+        assert_eq!(
+            sanitize_file_name("bell\u{7}  and   spaces"),
+            "bell and spaces"
+        );
+        assert_eq!(sanitize_file_name("  ..hidden..  "), "hidden");
+        assert_eq!(sanitize_file_name("???"), EMPTY_FILE_NAME_REPLACEMENT);
+        assert_eq!(sanitize_file_name(".."), EMPTY_FILE_NAME_REPLACEMENT);
+    }
+
+    #[test]
+    fn renames_windows_reserved_names() {
+        assert_eq!(sanitize_file_name("CON.mp3"), "CON_.mp3");
+        assert_eq!(sanitize_file_name("lpt1"), "lpt1_");
+        assert_eq!(sanitize_file_name("Console.mp3"), "Console.mp3");
+    }
+
+    #[test]
+    fn limits_sanitized_file_name_length() {
+        let sanitized_file_name = sanitize_file_name(&format!("{}.mp3", "a".repeat(300)));
+
+        assert_eq!(sanitized_file_name.len(), MAX_FILE_NAME_BYTES);
+        assert!(sanitized_file_name.ends_with("a.mp3"));
+    }
+
+    #[test]
+    fn truncates_file_names_keeping_short_extensions() {
+        assert_eq!(truncate_file_name("short.mp3", 200), "short.mp3");
+        assert_eq!(
+            truncate_file_name(&format!("{}.flac", "a".repeat(50)), 40),
+            format!("{}.flac", "a".repeat(35))
+        );
+        // Extensions of a quarter of the limit or longer are treated as part of the name
+        assert_eq!(
+            truncate_file_name(&format!("name.{}", "x".repeat(20)), 12),
+            "name.xxxxxxx"
+        );
+    }
+
+    #[test]
+    fn truncates_file_names_without_splitting_characters() {
+        let truncated_file_name = truncate_file_name(&format!("{}.flac", "é".repeat(150)), 200);
+
+        assert_eq!(truncated_file_name, format!("{}.flac", "é".repeat(97)));
+    }
+
+    #[test]
+    fn joins_library_file_names_with_the_library_dir() {
+        let library_dir = Path::new("/srv/music");
+
+        assert_eq!(
+            get_library_file_path(library_dir, "Artist/Album/Title.mp3").unwrap(),
+            library_dir.join("Artist").join("Album").join("Title.mp3")
+        );
+        assert_eq!(
+            get_library_file_path(library_dir, "Title..mp3").unwrap(),
+            library_dir.join("Title..mp3")
+        );
+    }
+
+    #[test]
+    fn rejects_library_file_names_outside_the_library() {
+        let library_dir = Path::new("/srv/music");
+
+        for library_file_name in [
+            "",
+            ".",
+            "..",
+            "../secret.mp3",
+            "Artist/../../secret.mp3",
+            "./Title.mp3",
+            "/etc/passwd",
+        ] {
+            let err = get_library_file_path(library_dir, library_file_name).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<RequestError>().map(|err| err.code),
+                Some(ErrorCode::InvalidRequest),
+                "{:?} should be rejected",
+                library_file_name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn extracts_7z_archives() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_dir = temp_dir.path().join("source");
+        std::fs::create_dir(&source_dir).unwrap();
+        std::fs::write(source_dir.join("ffmpeg"), b"binary").unwrap();
+        let archive_path = temp_dir.path().join("ffmpeg.7z");
+        sevenz_rust::compress_to_path(&source_dir, &archive_path).unwrap();
+
+        let output_path = temp_dir.path().join("output");
+        decompress_file(&archive_path, &output_path).await.unwrap();
+
+        assert_eq!(
+            std::fs::read(output_path.join("ffmpeg")).unwrap(),
+            b"binary"
+        );
+    }
+}
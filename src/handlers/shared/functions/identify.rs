@@ -0,0 +1,180 @@
+use crate::handlers::errors::{ErrorCode, RequestError};
+use crate::handlers::shared::functions::commands::run_command;
+use crate::handlers::shared::functions::tools::get_chromaprint_fpcalc_executable_path;
+use crate::handlers::shared::model::acoustid::AcoustIDApiLookupResponse;
+use crate::handlers::shared::model::identify::{
+    AudioIdentification, FpcalcFingerprintingResult, IdentifiedMetadata,
+};
+use crate::handlers::shared::model::musicbrainz::MusicbrainzAPIRecordingResponse;
+use crate::AppState;
+use anyhow::Context;
+use reqwest::header::USER_AGENT;
+use std::cmp::Ordering;
+use std::path::Path;
+use tracing::{error, info, instrument};
+
+pub const ACOUSTID_API_URL: &str = "https://api.acoustid.org/v2/lookup";
+pub const MUSICBRAINZ_API_URL: &str = "https://musicbrainz.org/ws/2";
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+const APP_REPOSITORY_URL: &str = env!("CARGO_PKG_REPOSITORY");
+
+/// Fingerprints the audio file with chromaprint's fpcalc and looks up the best match in AcoustID and MusicBrainz.
+/// If fingerprinting fails, only the fpcalc command results are returned.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn identify_audio_file(
+    app_state: &AppState,
+    audio_file_path: &Path,
+) -> Result<AudioIdentification, anyhow::Error> {
+    let fpcalc_executable_path = get_chromaprint_fpcalc_executable_path(app_state)
+        .await
+        .context("Failed to get chromaprint's fpcalc executable path")?;
+
+    info!(
+        "Fingerprinting audio file with chromaprint's fpcalc: {}",
+        audio_file_path.display()
+    );
+
+    let command_execution_results = run_command(
+        &fpcalc_executable_path,
+        &["-json", &audio_file_path.to_string_lossy()],
+    )
+    .await
+    .context("Failed to fingerprint audio with chromaprint's fpcalc")?;
+
+    if !command_execution_results.command_completed_successfully {
+        error!("Failed to fingerprint audio with chromaprint's fpcalc");
+        return Ok(AudioIdentification {
+            musicbrainz_response: None,
+            acoustid_response: None,
+            fpcalc_fingerprint: None,
+            fingerprinting_command_result: command_execution_results,
+        });
+    }
+
+    info!("Parsing fingerprinting results as JSON");
+    let fingerprinting_result: FpcalcFingerprintingResult = serde_json::from_str(
+        &command_execution_results
+            .stdout
+            .clone()
+            .context("Failed to use stdout from fpcalc as serde_json input")?,
+    )
+    .context("JSON parsing failed")?;
+
+    info!("Querying AcoustID API for track identification information");
+    let resp = app_state
+        .http_client
+        .get(ACOUSTID_API_URL)
+        .query(&[
+            ("client", "IVmzA2lk9AQ"), // TODO: Proper API key goes here
+            ("meta", "recordingids"),
+            (
+                "duration",
+                // TODO: Is trunc here correct?
+                &fingerprinting_result.duration.trunc().to_string(),
+            ),
+            ("fingerprint", &fingerprinting_result.fingerprint),
+        ])
+        .send()
+        .await
+        .context("Error sending request to AcoustID API")?
+        .error_for_status()
+        .context("AcoustID API returned an error status")?;
+
+    let acoustid_response: AcoustIDApiLookupResponse = resp
+        .json()
+        .await
+        .context("Failed to parse AcoustID API response as JSON")?;
+
+    // TODO: Handle multiple IDs better?
+    let best_result = acoustid_response
+        .results
+        .iter()
+        .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal))
+        .ok_or_else(|| {
+            RequestError::new(
+                ErrorCode::UnprocessableMedia,
+                "No match found for the audio fingerprint in AcoustID",
+            )
+        })?;
+
+    info!(
+        "Best AcoustID match: {} with score {}",
+        best_result.id, best_result.score
+    );
+
+    let recording_id = best_result
+        .recordings
+        .first()
+        .map(|rec| &rec.id)
+        .ok_or_else(|| {
+            RequestError::new(
+                ErrorCode::UnprocessableMedia,
+                "No recording ID found in the best AcoustID match",
+            )
+        })?;
+
+    info!("Querying MusicBrainz API for track identification information");
+
+    let musicbrainz_user_agent =
+        format!("Ferrous Beats/{} ( {} )", APP_VERSION, APP_REPOSITORY_URL);
+    info!("Using MusicBrainz User-Agent: {}", musicbrainz_user_agent);
+
+    let resp = app_state
+        .http_client
+        .get(format!(
+            "{}/recording/{}",
+            MUSICBRAINZ_API_URL, recording_id
+        ))
+        .header(USER_AGENT, musicbrainz_user_agent)
+        .query(&[("fmt", "json"), ("inc", "artists+releases")])
+        .send()
+        .await
+        .context("Error sending request to MusicBrainz API")?
+        .error_for_status()
+        .context("MusicBrainz API returned an error status")?;
+
+    let musicbrainz_response: MusicbrainzAPIRecordingResponse = resp
+        .json()
+        .await
+        .context("Failed to parse Musicbrainz API response as JSON")?;
+
+    Ok(AudioIdentification {
+        musicbrainz_response: Some(musicbrainz_response),
+        acoustid_response: Some(acoustid_response),
+        fpcalc_fingerprint: Some(fingerprinting_result),
+        fingerprinting_command_result: command_execution_results,
+    })
+}
+
+/// Extracts the track metadata from a successful identification. Returns `None` if nothing was identified.
+pub fn get_identified_metadata(identification: &AudioIdentification) -> Option<IdentifiedMetadata> {
+    let musicbrainz_response = identification.musicbrainz_response.as_ref()?;
+    let acoustid_score = identification
+        .acoustid_response
+        .as_ref()?
+        .results
+        .iter()
+        .map(|result| result.score)
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))?;
+
+    let artist = musicbrainz_response
+        .artist_credit
+        .iter()
+        .map(|credit| format!("{}{}", credit.name, credit.joinphrase))
+        .collect::<String>()
+        .trim()
+        .to_string();
+
+    Some(IdentifiedMetadata {
+        musicbrainz_recording_id: musicbrainz_response.id.clone(),
+        acoustid_score,
+        title: musicbrainz_response.title.clone(),
+        artist: Some(artist).filter(|artist| !artist.is_empty()),
+        album: musicbrainz_response
+            .releases
+            .first()
+            .map(|release| release.title.clone()),
+        release_date: Some(musicbrainz_response.first_release_date.clone())
+            .filter(|release_date| !release_date.is_empty()),
+    })
+}
@@ -0,0 +1,743 @@
+use crate::handlers::errors::{ErrorCode, RequestError};
+use crate::handlers::shared::functions::commands::run_command;
+use crate::handlers::shared::functions::downloads::download_file_with_resume;
+#[cfg(not(target_os = "windows"))]
+use crate::handlers::shared::functions::files::set_executable_permissions;
+use crate::handlers::shared::functions::files::{
+    decompress_file, get_archive_kind, remove_subdirectories_with_prefix, search_and_copy_binaries,
+    search_and_move_binaries,
+};
+use crate::handlers::shared::functions::tools::{
+    get_tool_binaries, get_tool_dir, get_tool_version_args, parse_tool_version_for_install,
+    read_tool_install_manifest, TOOL_INSTALL_MANIFEST_FILE_NAME,
+};
+use crate::handlers::shared::model::tools::{Tool, ToolInstallManifest, ToolInstallation};
+use crate::AppState;
+use anyhow::Context;
+use fs2::FileExt;
+use std::path::{Path, PathBuf};
+use tokio::fs::{
+    copy, create_dir_all, metadata, read_dir, remove_dir_all, remove_file, rename, write,
+    OpenOptions,
+};
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+const STAGING_DIR_PREFIX: &str = ".staging-";
+/// Prefix of the directory an installed version is moved to while it is replaced, until the new install is verified.
+const REPLACED_DIR_PREFIX: &str = ".replaced-";
+const INSTALL_LOCK_FILE_NAME: &str = ".install.lock";
+
+/// Where the files of a tool install come from.
+#[derive(Debug)]
+enum ToolInstallSource {
+    /// Downloaded or imported archive or binary. The file is consumed by the install.
+    File(PathBuf),
+    /// Directory containing the binaries, for example an already extracted archive. It is left untouched.
+    Directory(PathBuf),
+}
+
+/// State of the tool install before a new version was activated.
+#[derive(Debug)]
+enum ReplacedInstall {
+    /// The installed version was already active, nothing was changed.
+    AlreadyActive,
+    Replaced {
+        /// The manifest that was replaced, `None` if the tool had no versioned install before.
+        manifest: Option<ToolInstallManifest>,
+        /// Where an inactive install of the same version was moved to, so it can be restored if the new install fails.
+        replaced_version_dir: Option<PathBuf>,
+    },
+}
+
+/// Downloads the tool from `download_url` and installs it side-by-side with the already installed versions.
+///
+/// The new version is prepared in a staging directory, its version is detected using the tool's version command
+/// and only then it is moved to its own version directory and made active by replacing the install manifest.
+/// The previously active version is kept for rollback, all older versions are removed.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn install_tool_version(
+    app_state: &AppState,
+    tool: Tool,
+    download_url: &str,
+    output_file_name: &str,
+    requested_version: Option<&str>,
+) -> Result<ToolInstallation, anyhow::Error> {
+    if let Some(requested_version) = requested_version {
+        validate_requested_version(requested_version)?;
+    }
+
+    let tool_dir = get_tool_dir(
+        Path::new(&app_state.config().server_settings.tools_download_dir),
+        tool,
+    );
+    create_dir_all(&tool_dir)
+        .await
+        .context(format!("Failed to create tool directory for {}", tool))?;
+    let _install_lock = lock_tool_install(&tool_dir, tool).await?;
+
+    let download_file_path = tool_dir.join(format!(
+        "{}-{}",
+        requested_version.unwrap_or("latest"),
+        output_file_name
+    ));
+
+    info!("Downloading {} from {}", tool, download_url);
+    download_file_with_resume(
+        &app_state.http_client,
+        download_url,
+        &download_file_path,
+        &app_state.download_progress,
+        &tool.to_string(),
+        None,
+    )
+    .await
+    .context(format!("Failed to download {}", tool))?;
+
+    info!("{} downloaded successfully", tool);
+
+    install_staged_tool(
+        &tool_dir,
+        tool,
+        &ToolInstallSource::File(download_file_path),
+    )
+    .await
+}
+
+/// Installs the tool from a local archive, binary or directory without any network access.
+/// Files are taken through the same extraction, verification and activation steps as downloaded tools.
+#[instrument(err, ret(level = "debug"))]
+pub async fn import_tool_from_path(
+    tools_download_dir: &Path,
+    tool: Tool,
+    source_path: &PathBuf,
+) -> Result<ToolInstallation, anyhow::Error> {
+    let source_metadata = metadata(source_path).await.context(format!(
+        "Failed to get metadata of the import source: {}",
+        source_path.display()
+    ))?;
+
+    let tool_dir = get_tool_dir(tools_download_dir, tool);
+    create_dir_all(&tool_dir)
+        .await
+        .context(format!("Failed to create tool directory for {}", tool))?;
+    let _install_lock = lock_tool_install(&tool_dir, tool).await?;
+
+    if source_metadata.is_dir() {
+        info!(
+            "Importing {} from directory {}",
+            tool,
+            source_path.display()
+        );
+        return install_staged_tool(
+            &tool_dir,
+            tool,
+            &ToolInstallSource::Directory(source_path.clone()),
+        )
+        .await;
+    }
+
+    let source_file_name = source_path
+        .file_name()
+        .context("Import source has no file name")?
+        .to_string_lossy()
+        .to_string();
+    let import_file_path = tool_dir.join(format!("import-{}-{}", Uuid::new_v4(), source_file_name));
+
+    info!("Importing {} from file {}", tool, source_path.display());
+    copy(source_path, &import_file_path)
+        .await
+        .context("Failed to copy import source to the tool directory")?;
+
+    install_staged_tool(&tool_dir, tool, &ToolInstallSource::File(import_file_path)).await
+}
+
+/// Installs the tool from an archive or binary that is owned by the install, for example an uploaded file.
+/// The file is moved or deleted during the install.
+#[instrument(err, ret(level = "debug"))]
+pub async fn install_tool_from_file(
+    tools_download_dir: &Path,
+    tool: Tool,
+    source_file_path: &PathBuf,
+) -> Result<ToolInstallation, anyhow::Error> {
+    let tool_dir = get_tool_dir(tools_download_dir, tool);
+    let _install_lock = match lock_tool_install(&tool_dir, tool).await {
+        Ok(install_lock) => install_lock,
+        Err(err) => {
+            remove_file(source_file_path)
+                .await
+                .context("Failed to delete source file")?;
+            return Err(err);
+        }
+    };
+
+    install_staged_tool(
+        &tool_dir,
+        tool,
+        &ToolInstallSource::File(source_file_path.clone()),
+    )
+    .await
+}
+
+/// Makes the previously active version of the tool active again.
+/// The version that was active before the rollback becomes the previous version, so rollback can be undone.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn rollback_tool_version(
+    app_state: &AppState,
+    tool: Tool,
+) -> Result<ToolInstallManifest, anyhow::Error> {
+    let tool_dir = get_tool_dir(
+        Path::new(&app_state.config().server_settings.tools_download_dir),
+        tool,
+    );
+    let _install_lock = lock_tool_install(&tool_dir, tool).await?;
+
+    let manifest = read_tool_install_manifest(&tool_dir)
+        .await?
+        .ok_or_else(|| {
+            RequestError::new(
+                ErrorCode::NotFound,
+                format!("No versioned install found for {}", tool),
+            )
+        })?;
+    let previous_version = manifest.previous_version.ok_or_else(|| {
+        RequestError::new(
+            ErrorCode::NotFound,
+            format!("No previous version of {} to roll back to", tool),
+        )
+    })?;
+
+    if !tool_dir.join(&previous_version).is_dir() {
+        anyhow::bail!(
+            "Previous version {} of {} is missing from {}",
+            previous_version,
+            tool,
+            tool_dir.display()
+        );
+    }
+
+    info!(
+        "Rolling back {} from version {} to version {}",
+        tool, manifest.current_version, previous_version
+    );
+
+    let rolled_back_manifest = ToolInstallManifest {
+        current_version: previous_version,
+        previous_version: Some(manifest.current_version),
+    };
+    write_tool_install_manifest(&tool_dir, &rolled_back_manifest).await?;
+
+    Ok(rolled_back_manifest)
+}
+
+/// Takes the install lock of the tool, which is held until the returned file is dropped, also when the request is cancelled.
+/// Installs, updates, imports and rollbacks of a tool take the lock, including the ones of the `import-tool` command
+/// running in another process, so they never change the staging directories and the install manifest at the same time.
+#[instrument(err, ret(level = "debug"))]
+async fn lock_tool_install(tool_dir: &Path, tool: Tool) -> Result<std::fs::File, anyhow::Error> {
+    create_dir_all(tool_dir)
+        .await
+        .context(format!("Failed to create tool directory for {}", tool))?;
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(tool_dir.join(INSTALL_LOCK_FILE_NAME))
+        .await
+        .context(format!("Failed to open install lock file of {}", tool))?
+        .into_std()
+        .await;
+
+    match lock_file.try_lock_exclusive() {
+        Ok(()) => Ok(lock_file),
+        Err(err) if err.kind() == fs2::lock_contended_error().kind() => Err(RequestError::new(
+            ErrorCode::DownloadInProgress,
+            format!(
+                "Another install, update or import of {} is in progress",
+                tool
+            ),
+        )
+        .into()),
+        Err(err) => {
+            Err(anyhow::Error::new(err).context(format!("Failed to take install lock of {}", tool)))
+        }
+    }
+}
+
+#[instrument(err, ret(level = "debug"))]
+async fn install_staged_tool(
+    tool_dir: &PathBuf,
+    tool: Tool,
+    source: &ToolInstallSource,
+) -> Result<ToolInstallation, anyhow::Error> {
+    // Installs hold the install lock, so staging directories that exist now were left behind by a crash
+    remove_subdirectories_with_prefix(tool_dir, STAGING_DIR_PREFIX)
+        .await
+        .context("Failed to remove stale staging directories")?;
+
+    let staging_dir = tool_dir.join(format!("{}{}", STAGING_DIR_PREFIX, Uuid::new_v4()));
+    create_dir_all(&staging_dir)
+        .await
+        .context("Failed to create staging directory")?;
+
+    let staging_result = match stage_tool_install(tool, source, &staging_dir).await {
+        Ok(()) => run_post_install_steps(tool, &staging_dir).await,
+        Err(err) => Err(err),
+    };
+
+    let installed_version = match staging_result {
+        Ok(installed_version) => installed_version,
+        Err(err) => {
+            warn!("Failed to stage {} install, cleaning up", tool);
+            remove_dir_all(&staging_dir)
+                .await
+                .context("Failed to remove staging directory")?;
+            if let ToolInstallSource::File(source_file_path) = source {
+                if source_file_path.exists() {
+                    remove_file(source_file_path)
+                        .await
+                        .context("Failed to delete source file")?;
+                }
+            }
+            return Err(err);
+        }
+    };
+
+    let (installation, replaced_install) =
+        activate_staged_install(tool_dir, &staging_dir, installed_version).await?;
+
+    if let Err(err) = verify_tool_binaries(tool, &installation.install_dir).await {
+        error!(
+            "Activated {} version {} does not run, rolling back the install",
+            tool, installation.installed_version
+        );
+        roll_back_failed_install(tool_dir, &installation, replaced_install).await?;
+        return Err(err.context(format!(
+            "Installed {} version {} failed verification and was rolled back",
+            tool, installation.installed_version
+        )));
+    }
+
+    if let ReplacedInstall::Replaced {
+        replaced_version_dir: Some(replaced_version_dir),
+        ..
+    } = &replaced_install
+    {
+        remove_dir_all(replaced_version_dir)
+            .await
+            .context("Failed to remove replaced version directory")?;
+    }
+
+    if let Some(manifest) = read_tool_install_manifest(tool_dir).await? {
+        remove_stale_versions(tool_dir, &manifest).await?;
+    }
+
+    Ok(installation)
+}
+
+#[instrument(err, ret(level = "debug"))]
+async fn stage_tool_install(
+    tool: Tool,
+    source: &ToolInstallSource,
+    staging_dir: &PathBuf,
+) -> Result<(), anyhow::Error> {
+    let (binaries, minimum_binaries) = get_tool_binaries(tool);
+
+    match source {
+        ToolInstallSource::Directory(source_dir) => {
+            search_and_copy_binaries(source_dir, staging_dir, binaries, minimum_binaries)
+                .await
+                .context(format!("Failed to copy {} binaries to staging", tool))?;
+        }
+        ToolInstallSource::File(source_file_path)
+            if get_archive_kind(source_file_path).is_some() =>
+        {
+            decompress_file(source_file_path, staging_dir)
+                .await
+                .context(format!("Failed to extract {} archive", tool))?;
+
+            search_and_move_binaries(staging_dir, staging_dir, binaries, minimum_binaries)
+                .await
+                .context(format!(
+                    "Failed to move {} binaries to the correct location",
+                    tool
+                ))?;
+
+            info!("Deleting archive file");
+            remove_file(source_file_path)
+                .await
+                .context("Failed to delete archive file")?;
+
+            info!("Deleting extracted directories");
+            remove_subdirectories_with_prefix(staging_dir, "")
+                .await
+                .context("Failed to remove extracted directories")?;
+        }
+        ToolInstallSource::File(source_file_path) => {
+            info!("Source file is the {} binary, moving it to staging", tool);
+            let binary_path = staging_dir.join(get_tool_executable_file_name(tool));
+            rename(source_file_path, &binary_path)
+                .await
+                .context("Failed to move binary to staging directory")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Post-install step applied to every installed binary of the tool.
+/// Sets executable permissions on Unix and returns the version of the tool's main executable.
+/// All binaries are verified once the install is activated, see [`verify_tool_binaries`].
+#[instrument(err, ret(level = "debug"))]
+async fn run_post_install_steps(
+    tool: Tool,
+    install_dir: &PathBuf,
+) -> Result<String, anyhow::Error> {
+    #[cfg(not(target_os = "windows"))]
+    for binary_path in get_installed_binaries(tool, install_dir) {
+        set_executable_permissions(&binary_path)
+            .await
+            .context(format!(
+                "Failed to set executable permissions for {}",
+                binary_path.display()
+            ))?;
+    }
+
+    let executable_path = install_dir.join(get_tool_executable_file_name(tool));
+    let command_execution_results = run_command(&executable_path, get_tool_version_args(tool))
+        .await
+        .context(format!("Failed to run {} version command", tool))?;
+
+    if !command_execution_results.command_completed_successfully {
+        anyhow::bail!(
+            "{} version command failed with exit code: {:?}",
+            tool,
+            command_execution_results.exit_code
+        );
+    }
+
+    let installed_version = command_execution_results
+        .stdout
+        .as_deref()
+        .and_then(|stdout| parse_tool_version_for_install(tool, stdout))
+        .context(format!("Failed to parse {} version", tool))?;
+
+    info!("Staged {} version {}", tool, installed_version);
+
+    Ok(installed_version)
+}
+
+/// Runs the version command of every installed binary of the tool and fails if any of them does not run.
+#[instrument(err, ret(level = "debug"))]
+async fn verify_tool_binaries(tool: Tool, install_dir: &PathBuf) -> Result<(), anyhow::Error> {
+    let installed_binaries = get_installed_binaries(tool, install_dir);
+    if installed_binaries.is_empty() {
+        anyhow::bail!("No {} binaries found in {}", tool, install_dir.display());
+    }
+
+    for binary_path in installed_binaries {
+        info!("Verifying binary runs: {}", binary_path.display());
+        let command_execution_results = run_command(&binary_path, get_tool_version_args(tool))
+            .await
+            .context(format!("Failed to run {}", binary_path.display()))?;
+
+        if !command_execution_results.command_completed_successfully {
+            anyhow::bail!(
+                "Binary {} failed to run its version command, exit code: {:?}",
+                binary_path.display(),
+                command_execution_results.exit_code
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns paths of the tool's binaries for the current operating system that are present in the install directory.
+fn get_installed_binaries(tool: Tool, install_dir: &Path) -> Vec<PathBuf> {
+    let is_windows = std::env::consts::OS == "windows";
+    let (binaries, _) = get_tool_binaries(tool);
+
+    binaries
+        .iter()
+        .filter(|binary| binary.ends_with(".exe") == is_windows)
+        .map(|binary| install_dir.join(binary))
+        .filter(|binary_path| binary_path.is_file())
+        .collect()
+}
+
+#[instrument(err, ret(level = "debug"))]
+async fn activate_staged_install(
+    tool_dir: &PathBuf,
+    staging_dir: &PathBuf,
+    installed_version: String,
+) -> Result<(ToolInstallation, ReplacedInstall), anyhow::Error> {
+    let manifest = read_tool_install_manifest(tool_dir).await?;
+    let version_dir = tool_dir.join(&installed_version);
+
+    if let Some(manifest) = manifest
+        .as_ref()
+        .filter(|manifest| manifest.current_version == installed_version)
+    {
+        info!(
+            "Version {} is already installed and active, discarding staged install",
+            installed_version
+        );
+        remove_dir_all(staging_dir)
+            .await
+            .context("Failed to remove staging directory")?;
+
+        return Ok((
+            ToolInstallation {
+                installed_version,
+                previous_version: manifest.previous_version.clone(),
+                install_dir: version_dir,
+            },
+            ReplacedInstall::AlreadyActive,
+        ));
+    }
+
+    // The existing directory is only removed once the new install is verified, so a failed install can restore it
+    let replaced_version_dir = if version_dir.exists() {
+        let replaced_version_dir =
+            tool_dir.join(format!("{}{}", REPLACED_DIR_PREFIX, Uuid::new_v4()));
+        info!(
+            "Moving inactive installed version directory {} aside to {}",
+            version_dir.display(),
+            replaced_version_dir.display()
+        );
+        rename(&version_dir, &replaced_version_dir)
+            .await
+            .context("Failed to move existing version directory aside")?;
+        Some(replaced_version_dir)
+    } else {
+        None
+    };
+
+    rename(staging_dir, &version_dir)
+        .await
+        .context("Failed to move staged install to version directory")?;
+
+    let new_manifest = ToolInstallManifest {
+        current_version: installed_version.clone(),
+        previous_version: manifest
+            .as_ref()
+            .map(|manifest| manifest.current_version.clone()),
+    };
+    write_tool_install_manifest(tool_dir, &new_manifest).await?;
+
+    Ok((
+        ToolInstallation {
+            installed_version,
+            previous_version: new_manifest.previous_version,
+            install_dir: version_dir,
+        },
+        ReplacedInstall::Replaced {
+            manifest,
+            replaced_version_dir,
+        },
+    ))
+}
+
+/// Restores the manifest that was active before the failed install and removes the installed version.
+/// An install of the same version that was replaced by the failed install is moved back in its place.
+#[instrument(err, ret(level = "debug"))]
+async fn roll_back_failed_install(
+    tool_dir: &PathBuf,
+    installation: &ToolInstallation,
+    replaced_install: ReplacedInstall,
+) -> Result<(), anyhow::Error> {
+    let replaced_version_dir = match replaced_install {
+        ReplacedInstall::AlreadyActive => {
+            warn!("Version was already active before the install, nothing to roll back");
+            return Ok(());
+        }
+        ReplacedInstall::Replaced {
+            manifest: Some(previous_manifest),
+            replaced_version_dir,
+        } => {
+            write_tool_install_manifest(tool_dir, &previous_manifest).await?;
+            replaced_version_dir
+        }
+        ReplacedInstall::Replaced {
+            manifest: None,
+            replaced_version_dir,
+        } => {
+            info!("No version was active before the install, removing install manifest");
+            remove_file(tool_dir.join(TOOL_INSTALL_MANIFEST_FILE_NAME))
+                .await
+                .context("Failed to remove install manifest")?;
+            replaced_version_dir
+        }
+    };
+
+    remove_dir_all(&installation.install_dir)
+        .await
+        .context("Failed to remove rolled back version directory")?;
+
+    if let Some(replaced_version_dir) = replaced_version_dir {
+        info!(
+            "Restoring replaced version directory {}",
+            installation.install_dir.display()
+        );
+        rename(&replaced_version_dir, &installation.install_dir)
+            .await
+            .context("Failed to restore replaced version directory")?;
+    }
+
+    Ok(())
+}
+
+/// Writes the manifest to a temporary file first and renames it, so readers never see a partially written manifest.
+#[instrument(err, ret(level = "debug"))]
+async fn write_tool_install_manifest(
+    tool_dir: &PathBuf,
+    manifest: &ToolInstallManifest,
+) -> Result<(), anyhow::Error> {
+    let manifest_path = tool_dir.join(TOOL_INSTALL_MANIFEST_FILE_NAME);
+    let temp_manifest_path = tool_dir.join(format!("{}.tmp", TOOL_INSTALL_MANIFEST_FILE_NAME));
+
+    let manifest_contents =
+        serde_json::to_string_pretty(manifest).context("Failed to serialize install manifest")?;
+    write(&temp_manifest_path, manifest_contents)
+        .await
+        .context("Failed to write temporary install manifest")?;
+    rename(&temp_manifest_path, &manifest_path)
+        .await
+        .context("Failed to replace install manifest")?;
+
+    info!("Active version switched to {}", manifest.current_version);
+
+    Ok(())
+}
+
+#[instrument(err, ret(level = "debug"))]
+async fn remove_stale_versions(
+    tool_dir: &PathBuf,
+    manifest: &ToolInstallManifest,
+) -> Result<(), anyhow::Error> {
+    let mut entries = read_dir(tool_dir)
+        .await
+        .context("Failed to read directory entries")?;
+
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .context("Failed to read next entry")?
+    {
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        let is_kept_version = dir_name == manifest.current_version
+            || manifest.previous_version.as_deref() == Some(dir_name.as_str());
+
+        if is_kept_version
+            || dir_name.starts_with(STAGING_DIR_PREFIX)
+            || !entry
+                .file_type()
+                .await
+                .context("Failed to get file type")?
+                .is_dir()
+        {
+            continue;
+        }
+
+        info!("Removing stale tool version: {}", dir_name);
+        remove_dir_all(entry.path())
+            .await
+            .context(format!("Failed to remove stale tool version: {}", dir_name))?;
+    }
+
+    Ok(())
+}
+
+/// Requested versions are used in download URLs and file names, so only a safe subset of characters is allowed.
+#[instrument(err)]
+fn validate_requested_version(requested_version: &str) -> Result<(), anyhow::Error> {
+    debug!("Validating requested tool version");
+
+    let is_valid = !requested_version.is_empty()
+        && !requested_version.starts_with('.')
+        && requested_version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_');
+
+    if !is_valid {
+        return Err(RequestError::new(
+            ErrorCode::InvalidRequest,
+            format!("Invalid tool version requested: {}", requested_version),
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn get_tool_executable_file_name(tool: Tool) -> String {
+    let executable_name = match tool {
+        Tool::YtDlp => "yt-dlp",
+        Tool::Ffmpeg => "ffmpeg",
+        Tool::Chromaprint => "fpcalc",
+    };
+
+    if std::env::consts::OS == "windows" {
+        format!("{}.exe", executable_name)
+    } else {
+        executable_name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn restores_previous_version_when_its_reinstall_is_rolled_back() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let tool_dir = temp_dir.path().to_path_buf();
+        for version in ["1.0", "2.0"] {
+            std::fs::create_dir(tool_dir.join(version)).unwrap();
+            std::fs::write(tool_dir.join(version).join("ffmpeg"), version).unwrap();
+        }
+        let manifest = ToolInstallManifest {
+            current_version: "2.0".to_string(),
+            previous_version: Some("1.0".to_string()),
+        };
+        write_tool_install_manifest(&tool_dir, &manifest)
+            .await
+            .unwrap();
+        let staging_dir = tool_dir.join(format!("{}reinstall", STAGING_DIR_PREFIX));
+        std::fs::create_dir(&staging_dir).unwrap();
+        std::fs::write(staging_dir.join("ffmpeg"), "broken").unwrap();
+
+        let (installation, replaced_install) =
+            activate_staged_install(&tool_dir, &staging_dir, "1.0".to_string())
+                .await
+                .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(tool_dir.join("1.0").join("ffmpeg")).unwrap(),
+            "broken"
+        );
+
+        roll_back_failed_install(&tool_dir, &installation, replaced_install)
+            .await
+            .unwrap();
+
+        let manifest = read_tool_install_manifest(&tool_dir)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(manifest.current_version, "2.0");
+        assert_eq!(manifest.previous_version.as_deref(), Some("1.0"));
+        for version in ["1.0", "2.0"] {
+            assert_eq!(
+                std::fs::read_to_string(tool_dir.join(version).join("ffmpeg")).unwrap(),
+                version
+            );
+        }
+        let mut dir_names = std::fs::read_dir(&tool_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        dir_names.sort();
+        assert_eq!(dir_names, ["1.0", "2.0", TOOL_INSTALL_MANIFEST_FILE_NAME]);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::handlers::shared::functions::files::{
+    move_file_without_overwriting, to_library_file_name,
+};
+use crate::handlers::shared::functions::naming::{get_file_name_values, render_file_name_template};
+use crate::handlers::shared::functions::yt_dlp::DOWNLOADED_CHAPTER_FILE_PREFIX;
+use crate::handlers::shared::model::library::{LibraryChapter, LibraryIndex, LibraryIndexEntry};
+use crate::handlers::shared::model::yt_dlp::{YtDlpChapter, YtDlpMediaInfo};
+use crate::AppState;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use tokio::fs::{create_dir_all, read_dir, read_to_string, remove_file, rename, write};
+use tracing::{info, instrument, warn};
+
+/// Name of the library index file. The leading dot keeps it out of library listings.
+pub const LIBRARY_INDEX_FILE_NAME: &str = ".ferrous-beats-index.json";
+
+/// Suffix of the info JSON written next to the downloaded media.
+pub const INFO_JSON_SUFFIX: &str = ".info.json";
+
+/// Moves downloaded files with the `download_id` prefix from `download_dir` to `library_dir` and records them in the library index.
+/// Files are named using the library file name template and the info JSON written by yt-dlp, which is stored in the index instead of being moved to the library.
+/// Existing library files are never overwritten, colliding names get a numeric suffix.
+/// Returns the names of the files moved to the library, relative to the library directory.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn move_downloaded_media_to_library(
+    app_state: &AppState,
+    download_dir: &Path,
+    library_dir: &Path,
+    download_id: &str,
+) -> Result<Vec<String>, anyhow::Error> {
+    let media_info = take_downloaded_info_json(download_dir, download_id)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("Failed to read info JSON written by yt-dlp: {:#}", err);
+            None
+        });
+
+    info!("Moving downloaded files to the library");
+    let mut downloaded_files = Vec::new();
+    let mut dir_entries = read_dir(download_dir)
+        .await
+        .context("Failed to read directory entries")?;
+    while let Some(dir_entry) = dir_entries
+        .next_entry()
+        .await
+        .context("Failed to read next entry")?
+    {
+        let file_name = dir_entry.file_name().to_string_lossy().to_string();
+        if let Some(downloaded_name) = file_name.strip_prefix(download_id) {
+            downloaded_files.push((dir_entry.path(), downloaded_name.to_string()));
+        }
+    }
+
+    if downloaded_files.is_empty() {
+        anyhow::bail!(
+            "No matching file found that starts with prefix: {}",
+            download_id
+        )
+    }
+
+    let library_dir = library_dir
+        .canonicalize()
+        .context("Failed to canonicalize library directory")?;
+    let file_name_template = &app_state.config().library_settings.file_name_template;
+    let mut library_files = Vec::with_capacity(downloaded_files.len());
+    let mut entries = Vec::with_capacity(downloaded_files.len());
+    for (source_path, downloaded_name) in downloaded_files {
+        let ext = Path::new(&downloaded_name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let chapter = find_downloaded_chapter(&downloaded_name, media_info.as_ref());
+        let file_name_values = get_file_name_values(media_info.as_ref(), chapter, &ext);
+        let destination_path = library_dir.join(render_file_name_template(
+            file_name_template,
+            &file_name_values,
+        ));
+
+        if let Some(destination_dir) = destination_path.parent() {
+            create_dir_all(destination_dir)
+                .await
+                .context("Failed to create library subdirectory")?;
+        }
+
+        info!(
+            "Moving media file: {} -> {}",
+            source_path.display(),
+            destination_path.display()
+        );
+        let moved_path = move_file_without_overwriting(&source_path, &destination_path)
+            .await
+            .context("Failed to move media file to the library")?;
+
+        let library_file_name = to_library_file_name(&library_dir, &moved_path)?;
+        entries.push(build_library_index_entry(
+            &library_file_name,
+            download_id,
+            media_info.as_ref(),
+            chapter,
+        ));
+        library_files.push(library_file_name);
+    }
+
+    add_library_index_entries(app_state, &library_dir, entries)
+        .await
+        .context("Failed to add downloaded files to the library index")?;
+
+    Ok(library_files)
+}
+
+/// Reads the library index stored in `library_dir`. Returns an empty index if it does not exist yet.
+#[instrument(err, ret(level = "debug"))]
+pub async fn read_library_index(library_dir: &Path) -> Result<LibraryIndex, anyhow::Error> {
+    let index_path = library_dir.join(LIBRARY_INDEX_FILE_NAME);
+    if !index_path.exists() {
+        return Ok(LibraryIndex::default());
+    }
+
+    let index_contents = read_to_string(&index_path)
+        .await
+        .context("Failed to read library index")?;
+
+    serde_json::from_str(&index_contents).context("Failed to parse library index as JSON")
+}
+
+/// Finds the library entry of media downloaded before from the same extractor with the same media ID.
+/// Entries whose files were removed from the library are ignored.
+#[instrument(err, ret(level = "debug"))]
+pub async fn find_library_index_entry_by_source(
+    library_dir: &Path,
+    extractor: &str,
+    media_id: &str,
+) -> Result<Option<LibraryIndexEntry>, anyhow::Error> {
+    let library_index = read_library_index(library_dir).await?;
+
+    let existing_entry = library_index.entries.into_values().find(|entry| {
+        entry
+            .extractor
+            .as_deref()
+            .is_some_and(|entry_extractor| entry_extractor.eq_ignore_ascii_case(extractor))
+            && entry.media_id.as_deref() == Some(media_id)
+            && library_dir.join(&entry.file_name).exists()
+    });
+
+    Ok(existing_entry)
+}
+
+/// Adds entries to the library index, replacing existing entries for the same file names.
+/// Updates are serialized so concurrent downloads do not overwrite each other's entries.
+#[instrument(err, ret(level = "debug"), skip(app_state, entries))]
+pub async fn add_library_index_entries(
+    app_state: &AppState,
+    library_dir: &Path,
+    entries: Vec<LibraryIndexEntry>,
+) -> Result<(), anyhow::Error> {
+    let _index_guard = app_state.library_index_lock.lock().await;
+
+    let mut library_index = read_library_index(library_dir).await?;
+    for entry in entries {
+        library_index.entries.insert(entry.file_name.clone(), entry);
+    }
+
+    write_library_index(library_dir, &library_index).await
+}
+
+/// Applies `update` to the index entry of `file_name` and stores it under its possibly changed file name.
+/// Files without an index entry get a new entry before the update is applied.
+#[instrument(err, ret(level = "debug"), skip(app_state, update))]
+pub async fn update_library_index_entry(
+    app_state: &AppState,
+    library_dir: &Path,
+    file_name: &str,
+    update: impl FnOnce(&mut LibraryIndexEntry),
+) -> Result<(), anyhow::Error> {
+    let _index_guard = app_state.library_index_lock.lock().await;
+
+    let mut library_index = read_library_index(library_dir).await?;
+    let mut entry = library_index
+        .entries
+        .remove(file_name)
+        .unwrap_or_else(|| build_library_index_entry(file_name, "", None, None));
+    update(&mut entry);
+    library_index.entries.insert(entry.file_name.clone(), entry);
+
+    write_library_index(library_dir, &library_index).await
+}
+
+#[instrument(err, skip(library_index))]
+async fn write_library_index(
+    library_dir: &Path,
+    library_index: &LibraryIndex,
+) -> Result<(), anyhow::Error> {
+    let index_path = library_dir.join(LIBRARY_INDEX_FILE_NAME);
+    let temp_index_path = get_temp_index_path(&index_path);
+
+    let index_contents =
+        serde_json::to_string_pretty(library_index).context("Failed to serialize library index")?;
+    write(&temp_index_path, index_contents)
+        .await
+        .context("Failed to write temporary library index")?;
+    rename(&temp_index_path, &index_path)
+        .await
+        .context("Failed to replace library index")?;
+
+    Ok(())
+}
+
+/// Finds the info JSON written by yt-dlp for the download, parses it and removes the file.
+#[instrument(err, ret(level = "debug"))]
+async fn take_downloaded_info_json(
+    download_dir: &Path,
+    download_id: &str,
+) -> Result<Option<YtDlpMediaInfo>, anyhow::Error> {
+    let mut entries = read_dir(download_dir)
+        .await
+        .context("Failed to read directory entries")?;
+
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .context("Failed to read next entry")?
+    {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.starts_with(download_id) && file_name.ends_with(INFO_JSON_SUFFIX) {
+            let info_json_contents = read_to_string(entry.path())
+                .await
+                .context("Failed to read info JSON")?;
+            remove_file(entry.path())
+                .await
+                .context("Failed to remove info JSON")?;
+
+            let media_info =
+                serde_json::from_str(&info_json_contents).context("Failed to parse info JSON")?;
+
+            return Ok(Some(media_info));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the number and the chapter of a file split from a chapter, based on its name after the download ID prefix.
+fn find_downloaded_chapter<'a>(
+    downloaded_name: &str,
+    media_info: Option<&'a YtDlpMediaInfo>,
+) -> Option<(u32, &'a YtDlpChapter)> {
+    let chapter_number: u32 = downloaded_name
+        .strip_prefix(DOWNLOADED_CHAPTER_FILE_PREFIX)?
+        .split('.')
+        .next()?
+        .parse()
+        .ok()?;
+    let chapter = media_info?
+        .chapters
+        .as_ref()?
+        .get(usize::try_from(chapter_number).ok()?.checked_sub(1)?)?;
+
+    Some((chapter_number, chapter))
+}
+
+fn build_library_index_entry(
+    file_name: &str,
+    download_id: &str,
+    media_info: Option<&YtDlpMediaInfo>,
+    chapter: Option<(u32, &YtDlpChapter)>,
+) -> LibraryIndexEntry {
+    // Files split from a chapter keep the source of the full media but describe only their part of it
+    if let Some((_, chapter)) = chapter {
+        return LibraryIndexEntry {
+            file_name: file_name.to_string(),
+            download_id: download_id.to_string(),
+            extractor: media_info.and_then(|info| info.extractor.clone()),
+            media_id: media_info.and_then(|info| info.id.clone()),
+            title: chapter.title.clone(),
+            uploader: media_info.and_then(|info| info.uploader.clone()),
+            upload_date: media_info.and_then(|info| info.upload_date.clone()),
+            source_url: media_info.and_then(|info| info.webpage_url.clone()),
+            duration: Some(chapter.end_time - chapter.start_time),
+            chapters: Vec::new(),
+            identification: None,
+        };
+    }
+
+    LibraryIndexEntry {
+        file_name: file_name.to_string(),
+        download_id: download_id.to_string(),
+        extractor: media_info.and_then(|info| info.extractor.clone()),
+        media_id: media_info.and_then(|info| info.id.clone()),
+        title: media_info.and_then(|info| info.title.clone()),
+        uploader: media_info.and_then(|info| info.uploader.clone()),
+        upload_date: media_info.and_then(|info| info.upload_date.clone()),
+        source_url: media_info.and_then(|info| info.webpage_url.clone()),
+        duration: media_info.and_then(|info| info.duration),
+        chapters: media_info
+            .and_then(|info| info.chapters.as_ref())
+            .map(|chapters| {
+                chapters
+                    .iter()
+                    .map(|chapter| LibraryChapter {
+                        title: chapter.title.clone(),
+                        start_time: chapter.start_time,
+                        end_time: chapter.end_time,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        identification: None,
+    }
+}
+
+fn get_temp_index_path(index_path: &Path) -> PathBuf {
+    let mut temp_index_path = index_path.as_os_str().to_owned();
+    temp_index_path.push(".tmp");
+    PathBuf::from(temp_index_path)
+}
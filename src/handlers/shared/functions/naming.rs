@@ -0,0 +1,111 @@
+use crate::handlers::shared::functions::files::sanitize_file_name;
+use crate::handlers::shared::model::identify::IdentifiedMetadata;
+use crate::handlers::shared::model::library::LibraryIndexEntry;
+use crate::handlers::shared::model::naming::{
+    FileNameTemplate, FileNameValues, TemplateField, TemplateSegment,
+};
+use crate::handlers::shared::model::yt_dlp::{YtDlpChapter, YtDlpMediaInfo};
+
+const UNKNOWN_ARTIST: &str = "Unknown Artist";
+const UNKNOWN_ALBUM: &str = "Unknown Album";
+const UNKNOWN_TITLE: &str = "Unknown Title";
+const UNKNOWN_VALUE: &str = "unknown";
+
+/// Renders the template into a library path relative to the library directory, using `/` as the separator.
+/// Field values are sanitized before they are inserted, so they cannot add directories, and every path component is sanitized afterwards.
+pub fn render_file_name_template(template: &FileNameTemplate, values: &FileNameValues) -> String {
+    let mut rendered = String::new();
+    for segment in template.segments() {
+        match segment {
+            TemplateSegment::Literal(literal) => rendered.push_str(literal),
+            TemplateSegment::Field { field, width } => {
+                rendered.push_str(&render_field(*field, *width, values));
+            }
+        }
+    }
+
+    rendered
+        .split('/')
+        .map(sanitize_file_name)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Collects the values used in file name templates from the info JSON written by yt-dlp.
+/// Files split from a chapter are named after the chapter, with the media title used as the album.
+pub fn get_file_name_values(
+    media_info: Option<&YtDlpMediaInfo>,
+    chapter: Option<(u32, &YtDlpChapter)>,
+    ext: &str,
+) -> FileNameValues {
+    let artist = media_info.and_then(|info| info.artist.clone().or(info.uploader.clone()));
+    let (album, track, title) = match chapter {
+        Some((chapter_number, chapter)) => (
+            media_info.and_then(|info| info.title.clone()),
+            Some(chapter_number),
+            chapter.title.clone(),
+        ),
+        None => (
+            media_info.and_then(|info| info.album.clone()),
+            media_info.and_then(|info| info.track_number),
+            media_info.and_then(|info| info.track.clone().or(info.title.clone())),
+        ),
+    };
+
+    FileNameValues {
+        artist,
+        album,
+        track,
+        title,
+        uploader: media_info.and_then(|info| info.uploader.clone()),
+        id: media_info.and_then(|info| info.id.clone()),
+        extractor: media_info.and_then(|info| info.extractor.clone()),
+        upload_date: media_info.and_then(|info| info.upload_date.clone()),
+        ext: ext.to_string(),
+    }
+}
+
+/// Collects the values used in file name templates from the metadata identified from the audio fingerprint.
+/// Values that identification does not provide are taken from the library index entry.
+pub fn get_identified_file_name_values(
+    entry: &LibraryIndexEntry,
+    identification: &IdentifiedMetadata,
+    ext: &str,
+) -> FileNameValues {
+    FileNameValues {
+        artist: identification
+            .artist
+            .clone()
+            .or_else(|| entry.uploader.clone()),
+        album: identification.album.clone(),
+        track: None,
+        title: Some(identification.title.clone()),
+        uploader: entry.uploader.clone(),
+        id: entry.media_id.clone(),
+        extractor: entry.extractor.clone(),
+        upload_date: entry.upload_date.clone(),
+        ext: ext.to_string(),
+    }
+}
+
+fn render_field(field: TemplateField, width: Option<usize>, values: &FileNameValues) -> String {
+    let value = match field {
+        TemplateField::Artist => values.artist.as_deref().unwrap_or(UNKNOWN_ARTIST),
+        TemplateField::Album => values.album.as_deref().unwrap_or(UNKNOWN_ALBUM),
+        TemplateField::Title => values.title.as_deref().unwrap_or(UNKNOWN_TITLE),
+        TemplateField::Uploader => values.uploader.as_deref().unwrap_or(UNKNOWN_ARTIST),
+        TemplateField::Id => values.id.as_deref().unwrap_or(UNKNOWN_VALUE),
+        TemplateField::Extractor => values.extractor.as_deref().unwrap_or(UNKNOWN_VALUE),
+        TemplateField::UploadDate => values.upload_date.as_deref().unwrap_or(UNKNOWN_VALUE),
+        TemplateField::Ext => values.ext.as_str(),
+        // Missing track numbers are left out instead of rendering a placeholder
+        TemplateField::Track => {
+            return match values.track {
+                Some(track) => format!("{:0width$}", track, width = width.unwrap_or(0)),
+                None => String::new(),
+            };
+        }
+    };
+
+    sanitize_file_name(value)
+}
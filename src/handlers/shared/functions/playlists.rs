@@ -0,0 +1,350 @@
+use crate::handlers::shared::functions::library::{
+    find_library_index_entry_by_source, move_downloaded_media_to_library,
+};
+use crate::handlers::shared::functions::postprocessing::run_post_processing;
+use crate::handlers::shared::functions::urls::validate_media_url;
+use crate::handlers::shared::functions::yt_dlp::{
+    download_audio_with_yt_dlp, fetch_playlist_info, flatten_playlist_entries,
+    is_playlist_entry_unavailable,
+};
+use crate::handlers::shared::model::library::LibraryIndexEntry;
+use crate::handlers::shared::model::media::{
+    AudioDownloadOptions, PlaylistDownloadEntry, PlaylistDownloadJob, PlaylistDownloadOptions,
+    PlaylistDownloadState, PlaylistItemDownload, PlaylistItemState, YtDlpNetworkOptions,
+};
+use crate::handlers::shared::model::yt_dlp::YtDlpPlaylistEntry;
+use crate::AppState;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::fs::create_dir_all;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+/// How long finished playlist downloads can still be looked up by their download ID.
+const FINISHED_PLAYLIST_DOWNLOAD_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Creates the download directories and registers a new playlist download job in the playlist download registry.
+/// The job is run with [`download_playlist`].
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn start_playlist_download(
+    app_state: &AppState,
+    playlist_url: &str,
+) -> Result<PlaylistDownloadJob, anyhow::Error> {
+    let config = app_state.config();
+    let audio_download_dir = Path::new(&config.audio_download_settings.download_dir);
+    create_dir_all(audio_download_dir)
+        .await
+        .context("Failed to create download directory for audio")?;
+
+    let library_dir = Path::new(&config.library_settings.dir);
+    create_dir_all(library_dir)
+        .await
+        .context("Failed to create library directory")?;
+
+    let job = PlaylistDownloadJob {
+        download_id: Uuid::new_v4().to_string(),
+        requested_url: playlist_url.to_string(),
+        playlist_id: None,
+        playlist_title: None,
+        library_dir: library_dir.to_string_lossy().to_string(),
+        state: PlaylistDownloadState::InProgress,
+        completed_items: 0,
+        failed_items: 0,
+        skipped_items: 0,
+        items: Vec::new(),
+        error: None,
+        command_execution_results: None,
+    };
+    update_playlist_download_job(app_state, &job).await;
+
+    Ok(job)
+}
+
+/// Downloads every entry of the playlist or channel of a job started with [`start_playlist_download`] as a separate
+/// child item and moves the results to the library.
+/// Progress of the job is reported in the playlist download registry under its download ID.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn download_playlist(
+    app_state: &AppState,
+    mut job: PlaylistDownloadJob,
+    playlist_download_options: &PlaylistDownloadOptions,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<PlaylistDownloadJob, anyhow::Error> {
+    let download_id = job.download_id.clone();
+    let playlist_url = job.requested_url.clone();
+    let config = app_state.config();
+    let library_dir = PathBuf::from(&job.library_dir);
+
+    info!(
+        "Listing playlist entries using yt-dlp. Download ID: {}",
+        download_id
+    );
+
+    let (command_execution_results, playlist_info) = match fetch_playlist_info(
+        app_state,
+        &playlist_url,
+        playlist_download_options.max_items,
+        network_options,
+    )
+    .await
+    {
+        Ok(fetch_results) => fetch_results,
+        Err(err) => {
+            job.state = PlaylistDownloadState::Failed;
+            job.error = Some(format!("{:#}", err));
+            update_playlist_download_job(app_state, &job).await;
+            return Err(err);
+        }
+    };
+    job.command_execution_results = Some(command_execution_results);
+
+    let Some(playlist_info) = playlist_info else {
+        error!("Failed to list playlist entries using yt-dlp");
+        job.state = PlaylistDownloadState::Failed;
+        job.error = Some("Failed to list playlist entries using yt-dlp".to_string());
+        update_playlist_download_job(app_state, &job).await;
+        return Ok(job);
+    };
+
+    job.playlist_id = playlist_info.id;
+    job.playlist_title = playlist_info.title;
+
+    let entries = if playlist_info.info_type.as_deref() == Some("playlist") {
+        flatten_playlist_entries(playlist_info.entries)
+    } else {
+        // A URL of a single media item is downloaded as a playlist with one entry
+        vec![YtDlpPlaylistEntry {
+            id: job.playlist_id.clone(),
+            title: job.playlist_title.clone(),
+            url: Some(playlist_url.clone()),
+            ie_key: playlist_info.extractor,
+            availability: None,
+            uploader: None,
+            channel: None,
+            duration: None,
+            thumbnails: Vec::new(),
+            entries: Vec::new(),
+        }]
+    };
+
+    info!("Found {} playlist entries", entries.len());
+
+    let mut unavailable_entries = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.into_iter().enumerate() {
+        unavailable_entries.push(is_playlist_entry_unavailable(&entry));
+        // Entries can be hosted on other sites than the playlist, so each one is checked against the URL policy
+        let url_policy_error = entry
+            .url
+            .as_deref()
+            .and_then(|url| validate_media_url(&config.url_policy_settings, url).err());
+        if let Some(url_policy_error) = &url_policy_error {
+            warn!(
+                "Skipping playlist entry {} not allowed by the URL policy: {}",
+                index + 1,
+                url_policy_error
+            );
+            job.skipped_items += 1;
+        }
+        job.items.push(PlaylistItemDownload {
+            index: index + 1,
+            entry_id: entry.id,
+            extractor: entry.ie_key.map(|ie_key| ie_key.to_lowercase()),
+            title: entry.title,
+            url: entry.url,
+            download_id: format!("{}-{}-", download_id, index + 1),
+            state: match url_policy_error {
+                Some(_) => PlaylistItemState::Skipped,
+                None => PlaylistItemState::Pending,
+            },
+            library_files: Vec::new(),
+            error: url_policy_error.map(|url_policy_error| url_policy_error.message),
+            existing_library_entry: None,
+            command_execution_results: None,
+            post_processing: Vec::new(),
+        });
+    }
+    update_playlist_download_job(app_state, &job).await;
+
+    for (item_index, is_unavailable) in unavailable_entries.into_iter().enumerate() {
+        if job.items[item_index].state == PlaylistItemState::Skipped {
+            continue;
+        }
+        if playlist_download_options.skip_unavailable && is_unavailable {
+            let item = &mut job.items[item_index];
+            warn!("Skipping unavailable playlist entry {}", item.index);
+            item.state = PlaylistItemState::Skipped;
+            item.error = Some("Entry is unavailable".to_string());
+            job.skipped_items += 1;
+            update_playlist_download_job(app_state, &job).await;
+            continue;
+        }
+
+        if !playlist_download_options.force {
+            let existing_library_entry =
+                match find_existing_library_entry(&library_dir, &job.items[item_index]).await {
+                    Ok(existing_library_entry) => existing_library_entry,
+                    Err(err) => {
+                        job.state = PlaylistDownloadState::Failed;
+                        job.error = Some(format!("{:#}", err));
+                        update_playlist_download_job(app_state, &job).await;
+                        return Err(err);
+                    }
+                };
+            if let Some(existing_library_entry) = existing_library_entry {
+                let item = &mut job.items[item_index];
+                info!(
+                    "Playlist entry {} is already in the library as {}",
+                    item.index, existing_library_entry.file_name
+                );
+                item.state = PlaylistItemState::AlreadyInLibrary;
+                item.library_files = vec![existing_library_entry.file_name.clone()];
+                item.existing_library_entry = Some(existing_library_entry);
+                job.skipped_items += 1;
+                update_playlist_download_job(app_state, &job).await;
+                continue;
+            }
+        }
+
+        job.items[item_index].state = PlaylistItemState::InProgress;
+        update_playlist_download_job(app_state, &job).await;
+
+        let item = &mut job.items[item_index];
+        match download_playlist_item(
+            app_state,
+            item,
+            &playlist_download_options.audio_download_options,
+            network_options,
+        )
+        .await
+        {
+            Ok(()) => {
+                item.state = PlaylistItemState::Completed;
+                job.completed_items += 1;
+            }
+            Err(err) => {
+                error!(
+                    "Failed to download playlist entry {}: {:#}",
+                    item.index, err
+                );
+                item.state = PlaylistItemState::Failed;
+                item.error = Some(format!("{:#}", err));
+                job.failed_items += 1;
+            }
+        }
+        update_playlist_download_job(app_state, &job).await;
+
+        if job.items[item_index].state == PlaylistItemState::Failed
+            && !playlist_download_options.skip_unavailable
+        {
+            warn!("Stopping playlist download after failed item");
+            for item in job.items.iter_mut().skip(item_index + 1) {
+                item.state = PlaylistItemState::Skipped;
+                item.error = Some("Skipped after a previous item failed".to_string());
+                job.skipped_items += 1;
+            }
+            break;
+        }
+    }
+
+    job.state = if job.failed_items == 0 {
+        PlaylistDownloadState::Completed
+    } else if job.completed_items > 0 {
+        PlaylistDownloadState::CompletedWithErrors
+    } else {
+        PlaylistDownloadState::Failed
+    };
+    update_playlist_download_job(app_state, &job).await;
+
+    info!(
+        "Playlist download finished. Completed: {}, failed: {}, skipped: {}",
+        job.completed_items, job.failed_items, job.skipped_items
+    );
+
+    Ok(job)
+}
+
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+async fn download_playlist_item(
+    app_state: &AppState,
+    item: &mut PlaylistItemDownload,
+    audio_download_options: &AudioDownloadOptions,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<(), anyhow::Error> {
+    let url = item.url.clone().context("Entry has no URL")?;
+    let config = app_state.config();
+    let audio_download_dir = Path::new(&config.audio_download_settings.download_dir);
+
+    info!(
+        "Downloading playlist entry {} using yt-dlp. Download ID: {}",
+        item.index, item.download_id
+    );
+
+    let command_execution_results = download_audio_with_yt_dlp(
+        app_state,
+        &url,
+        &item.download_id,
+        audio_download_options,
+        network_options,
+    )
+    .await?;
+    let command_completed_successfully = command_execution_results.command_completed_successfully;
+    item.command_execution_results = Some(command_execution_results);
+
+    if !command_completed_successfully {
+        anyhow::bail!("yt-dlp failed to download the entry");
+    }
+
+    let library_dir = Path::new(&config.library_settings.dir);
+    let library_files = move_downloaded_media_to_library(
+        app_state,
+        audio_download_dir,
+        library_dir,
+        &item.download_id,
+    )
+    .await?;
+    (item.library_files, item.post_processing) =
+        run_post_processing(app_state, library_dir, library_files, &[]).await;
+
+    Ok(())
+}
+
+#[instrument(err, ret(level = "debug"))]
+async fn find_existing_library_entry(
+    library_dir: &Path,
+    item: &PlaylistItemDownload,
+) -> Result<Option<LibraryIndexEntry>, anyhow::Error> {
+    match (&item.extractor, &item.entry_id) {
+        (Some(extractor), Some(entry_id)) => {
+            find_library_index_entry_by_source(library_dir, extractor, entry_id).await
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Stores the current state of the job in the playlist download registry.
+/// Jobs that finished more than [`FINISHED_PLAYLIST_DOWNLOAD_TTL`] ago are evicted, so the registry does not grow forever.
+pub async fn update_playlist_download_job(app_state: &AppState, job: &PlaylistDownloadJob) {
+    let mut playlist_downloads = app_state.playlist_downloads.write().await;
+
+    let finished_at = match job.state {
+        PlaylistDownloadState::InProgress => None,
+        _ => playlist_downloads
+            .get(&job.download_id)
+            .and_then(|entry| entry.finished_at)
+            .or_else(|| Some(Instant::now())),
+    };
+    playlist_downloads.insert(
+        job.download_id.clone(),
+        PlaylistDownloadEntry {
+            job: job.clone(),
+            finished_at,
+        },
+    );
+
+    playlist_downloads.retain(|_, entry| {
+        entry
+            .finished_at
+            .is_none_or(|finished_at| finished_at.elapsed() < FINISHED_PLAYLIST_DOWNLOAD_TTL)
+    });
+}
@@ -0,0 +1,436 @@
+use crate::handlers::shared::functions::convert::{
+    convert_audio_file, get_audio_codec_args, get_audio_format_extension, get_stream_args,
+    rewrite_audio_file,
+};
+use crate::handlers::shared::functions::files::{
+    get_library_file_path, move_file_without_overwriting, to_library_file_name,
+};
+use crate::handlers::shared::functions::identify::{get_identified_metadata, identify_audio_file};
+use crate::handlers::shared::functions::library::{read_library_index, update_library_index_entry};
+use crate::handlers::shared::functions::naming::{
+    get_identified_file_name_values, render_file_name_template,
+};
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use crate::handlers::shared::model::identify::IdentifiedMetadata;
+use crate::handlers::shared::model::library::LibraryIndexEntry;
+use crate::handlers::shared::model::postprocessing::{
+    PostProcessingStep, PostProcessingStepResult, PostProcessingStepState,
+};
+use crate::AppState;
+use anyhow::Context;
+use std::path::Path;
+use tokio::fs::{create_dir_all, remove_file};
+use tracing::{info, instrument, warn};
+
+/// Lowest AcoustID score of a match that is used to tag and organize files. Weaker matches are often wrong.
+const MIN_IDENTIFICATION_SCORE: f64 = 0.8;
+/// Maximum true peak and loudness range passed to ffmpeg's loudnorm filter along with the loudness target.
+const LOUDNORM_TRUE_PEAK: f64 = -1.5;
+const LOUDNORM_LOUDNESS_RANGE: f64 = 11.0;
+
+/// Outcome of a single step, with the new library file name if the step renamed the file.
+struct StepOutcome {
+    state: PostProcessingStepState,
+    message: Option<String>,
+    command_execution_results: Vec<CommandExecutionResults>,
+    renamed_library_file: Option<String>,
+}
+
+impl StepOutcome {
+    fn new(state: PostProcessingStepState, message: impl Into<String>) -> Self {
+        StepOutcome {
+            state,
+            message: Some(message.into()),
+            command_execution_results: Vec::new(),
+            renamed_library_file: None,
+        }
+    }
+
+    fn from_command(
+        command_execution_results: CommandExecutionResults,
+        completed_message: impl Into<String>,
+        failed_message: impl Into<String>,
+    ) -> Self {
+        let (state, message) = if command_execution_results.command_completed_successfully {
+            (PostProcessingStepState::Completed, completed_message.into())
+        } else {
+            (PostProcessingStepState::Failed, failed_message.into())
+        };
+
+        StepOutcome {
+            state,
+            message: Some(message),
+            command_execution_results: vec![command_execution_results],
+            renamed_library_file: None,
+        }
+    }
+
+    fn command_failed(
+        command_execution_results: CommandExecutionResults,
+        message: impl Into<String>,
+    ) -> Self {
+        StepOutcome {
+            state: PostProcessingStepState::Failed,
+            message: Some(message.into()),
+            command_execution_results: vec![command_execution_results],
+            renamed_library_file: None,
+        }
+    }
+}
+
+/// Runs the configured post-processing steps on each library file, except the steps in `skipped_steps`.
+///
+/// Steps never fail the download. A failed step leaves the file as it was, is reported with its error and
+/// the remaining steps still run. Returns the library file names after the steps renamed the files, with
+/// the result of every configured step for every file.
+#[instrument(ret(level = "debug"), skip(app_state))]
+pub async fn run_post_processing(
+    app_state: &AppState,
+    library_dir: &Path,
+    library_files: Vec<String>,
+    skipped_steps: &[PostProcessingStep],
+) -> (Vec<String>, Vec<PostProcessingStepResult>) {
+    let configured_steps = &app_state.config().post_processing_settings.steps;
+    if configured_steps.is_empty() {
+        return (library_files, Vec::new());
+    }
+
+    let mut processed_library_files = Vec::with_capacity(library_files.len());
+    let mut step_results = Vec::new();
+    for mut library_file in library_files {
+        for step in PostProcessingStep::ALL {
+            if !configured_steps.contains(&step) {
+                continue;
+            }
+
+            let step_outcome = if skipped_steps.contains(&step) {
+                StepOutcome::new(PostProcessingStepState::Skipped, "Skipped by the request")
+            } else {
+                info!("Running post-processing step {} on {}", step, library_file);
+                run_post_processing_step(app_state, library_dir, &library_file, step)
+                    .await
+                    .unwrap_or_else(|err| {
+                        warn!("Post-processing step {} failed: {:#}", step, err);
+                        StepOutcome::new(PostProcessingStepState::Failed, format!("{:#}", err))
+                    })
+            };
+
+            step_results.push(PostProcessingStepResult {
+                library_file: library_file.clone(),
+                step,
+                state: step_outcome.state,
+                message: step_outcome.message,
+                command_execution_results: step_outcome.command_execution_results,
+            });
+            if let Some(renamed_library_file) = step_outcome.renamed_library_file {
+                library_file = renamed_library_file;
+            }
+        }
+        processed_library_files.push(library_file);
+    }
+
+    (processed_library_files, step_results)
+}
+
+async fn run_post_processing_step(
+    app_state: &AppState,
+    library_dir: &Path,
+    library_file: &str,
+    step: PostProcessingStep,
+) -> Result<StepOutcome, anyhow::Error> {
+    let audio_file_path = get_library_file_path(library_dir, library_file)?;
+
+    match step {
+        PostProcessingStep::Identify => {
+            identify(app_state, library_dir, library_file, &audio_file_path).await
+        }
+        PostProcessingStep::Tag => {
+            tag(app_state, library_dir, library_file, &audio_file_path).await
+        }
+        PostProcessingStep::Normalize => normalize(app_state, &audio_file_path).await,
+        PostProcessingStep::Convert => {
+            convert(app_state, library_dir, library_file, &audio_file_path).await
+        }
+        PostProcessingStep::Organize => {
+            organize(app_state, library_dir, library_file, &audio_file_path).await
+        }
+    }
+}
+
+async fn identify(
+    app_state: &AppState,
+    library_dir: &Path,
+    library_file: &str,
+    audio_file_path: &Path,
+) -> Result<StepOutcome, anyhow::Error> {
+    let identification = identify_audio_file(app_state, audio_file_path).await?;
+    let fingerprinting_command_result = identification.fingerprinting_command_result.clone();
+    if !fingerprinting_command_result.command_completed_successfully {
+        return Ok(StepOutcome::command_failed(
+            fingerprinting_command_result,
+            "Failed to fingerprint the audio",
+        ));
+    }
+
+    let identified_metadata =
+        get_identified_metadata(&identification).context("No match found for the fingerprint")?;
+    if identified_metadata.acoustid_score < MIN_IDENTIFICATION_SCORE {
+        return Ok(StepOutcome {
+            state: PostProcessingStepState::Skipped,
+            message: Some(format!(
+                "Best match {} has a score of {:.2}, which is below {:.2}. The file is left unidentified",
+                describe_identified_metadata(&identified_metadata),
+                identified_metadata.acoustid_score,
+                MIN_IDENTIFICATION_SCORE
+            )),
+            command_execution_results: vec![fingerprinting_command_result],
+            renamed_library_file: None,
+        });
+    }
+
+    let message = format!(
+        "Identified as {} with a score of {:.2}",
+        describe_identified_metadata(&identified_metadata),
+        identified_metadata.acoustid_score
+    );
+    update_library_index_entry(app_state, library_dir, library_file, |entry| {
+        entry.identification = Some(identified_metadata);
+    })
+    .await?;
+
+    Ok(StepOutcome {
+        state: PostProcessingStepState::Completed,
+        message: Some(message),
+        command_execution_results: vec![fingerprinting_command_result],
+        renamed_library_file: None,
+    })
+}
+
+async fn tag(
+    app_state: &AppState,
+    library_dir: &Path,
+    library_file: &str,
+    audio_file_path: &Path,
+) -> Result<StepOutcome, anyhow::Error> {
+    let Some(identified_metadata) = get_library_index_entry(library_dir, library_file)
+        .await?
+        .and_then(|entry| entry.identification)
+    else {
+        return Ok(StepOutcome::new(
+            PostProcessingStepState::Skipped,
+            "The file was not identified, its tags are kept",
+        ));
+    };
+
+    let mut tags = vec![
+        ("title", Some(identified_metadata.title.clone())),
+        ("artist", identified_metadata.artist.clone()),
+        ("album", identified_metadata.album.clone()),
+        ("date", identified_metadata.release_date.clone()),
+        (
+            "MUSICBRAINZ_TRACKID",
+            Some(identified_metadata.musicbrainz_recording_id.clone()),
+        ),
+    ];
+    tags.retain(|(_, value)| value.is_some());
+
+    let mut args = vec![
+        "-map".to_string(),
+        "0".to_string(),
+        "-c".to_string(),
+        "copy".to_string(),
+    ];
+    for (key, value) in tags {
+        args.push("-metadata".to_string());
+        args.push(format!("{}={}", key, value.unwrap_or_default()));
+    }
+
+    let command_execution_results = rewrite_audio_file(app_state, audio_file_path, &args).await?;
+
+    Ok(StepOutcome::from_command(
+        command_execution_results,
+        format!(
+            "Tagged as {}",
+            describe_identified_metadata(&identified_metadata)
+        ),
+        "Failed to write tags using ffmpeg",
+    ))
+}
+
+async fn normalize(
+    app_state: &AppState,
+    audio_file_path: &Path,
+) -> Result<StepOutcome, anyhow::Error> {
+    let loudness_target = app_state.config().post_processing_settings.loudness_target;
+    let extension = audio_file_path
+        .extension()
+        .context("Failed to get audio file extension")?
+        .to_string_lossy()
+        .to_string();
+
+    let mut args = get_stream_args(&extension);
+    args.extend(get_audio_codec_args(&extension)?);
+    args.extend([
+        "-af".to_string(),
+        format!(
+            "loudnorm=I={}:TP={}:LRA={}",
+            loudness_target, LOUDNORM_TRUE_PEAK, LOUDNORM_LOUDNESS_RANGE
+        ),
+    ]);
+
+    let command_execution_results = rewrite_audio_file(app_state, audio_file_path, &args).await?;
+
+    Ok(StepOutcome::from_command(
+        command_execution_results,
+        format!("Normalized loudness to {} LUFS", loudness_target),
+        "Failed to normalize loudness using ffmpeg",
+    ))
+}
+
+async fn convert(
+    app_state: &AppState,
+    library_dir: &Path,
+    library_file: &str,
+    audio_file_path: &Path,
+) -> Result<StepOutcome, anyhow::Error> {
+    let preferred_audio_format = app_state
+        .config()
+        .post_processing_settings
+        .preferred_audio_format;
+    let Some(preferred_extension) = get_audio_format_extension(preferred_audio_format) else {
+        return Ok(StepOutcome::new(
+            PostProcessingStepState::Skipped,
+            "No preferred audio format is configured",
+        ));
+    };
+
+    let is_preferred_format = audio_file_path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(preferred_extension));
+    if is_preferred_format {
+        return Ok(StepOutcome::new(
+            PostProcessingStepState::Skipped,
+            format!(
+                "The file is already in the {} format",
+                preferred_audio_format
+            ),
+        ));
+    }
+
+    let (converted_file_path, command_execution_results) =
+        convert_audio_file(app_state, audio_file_path, preferred_audio_format).await?;
+    let Some(converted_file_path) = converted_file_path else {
+        return Ok(StepOutcome::command_failed(
+            command_execution_results,
+            format!(
+                "Failed to convert to {} using ffmpeg",
+                preferred_audio_format
+            ),
+        ));
+    };
+
+    let converted_library_file = to_library_file_name(library_dir, &converted_file_path)?;
+    rename_library_index_entry(
+        app_state,
+        library_dir,
+        library_file,
+        &converted_library_file,
+    )
+    .await?;
+    remove_file(audio_file_path)
+        .await
+        .context("Failed to remove the file converted to the preferred format")?;
+
+    Ok(StepOutcome {
+        state: PostProcessingStepState::Completed,
+        message: Some(format!(
+            "Converted to {} as {}",
+            preferred_audio_format, converted_library_file
+        )),
+        command_execution_results: vec![command_execution_results],
+        renamed_library_file: Some(converted_library_file),
+    })
+}
+
+async fn organize(
+    app_state: &AppState,
+    library_dir: &Path,
+    library_file: &str,
+    audio_file_path: &Path,
+) -> Result<StepOutcome, anyhow::Error> {
+    let entry = get_library_index_entry(library_dir, library_file).await?;
+    let Some((entry, identified_metadata)) = entry.and_then(|entry| {
+        let identified_metadata = entry.identification.clone()?;
+        Some((entry, identified_metadata))
+    }) else {
+        return Ok(StepOutcome::new(
+            PostProcessingStepState::Skipped,
+            "The file was not identified, it keeps the name it was downloaded with",
+        ));
+    };
+
+    let extension = audio_file_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file_name_values =
+        get_identified_file_name_values(&entry, &identified_metadata, &extension);
+    let organized_library_file = render_file_name_template(
+        &app_state.config().library_settings.file_name_template,
+        &file_name_values,
+    );
+    if organized_library_file == library_file {
+        return Ok(StepOutcome::new(
+            PostProcessingStepState::Skipped,
+            "The file is already organized",
+        ));
+    }
+
+    let destination_path = get_library_file_path(library_dir, &organized_library_file)?;
+    if let Some(destination_dir) = destination_path.parent() {
+        create_dir_all(destination_dir)
+            .await
+            .context("Failed to create library subdirectory")?;
+    }
+    let moved_path = move_file_without_overwriting(audio_file_path, &destination_path)
+        .await
+        .context("Failed to move the file to its organized location")?;
+    let moved_library_file = to_library_file_name(library_dir, &moved_path)?;
+    rename_library_index_entry(app_state, library_dir, library_file, &moved_library_file).await?;
+
+    Ok(StepOutcome {
+        state: PostProcessingStepState::Completed,
+        message: Some(format!("Moved to {}", moved_library_file)),
+        command_execution_results: Vec::new(),
+        renamed_library_file: Some(moved_library_file),
+    })
+}
+
+async fn get_library_index_entry(
+    library_dir: &Path,
+    library_file: &str,
+) -> Result<Option<LibraryIndexEntry>, anyhow::Error> {
+    Ok(read_library_index(library_dir)
+        .await?
+        .entries
+        .remove(library_file))
+}
+
+async fn rename_library_index_entry(
+    app_state: &AppState,
+    library_dir: &Path,
+    library_file: &str,
+    renamed_library_file: &str,
+) -> Result<(), anyhow::Error> {
+    update_library_index_entry(app_state, library_dir, library_file, |entry| {
+        entry.file_name = renamed_library_file.to_string();
+    })
+    .await
+}
+
+fn describe_identified_metadata(identified_metadata: &IdentifiedMetadata) -> String {
+    match &identified_metadata.artist {
+        Some(artist) => format!("{} - {}", artist, identified_metadata.title),
+        None => identified_metadata.title.clone(),
+    }
+}
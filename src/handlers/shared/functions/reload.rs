@@ -0,0 +1,131 @@
+use crate::config::{
+    get_changed_settings, get_restart_required_settings, keep_restart_required_settings,
+};
+use crate::handlers::shared::functions::yt_dlp::warn_if_cookies_file_is_shared;
+use crate::handlers::shared::model::reload::ConfigReloadResponse;
+use crate::AppState;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info, instrument, warn};
+
+/// How often the config file is checked for changes.
+const CONFIG_FILE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Loads the config again from the config file, environment variables and flags and replaces the running config.
+/// Settings that are only applied on startup keep their current values and are reported as requiring a restart.
+/// The running config is kept if the new config is invalid.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn reload_config(app_state: &AppState) -> Result<ConfigReloadResponse, anyhow::Error> {
+    let _config_reload_guard = app_state.config_reload_lock.lock().await;
+
+    let running_config = app_state.config();
+    let mut config = app_state.config_source.load()?;
+
+    let restart_required_settings =
+        get_restart_required_settings(&get_changed_settings(&running_config, &config));
+    keep_restart_required_settings(&mut config, &running_config);
+    let applied_settings = get_changed_settings(&running_config, &config);
+
+    let config_file = config
+        .config_file
+        .as_ref()
+        .map(|config_file| config_file.to_string_lossy().to_string());
+    let is_cookies_file_changed =
+        config.yt_dlp_settings.cookies_file != running_config.yt_dlp_settings.cookies_file;
+    app_state.config.replace(config);
+
+    if applied_settings.is_empty() {
+        info!("Reloaded config without changes");
+    } else {
+        info!(
+            "Reloaded config and applied: {}",
+            applied_settings.join(", ")
+        );
+    }
+    if !restart_required_settings.is_empty() {
+        warn!(
+            "Restart the server to apply: {}",
+            restart_required_settings.join(", ")
+        );
+    }
+    if is_cookies_file_changed {
+        warn_if_cookies_file_is_shared(app_state).await;
+    }
+
+    Ok(ConfigReloadResponse {
+        config_file,
+        applied_settings,
+        restart_required_settings,
+    })
+}
+
+/// Starts reloading the config when the config file changes and, on Unix, when the server receives `SIGHUP`.
+/// The config file is polled for changes of its modification time and size, which works on all platforms and file systems.
+pub fn spawn_config_reloaders(app_state: AppState) {
+    #[cfg(unix)]
+    spawn_hangup_signal_listener(app_state.clone());
+
+    let Some(config_file) = app_state.config().config_file.clone() else {
+        info!("No config file found, config file changes are not watched");
+        return;
+    };
+
+    info!("Watching config file {} for changes", config_file.display());
+
+    tokio::spawn(async move {
+        let mut last_config_file_state = get_file_state(&config_file);
+        loop {
+            tokio::time::sleep(CONFIG_FILE_POLL_INTERVAL).await;
+
+            let config_file_state = get_file_state(&config_file);
+            if config_file_state == last_config_file_state {
+                continue;
+            }
+            last_config_file_state = config_file_state;
+
+            if last_config_file_state.is_none() {
+                warn!(
+                    "Config file {} was removed, keeping the running config",
+                    config_file.display()
+                );
+                continue;
+            }
+
+            info!("Config file {} changed, reloading", config_file.display());
+            if let Err(err) = reload_config(&app_state).await {
+                error!("Failed to reload config: {:#}", err);
+            }
+        }
+    });
+}
+
+#[cfg(unix)]
+fn spawn_hangup_signal_listener(app_state: AppState) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup_signals = match signal(SignalKind::hangup()) {
+        Ok(hangup_signals) => hangup_signals,
+        Err(err) => {
+            error!(
+                "Failed to listen for SIGHUP, reload the config using the API instead: {}",
+                err
+            );
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        while hangup_signals.recv().await.is_some() {
+            info!("Received SIGHUP, reloading config");
+            if let Err(err) = reload_config(&app_state).await {
+                error!("Failed to reload config: {:#}", err);
+            }
+        }
+    });
+}
+
+/// Returns the modification time and size of the file, or `None` if it cannot be read.
+pub fn get_file_state(file_path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(file_path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
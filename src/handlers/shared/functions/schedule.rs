@@ -0,0 +1,261 @@
+use crate::handlers::shared::model::subscriptions::{CronFields, CronSchedule};
+use time::{Date, Duration, OffsetDateTime, Time};
+
+/// How far into the future the next matching time is searched for. Schedules such as `0 0 31 2 *` never match.
+const MAX_SCHEDULE_LOOKAHEAD: Duration = Duration::days(366 * 5);
+
+/// Parses a five field cron expression or one of the `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` aliases.
+pub fn parse_cron_expression(expression: &str) -> Result<CronFields, String> {
+    let expanded_expression = match expression.trim() {
+        "@hourly" => "0 * * * *",
+        "@daily" | "@midnight" => "0 0 * * *",
+        "@weekly" => "0 0 * * 0",
+        "@monthly" => "0 0 1 * *",
+        "@yearly" | "@annually" => "0 0 1 1 *",
+        expression => expression,
+    };
+
+    let fields: Vec<&str> = expanded_expression.split_whitespace().collect();
+    let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+        return Err(format!(
+            "Invalid schedule: {}. Expected 5 fields: minute hour day-of-month month day-of-week",
+            expression
+        ));
+    };
+
+    let mut days_of_week_mask = parse_cron_field(days_of_week, 0, 7)
+        .map_err(|err| format!("Invalid day of week in schedule: {}", err))?;
+    // Both 0 and 7 mean Sunday
+    if days_of_week_mask & (1 << 7) != 0 {
+        days_of_week_mask = (days_of_week_mask & !(1 << 7)) | 1;
+    }
+
+    Ok(CronFields {
+        minutes: parse_cron_field(minutes, 0, 59)
+            .map_err(|err| format!("Invalid minute in schedule: {}", err))?,
+        hours: parse_cron_field(hours, 0, 23)
+            .map_err(|err| format!("Invalid hour in schedule: {}", err))?,
+        days_of_month: parse_cron_field(days_of_month, 1, 31)
+            .map_err(|err| format!("Invalid day of month in schedule: {}", err))?,
+        months: parse_cron_field(months, 1, 12)
+            .map_err(|err| format!("Invalid month in schedule: {}", err))?,
+        days_of_week: days_of_week_mask,
+        days_of_month_restricted: days_of_month != "*",
+        days_of_week_restricted: days_of_week != "*",
+    })
+}
+
+/// Returns the first time matching the schedule that is strictly after `after`, truncated to whole minutes.
+pub fn get_next_schedule_time(
+    schedule: &CronSchedule,
+    after: OffsetDateTime,
+) -> Option<OffsetDateTime> {
+    let fields = &schedule.fields;
+    let mut candidate = after.replace_time(Time::from_hms(after.hour(), after.minute(), 0).ok()?)
+        + Duration::minutes(1);
+    let limit = after + MAX_SCHEDULE_LOOKAHEAD;
+
+    while candidate <= limit {
+        if !is_bit_set(fields.months, u8::from(candidate.month())) {
+            let (year, month) = match candidate.month().next() {
+                time::Month::January => (candidate.year() + 1, time::Month::January),
+                next_month => (candidate.year(), next_month),
+            };
+            candidate = candidate
+                .replace_date(Date::from_calendar_date(year, month, 1).ok()?)
+                .replace_time(Time::MIDNIGHT);
+            continue;
+        }
+
+        if !is_day_matching(fields, candidate) {
+            candidate = candidate
+                .replace_date(candidate.date().next_day()?)
+                .replace_time(Time::MIDNIGHT);
+            continue;
+        }
+
+        if !is_bit_set(fields.hours, candidate.hour()) {
+            candidate = candidate.replace_time(Time::from_hms(candidate.hour(), 0, 0).ok()?)
+                + Duration::hours(1);
+            continue;
+        }
+
+        if !is_bit_set(fields.minutes, candidate.minute()) {
+            candidate += Duration::minutes(1);
+            continue;
+        }
+
+        return Some(candidate);
+    }
+
+    None
+}
+
+/// Parses a single cron field into a bit mask of the matched values.
+fn parse_cron_field(field: &str, min: u8, max: u8) -> Result<u64, String> {
+    let mut mask = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u8>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step: {}", part))?,
+            ),
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_cron_value(start, min, max)?,
+                parse_cron_value(end, min, max)?,
+            )
+        } else {
+            let value = parse_cron_value(range, min, max)?;
+            // A single value with a step such as `5/15` runs from the value to the maximum
+            if part.contains('/') {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start > end {
+            return Err(format!("invalid range: {}", part));
+        }
+
+        for value in (start..=end).step_by(usize::from(step)) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+fn parse_cron_value(value: &str, min: u8, max: u8) -> Result<u8, String> {
+    value
+        .parse::<u8>()
+        .ok()
+        .filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| format!("{} is not a number from {} to {}", value, min, max))
+}
+
+fn is_day_matching(fields: &CronFields, date_time: OffsetDateTime) -> bool {
+    let day_of_month_matches = is_bit_set(fields.days_of_month, date_time.day());
+    let day_of_week_matches = is_bit_set(
+        fields.days_of_week,
+        date_time.weekday().number_days_from_sunday(),
+    );
+
+    // Same as cron: when both day fields are restricted, a day matching either of them is used
+    if fields.days_of_month_restricted && fields.days_of_week_restricted {
+        day_of_month_matches || day_of_week_matches
+    } else {
+        day_of_month_matches && day_of_week_matches
+    }
+}
+
+fn is_bit_set(mask: u64, value: u8) -> bool {
+    mask & (1 << value) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::{Month, PrimitiveDateTime};
+
+    fn utc(year: i32, month: Month, day: u8, hour: u8, minute: u8) -> OffsetDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(year, month, day).unwrap(),
+            Time::from_hms(hour, minute, 0).unwrap(),
+        )
+        .assume_utc()
+    }
+
+    fn schedule(expression: &str) -> CronSchedule {
+        CronSchedule::try_from(expression.to_string()).unwrap()
+    }
+
+    #[test]
+    fn parses_values_ranges_lists_and_steps() {
+        let fields = parse_cron_expression("*/15 1-3,5 10/10 * 1-5").unwrap();
+
+        assert_eq!(fields.minutes, 1 << 0 | 1 << 15 | 1 << 30 | 1 << 45);
+        assert_eq!(fields.hours, 1 << 1 | 1 << 2 | 1 << 3 | 1 << 5);
+        assert_eq!(fields.days_of_month, 1 << 10 | 1 << 20 | 1 << 30);
+        assert_eq!(fields.months, 0b1_1111_1111_1110);
+        assert_eq!(fields.days_of_week, 0b11_1110);
+        assert!(fields.days_of_month_restricted);
+        assert!(fields.days_of_week_restricted);
+    }
+
+    #[test]
+    fn expands_aliases_and_maps_day_7_to_sunday() {
+        assert_eq!(
+            parse_cron_expression("@daily").unwrap(),
+            parse_cron_expression("0 0 * * *").unwrap()
+        );
+        assert_eq!(parse_cron_expression("0 0 * * 7").unwrap().days_of_week, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "a * * * *",
+            "-1 * * * *",
+        ] {
+            assert!(
+                parse_cron_expression(expression).is_err(),
+                "{:?} should be rejected",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn finds_next_time_strictly_after_the_given_time() {
+        assert_eq!(
+            get_next_schedule_time(&schedule("30 * * * *"), utc(2024, Month::May, 1, 10, 30)),
+            Some(utc(2024, Month::May, 1, 11, 30))
+        );
+        assert_eq!(
+            get_next_schedule_time(&schedule("@yearly"), utc(2024, Month::December, 31, 23, 59)),
+            Some(utc(2025, Month::January, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn matches_either_day_field_when_both_are_restricted() {
+        // 2024-05-01 is a Wednesday, so Friday the 3rd comes before the 13th
+        assert_eq!(
+            get_next_schedule_time(&schedule("0 12 13 * 5"), utc(2024, Month::May, 1, 0, 0)),
+            Some(utc(2024, Month::May, 3, 12, 0))
+        );
+        assert_eq!(
+            get_next_schedule_time(&schedule("0 12 * * 5"), utc(2024, Month::May, 3, 12, 0)),
+            Some(utc(2024, Month::May, 10, 12, 0))
+        );
+    }
+
+    #[test]
+    fn returns_none_for_schedules_that_never_match() {
+        assert_eq!(
+            get_next_schedule_time(&schedule("0 0 31 2 *"), utc(2024, Month::May, 1, 0, 0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,421 @@
+use crate::handlers::errors::{ErrorCode, RequestError};
+use crate::handlers::shared::functions::commands::run_command;
+use crate::handlers::shared::functions::files::{
+    reserve_free_file_path, sanitize_file_name, to_library_file_name,
+};
+use crate::handlers::shared::functions::tools::get_ffmpeg_executable_path;
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use crate::handlers::shared::model::library::LibraryChapter;
+use crate::handlers::shared::model::split::{FfprobeChapters, SplitTrack, TrackSegment};
+use crate::AppState;
+use anyhow::Context;
+use std::path::Path;
+use tokio::fs::remove_file;
+use tracing::{info, instrument, warn};
+
+/// Cue sheet timestamps are in MM:SS:FF format with 75 frames per second.
+const CUE_FRAMES_PER_SECOND: f64 = 75.0;
+
+/// Parses the tracks of a cue sheet. Each track starts at its `INDEX 01` and ends where the next track starts,
+/// so the start times have to increase from track to track.
+#[instrument(err, ret(level = "debug"), skip(cue_sheet))]
+pub fn parse_cue_sheet(cue_sheet: &str) -> Result<Vec<TrackSegment>, anyhow::Error> {
+    let mut album_performer = None;
+    let mut segments: Vec<TrackSegment> = Vec::new();
+    let mut current_segment: Option<TrackSegment> = None;
+
+    for line in cue_sheet.lines().map(str::trim) {
+        let (command, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        match command.to_ascii_uppercase().as_str() {
+            "TRACK" => {
+                if let Some(segment) = current_segment.take() {
+                    segments.push(segment);
+                }
+                current_segment = Some(TrackSegment {
+                    title: None,
+                    performer: album_performer.clone(),
+                    start_time: -1.0,
+                    end_time: None,
+                });
+            }
+            "TITLE" => {
+                if let Some(segment) = current_segment.as_mut() {
+                    segment.title = Some(unquote_cue_value(arguments));
+                }
+            }
+            "PERFORMER" => match current_segment.as_mut() {
+                Some(segment) => segment.performer = Some(unquote_cue_value(arguments)),
+                None => album_performer = Some(unquote_cue_value(arguments)),
+            },
+            "INDEX" => {
+                let (index_number, timestamp) =
+                    arguments.trim().split_once(' ').ok_or_else(|| {
+                        RequestError::new(
+                            ErrorCode::InvalidRequest,
+                            format!("Invalid cue sheet INDEX line: {}", line),
+                        )
+                    })?;
+                if index_number == "01" {
+                    let segment = current_segment.as_mut().ok_or_else(|| {
+                        RequestError::new(
+                            ErrorCode::InvalidRequest,
+                            "Cue sheet INDEX line found before any TRACK",
+                        )
+                    })?;
+                    segment.start_time = parse_cue_timestamp(timestamp.trim())?;
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(segment) = current_segment.take() {
+        segments.push(segment);
+    }
+
+    if segments.is_empty() {
+        return Err(RequestError::new(
+            ErrorCode::InvalidRequest,
+            "Cue sheet does not contain any tracks",
+        )
+        .into());
+    }
+    if segments.iter().any(|segment| segment.start_time < 0.0) {
+        return Err(RequestError::new(
+            ErrorCode::InvalidRequest,
+            "Every cue sheet track needs an INDEX 01 line",
+        )
+        .into());
+    }
+    if segments
+        .windows(2)
+        .any(|segments| segments[1].start_time <= segments[0].start_time)
+    {
+        return Err(RequestError::new(
+            ErrorCode::InvalidRequest,
+            "Cue sheet track start times have to increase from track to track",
+        )
+        .into());
+    }
+
+    let next_start_times: Vec<f64> = segments
+        .iter()
+        .skip(1)
+        .map(|segment| segment.start_time)
+        .collect();
+    for (segment, next_start_time) in segments.iter_mut().zip(next_start_times) {
+        segment.end_time = Some(next_start_time);
+    }
+
+    Ok(segments)
+}
+
+/// Converts chapters stored in the library index into track segments.
+pub fn chapters_to_track_segments(chapters: &[LibraryChapter]) -> Vec<TrackSegment> {
+    chapters
+        .iter()
+        .map(|chapter| TrackSegment {
+            title: chapter.title.clone(),
+            performer: None,
+            start_time: chapter.start_time,
+            end_time: Some(chapter.end_time),
+        })
+        .collect()
+}
+
+/// Reads the chapters embedded in an audio file using ffprobe shipped with the managed ffmpeg.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn probe_embedded_chapters(
+    app_state: &AppState,
+    audio_file_path: &Path,
+) -> Result<Vec<TrackSegment>, anyhow::Error> {
+    let ffmpeg_executable_path = get_ffmpeg_executable_path(app_state)
+        .await
+        .context("Failed to get ffmpeg executable path")?;
+    let ffprobe_executable_path =
+        ffmpeg_executable_path.with_file_name(if std::env::consts::OS == "windows" {
+            "ffprobe.exe"
+        } else {
+            "ffprobe"
+        });
+
+    let command_execution_results = run_command(
+        &ffprobe_executable_path,
+        &[
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_chapters",
+            &audio_file_path.to_string_lossy(),
+        ],
+    )
+    .await
+    .context("Failed to read chapters using ffprobe")?;
+
+    if !command_execution_results.command_completed_successfully {
+        anyhow::bail!(
+            "ffprobe failed to read chapters: {}",
+            command_execution_results.stderr.unwrap_or_default()
+        );
+    }
+
+    let ffprobe_chapters: FfprobeChapters = serde_json::from_str(
+        command_execution_results
+            .stdout
+            .as_deref()
+            .context("ffprobe did not print chapters")?,
+    )
+    .context("Failed to parse chapters printed by ffprobe")?;
+
+    ffprobe_chapters
+        .chapters
+        .into_iter()
+        .map(|chapter| {
+            Ok(TrackSegment {
+                title: chapter.tags.title,
+                performer: None,
+                start_time: chapter
+                    .start_time
+                    .parse()
+                    .context("Invalid chapter start time")?,
+                end_time: Some(
+                    chapter
+                        .end_time
+                        .parse()
+                        .context("Invalid chapter end time")?,
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Extracts each segment of the audio file into a separate file next to it using ffmpeg stream copy.
+/// Each track is tagged with its track number, title and performer. Existing library files are never overwritten.
+/// If any track fails, the tracks written before it are removed, so the split either creates all tracks or none.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn split_audio_file(
+    app_state: &AppState,
+    audio_file_path: &Path,
+    segments: &[TrackSegment],
+    library_dir: &Path,
+) -> Result<(Vec<SplitTrack>, Vec<CommandExecutionResults>), anyhow::Error> {
+    let ffmpeg_executable_path = get_ffmpeg_executable_path(app_state)
+        .await
+        .context("Failed to get ffmpeg executable path")?;
+
+    let file_stem = audio_file_path
+        .file_stem()
+        .context("Failed to get audio file name")?
+        .to_string_lossy()
+        .to_string();
+    let extension = audio_file_path
+        .extension()
+        .context("Failed to get audio file extension")?
+        .to_string_lossy()
+        .to_string();
+
+    let output_dir = audio_file_path
+        .parent()
+        .context("Failed to get audio file directory")?;
+
+    let total_tracks = segments.len();
+    let mut tracks = Vec::with_capacity(total_tracks);
+    let mut all_command_execution_results = Vec::with_capacity(total_tracks);
+
+    // Paths of the tracks written so far, including the reserved path of the track being extracted
+    let mut output_paths = Vec::with_capacity(total_tracks);
+    let split_result = async {
+        for (segment_index, segment) in segments.iter().enumerate() {
+            let track_number = segment_index + 1;
+            let title = segment
+                .title
+                .clone()
+                .unwrap_or_else(|| format!("Track {}", track_number));
+            let output_path =
+                reserve_free_file_path(&output_dir.join(sanitize_file_name(&format!(
+                    "{} - {:02} - {}.{}",
+                    file_stem, track_number, title, extension
+                ))))
+                .await?;
+            output_paths.push(output_path.clone());
+            let file_name = to_library_file_name(library_dir, &output_path)?;
+
+            info!("Extracting track {} to {}", track_number, file_name);
+
+            let mut args = vec![
+                "-hide_banner".to_string(),
+                "-nostdin".to_string(),
+                // The output path is reserved with an empty file
+                "-y".to_string(),
+                "-i".to_string(),
+                audio_file_path.to_string_lossy().to_string(),
+                "-ss".to_string(),
+                format!("{:.3}", segment.start_time),
+            ];
+            if let Some(end_time) = segment.end_time {
+                args.push("-to".to_string());
+                args.push(format!("{:.3}", end_time));
+            }
+            args.extend([
+                "-map".to_string(),
+                "0:a".to_string(),
+                "-map_chapters".to_string(),
+                "-1".to_string(),
+                "-c".to_string(),
+                "copy".to_string(),
+                "-metadata".to_string(),
+                format!("track={}/{}", track_number, total_tracks),
+                "-metadata".to_string(),
+                format!("title={}", title),
+            ]);
+            if let Some(performer) = &segment.performer {
+                args.push("-metadata".to_string());
+                args.push(format!("artist={}", performer));
+            }
+            args.push(output_path.to_string_lossy().to_string());
+
+            let command_execution_results = match run_command(
+                &ffmpeg_executable_path,
+                &args.iter().map(String::as_str).collect::<Vec<_>>(),
+            )
+            .await
+            {
+                Ok(command_execution_results) => command_execution_results,
+                Err(err) => return Err(err).context("Failed to split audio file using ffmpeg"),
+            };
+            let command_completed_successfully =
+                command_execution_results.command_completed_successfully;
+            all_command_execution_results.push(command_execution_results);
+
+            if !command_completed_successfully {
+                anyhow::bail!("ffmpeg failed to extract track {}", track_number);
+            }
+
+            tracks.push(SplitTrack {
+                track_number,
+                title,
+                start_time: segment.start_time,
+                end_time: segment.end_time,
+                file_name,
+            });
+        }
+
+        Ok::<(), anyhow::Error>(())
+    }
+    .await;
+
+    if let Err(err) = split_result {
+        // Removing the tracks written before the failure lets a retry use the same file names
+        for output_path in &output_paths {
+            if let Err(err) = remove_file(output_path).await {
+                warn!(
+                    "Failed to remove track {} of failed split: {}",
+                    output_path.display(),
+                    err
+                );
+            }
+        }
+        return Err(err);
+    }
+
+    Ok((tracks, all_command_execution_results))
+}
+
+fn parse_cue_timestamp(timestamp: &str) -> Result<f64, anyhow::Error> {
+    let parts: Vec<&str> = timestamp.split(':').collect();
+    let invalid_timestamp_error = || {
+        RequestError::new(
+            ErrorCode::InvalidRequest,
+            format!("Invalid cue sheet timestamp: {}", timestamp),
+        )
+    };
+    let [minutes, seconds, frames] = parts[..] else {
+        return Err(invalid_timestamp_error().into());
+    };
+
+    let parse_part = |part: &str| part.parse::<u32>().map_err(|_| invalid_timestamp_error());
+
+    Ok(f64::from(parse_part(minutes)?) * 60.0
+        + f64::from(parse_part(seconds)?)
+        + f64::from(parse_part(frames)?) / CUE_FRAMES_PER_SECOND)
+}
+
+fn unquote_cue_value(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cue_sheet_tracks() {
+        let cue_sheet = r#"
+PERFORMER "Album Artist"
+TITLE "Album"
+FILE "album.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "First"
+    INDEX 00 00:00:00
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Second"
+    PERFORMER "Guest"
+    INDEX 01 03:25:37
+"#;
+
+        let segments = parse_cue_sheet(cue_sheet).unwrap();
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].title.as_deref(), Some("First"));
+        assert_eq!(segments[0].performer.as_deref(), Some("Album Artist"));
+        assert_eq!(segments[0].start_time, 0.0);
+        assert_eq!(segments[0].end_time, Some(segments[1].start_time));
+        assert_eq!(segments[1].title.as_deref(), Some("Second"));
+        assert_eq!(segments[1].performer.as_deref(), Some("Guest"));
+        assert_eq!(segments[1].start_time, 205.0 + 37.0 / 75.0);
+        assert_eq!(segments[1].end_time, None);
+    }
+
+    #[test]
+    fn rejects_cue_sheets_without_usable_tracks() {
+        for cue_sheet in [
+            "",
+            "TITLE \"Album\"",
+            "TRACK 01 AUDIO\nTITLE \"No index\"",
+            "INDEX 01 00:00:00",
+            "TRACK 01 AUDIO\nINDEX 01",
+            "TRACK 01 AUDIO\nINDEX 01 00:00",
+            "TRACK 01 AUDIO\nINDEX 01 01:00:00\nTRACK 02 AUDIO\nINDEX 01 01:00:00",
+            "TRACK 01 AUDIO\nINDEX 01 02:00:00\nTRACK 02 AUDIO\nINDEX 01 01:00:00",
+        ] {
+            let err = parse_cue_sheet(cue_sheet).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<RequestError>().map(|err| err.code),
+                Some(ErrorCode::InvalidRequest),
+                "{:?} should be rejected",
+                cue_sheet
+            );
+        }
+    }
+
+    #[test]
+    fn parses_cue_timestamps() {
+        assert_eq!(parse_cue_timestamp("00:00:00").unwrap(), 0.0);
+        assert_eq!(parse_cue_timestamp("01:02:00").unwrap(), 62.0);
+        assert_eq!(parse_cue_timestamp("74:30:15").unwrap(), 4470.2);
+
+        for timestamp in ["", "1:2", "1:2:3:4", "aa:00:00", "-1:00:00", "00:00:0.5"] {
+            assert!(
+                parse_cue_timestamp(timestamp).is_err(),
+                "{:?} should be rejected",
+                timestamp
+            );
+        }
+    }
+}
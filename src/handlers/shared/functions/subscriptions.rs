@@ -0,0 +1,200 @@
+use crate::handlers::shared::functions::playlists::{download_playlist, start_playlist_download};
+use crate::handlers::shared::functions::schedule::get_next_schedule_time;
+use crate::handlers::shared::functions::urls::validate_media_url;
+use crate::handlers::shared::model::media::{
+    PlaylistDownloadOptions, PlaylistDownloadState, YtDlpNetworkOptions,
+};
+use crate::handlers::shared::model::subscriptions::{
+    Subscription, SubscriptionStore, SubscriptionSync,
+};
+use crate::AppState;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::fs::{create_dir_all, read_to_string, rename, write};
+use tracing::{error, info, instrument, warn};
+
+pub const SUBSCRIPTIONS_FILE_NAME: &str = "subscriptions.json";
+
+/// How often the scheduler checks for subscriptions that are due for a sync.
+const SUBSCRIPTION_SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reads the subscriptions stored in the data directory. Returns an empty store if there are none yet.
+#[instrument(err, ret(level = "debug"))]
+pub async fn read_subscriptions(data_dir: &Path) -> Result<SubscriptionStore, anyhow::Error> {
+    let subscriptions_path = data_dir.join(SUBSCRIPTIONS_FILE_NAME);
+    if !subscriptions_path.exists() {
+        return Ok(SubscriptionStore::default());
+    }
+
+    let subscriptions_contents = read_to_string(&subscriptions_path)
+        .await
+        .context("Failed to read subscriptions")?;
+
+    serde_json::from_str(&subscriptions_contents).context("Failed to parse subscriptions as JSON")
+}
+
+/// Applies `update` to the stored subscriptions and persists the result.
+/// Updates are serialized so the scheduler and API handlers do not overwrite each other's changes.
+#[instrument(err, skip(app_state, update))]
+pub async fn update_subscriptions<T>(
+    app_state: &AppState,
+    update: impl FnOnce(&mut SubscriptionStore) -> T,
+) -> Result<T, anyhow::Error> {
+    let _subscriptions_guard = app_state.subscriptions_lock.lock().await;
+
+    let config = app_state.config();
+    let data_dir = Path::new(&config.server_settings.data_dir);
+    let mut subscription_store = read_subscriptions(data_dir).await?;
+    let update_result = update(&mut subscription_store);
+    write_subscriptions(data_dir, &subscription_store).await?;
+
+    Ok(update_result)
+}
+
+/// Starts the in-process scheduler that periodically syncs subscriptions that are due.
+/// Due subscriptions are synced one after another, so syncs never overlap.
+pub fn spawn_subscription_scheduler(app_state: AppState) {
+    info!("Starting subscription scheduler");
+
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = sync_due_subscriptions(&app_state).await {
+                error!("Failed to sync subscriptions: {:#}", err);
+            }
+            tokio::time::sleep(SUBSCRIPTION_SCHEDULER_INTERVAL).await;
+        }
+    });
+}
+
+#[instrument(err, skip(app_state))]
+async fn sync_due_subscriptions(app_state: &AppState) -> Result<(), anyhow::Error> {
+    let now = OffsetDateTime::now_utc();
+    let due_subscriptions: Vec<Subscription> =
+        read_subscriptions(Path::new(&app_state.config().server_settings.data_dir))
+            .await?
+            .subscriptions
+            .into_iter()
+            .filter(|subscription| {
+                subscription
+                    .next_sync_at
+                    .is_some_and(|next_sync_at| next_sync_at <= now)
+            })
+            .collect();
+
+    for subscription in due_subscriptions {
+        let subscription_sync = sync_subscription(app_state, &subscription).await;
+        let next_sync_at =
+            get_next_schedule_time(&subscription.schedule, OffsetDateTime::now_utc());
+
+        let is_subscription_present = update_subscriptions(app_state, |subscription_store| {
+            match subscription_store
+                .subscriptions
+                .iter_mut()
+                .find(|stored_subscription| stored_subscription.id == subscription.id)
+            {
+                Some(stored_subscription) => {
+                    stored_subscription.last_sync = Some(subscription_sync);
+                    stored_subscription.next_sync_at = next_sync_at;
+                    true
+                }
+                None => false,
+            }
+        })
+        .await?;
+
+        if !is_subscription_present {
+            warn!(
+                "Subscription {} was deleted while it was synced",
+                subscription.id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Downloads new items of the subscribed channel or playlist. Items already in the library are skipped.
+#[instrument(ret(level = "debug"), skip(app_state))]
+async fn sync_subscription(app_state: &AppState, subscription: &Subscription) -> SubscriptionSync {
+    info!("Syncing subscription {}", subscription.id);
+
+    let started_at = OffsetDateTime::now_utc();
+    let playlist_download_options = PlaylistDownloadOptions {
+        skip_unavailable: true,
+        force: false,
+        max_items: Some(subscription.max_items_per_sync),
+        audio_download_options: subscription.audio_download_options.clone(),
+    };
+
+    // The URL policy can change after the subscription was created, so the URL is checked on every sync
+    let download_result =
+        match validate_media_url(&app_state.config().url_policy_settings, &subscription.url) {
+            // Scheduled syncs use the server network settings
+            Ok(subscription_url) => {
+                match start_playlist_download(app_state, &subscription_url).await {
+                    Ok(job) => {
+                        download_playlist(
+                            app_state,
+                            job,
+                            &playlist_download_options,
+                            &YtDlpNetworkOptions::default(),
+                        )
+                        .await
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            Err(err) => Err(err.into()),
+        };
+
+    match download_result {
+        Ok(job) => SubscriptionSync {
+            started_at,
+            finished_at: OffsetDateTime::now_utc(),
+            download_id: Some(job.download_id),
+            state: job.state,
+            completed_items: job.completed_items,
+            failed_items: job.failed_items,
+            skipped_items: job.skipped_items,
+            error: job.error,
+        },
+        Err(err) => SubscriptionSync {
+            started_at,
+            finished_at: OffsetDateTime::now_utc(),
+            download_id: None,
+            state: PlaylistDownloadState::Failed,
+            completed_items: 0,
+            failed_items: 0,
+            skipped_items: 0,
+            error: Some(format!("{:#}", err)),
+        },
+    }
+}
+
+#[instrument(err, skip(subscription_store))]
+async fn write_subscriptions(
+    data_dir: &Path,
+    subscription_store: &SubscriptionStore,
+) -> Result<(), anyhow::Error> {
+    create_dir_all(data_dir)
+        .await
+        .context("Failed to create data directory")?;
+
+    let subscriptions_path = data_dir.join(SUBSCRIPTIONS_FILE_NAME);
+    let mut temp_subscriptions_path = subscriptions_path.as_os_str().to_owned();
+    temp_subscriptions_path.push(".tmp");
+    let temp_subscriptions_path = PathBuf::from(temp_subscriptions_path);
+
+    let subscriptions_contents = serde_json::to_string_pretty(subscription_store)
+        .context("Failed to serialize subscriptions")?;
+    write(&temp_subscriptions_path, subscriptions_contents)
+        .await
+        .context("Failed to write temporary subscriptions file")?;
+    rename(&temp_subscriptions_path, &subscriptions_path)
+        .await
+        .context("Failed to replace subscriptions file")?;
+
+    Ok(())
+}
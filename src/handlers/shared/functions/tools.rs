@@ -1,14 +1,23 @@
+use crate::handlers::errors::{ErrorCode, RequestError};
+use crate::handlers::shared::model::tools::{Tool, ToolInstallManifest, YtDlpUpdateChannels};
 use crate::AppState;
 use anyhow::Context;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use tokio::fs::read_to_string;
 use tracing::{debug, info, instrument};
 
+pub const TOOL_INSTALL_MANIFEST_FILE_NAME: &str = "manifest.json";
+
+const DEFAULT_CHROMAPRINT_VERSION: &str = "1.5.1";
+const DEFAULT_FFMPEG_MACOS_VERSION: &str = "7.0.2";
+
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn get_yt_dlp_executable_path(app_state: &AppState) -> Result<PathBuf, anyhow::Error> {
     debug!("Getting yt-dlp executable path");
 
     let os = std::env::consts::OS;
-    let dlp_dir = Path::new(&app_state.config.server_settings.tools_download_dir);
+    let dlp_dir = get_active_tool_dir(app_state, Tool::YtDlp).await?;
     let executable_path = dlp_dir.join(if os == "windows" {
         "yt-dlp.exe"
     } else {
@@ -16,7 +25,7 @@ pub async fn get_yt_dlp_executable_path(app_state: &AppState) -> Result<PathBuf,
     });
     let canonical_path = executable_path
         .canonicalize()
-        .context("Failed to canonicalize yt-dlp executable path. One of the reasons can be that the executable does not exist")?;
+        .map_err(|err| get_missing_executable_error(Tool::YtDlp, &executable_path, err))?;
 
     Ok(canonical_path)
 }
@@ -26,7 +35,7 @@ pub async fn get_ffmpeg_executable_path(app_state: &AppState) -> Result<PathBuf,
     debug!("Getting ffmpeg executable path");
 
     let os = std::env::consts::OS;
-    let dlp_dir = Path::new(&app_state.config.server_settings.tools_download_dir);
+    let dlp_dir = get_active_tool_dir(app_state, Tool::Ffmpeg).await?;
     let executable_path = dlp_dir.join(if os == "windows" {
         "ffmpeg.exe"
     } else {
@@ -34,7 +43,7 @@ pub async fn get_ffmpeg_executable_path(app_state: &AppState) -> Result<PathBuf,
     });
     let canonical_path = executable_path
         .canonicalize()
-        .context("Failed to canonicalize ffmpeg executable path. One of the reasons can be that the executable does not exist")?;
+        .map_err(|err| get_missing_executable_error(Tool::Ffmpeg, &executable_path, err))?;
 
     Ok(canonical_path)
 }
@@ -46,7 +55,7 @@ pub async fn get_chromaprint_fpcalc_executable_path(
     debug!("Getting chromaprint's fpcalc executable path");
 
     let os = std::env::consts::OS;
-    let dlp_dir = Path::new(&app_state.config.server_settings.tools_download_dir);
+    let dlp_dir = get_active_tool_dir(app_state, Tool::Chromaprint).await?;
     let executable_path = dlp_dir.join(if os == "windows" {
         "fpcalc.exe"
     } else {
@@ -54,79 +63,252 @@ pub async fn get_chromaprint_fpcalc_executable_path(
     });
     let canonical_path = executable_path
         .canonicalize()
-        .context("Failed to canonicalize chromaprint's fpcalc executable path. One of the reasons can be that the executable does not exist")?;
+        .map_err(|err| get_missing_executable_error(Tool::Chromaprint, &executable_path, err))?;
 
     Ok(canonical_path)
 }
 
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn get_tool_executable_path(
+    app_state: &AppState,
+    tool: Tool,
+) -> Result<PathBuf, anyhow::Error> {
+    match tool {
+        Tool::YtDlp => get_yt_dlp_executable_path(app_state).await,
+        Tool::Ffmpeg => get_ffmpeg_executable_path(app_state).await,
+        Tool::Chromaprint => get_chromaprint_fpcalc_executable_path(app_state).await,
+    }
+}
+
+/// Returns a [`RequestError`] if the executable does not exist, as the tool has to be installed before it can be used.
+fn get_missing_executable_error(
+    tool: Tool,
+    executable_path: &Path,
+    err: std::io::Error,
+) -> anyhow::Error {
+    if err.kind() == ErrorKind::NotFound {
+        return RequestError::new(
+            ErrorCode::ToolNotInstalled,
+            format!(
+                "{} is not installed. Install it using the tools endpoints",
+                tool
+            ),
+        )
+        .into();
+    }
+
+    anyhow::Error::new(err).context(format!(
+        "Failed to canonicalize {} executable path: {}",
+        tool,
+        executable_path.display()
+    ))
+}
+
+/// Returns the directory containing all installed versions of the tool.
+pub fn get_tool_dir(tools_download_dir: &Path, tool: Tool) -> PathBuf {
+    tools_download_dir.join(tool.to_string())
+}
+
+/// Returns the directory containing binaries of the currently active tool version.
+/// Falls back to the tools directory itself for tools downloaded before versioned installs were introduced.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn get_active_tool_dir(
+    app_state: &AppState,
+    tool: Tool,
+) -> Result<PathBuf, anyhow::Error> {
+    let tool_dir = get_tool_dir(
+        Path::new(&app_state.config().server_settings.tools_download_dir),
+        tool,
+    );
+
+    match read_tool_install_manifest(&tool_dir).await? {
+        Some(manifest) => Ok(tool_dir.join(manifest.current_version)),
+        None => Ok(PathBuf::from(
+            &app_state.config().server_settings.tools_download_dir,
+        )),
+    }
+}
+
+#[instrument(err, ret(level = "debug"))]
+pub async fn read_tool_install_manifest(
+    tool_dir: &PathBuf,
+) -> Result<Option<ToolInstallManifest>, anyhow::Error> {
+    let manifest_path = tool_dir.join(TOOL_INSTALL_MANIFEST_FILE_NAME);
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+
+    let manifest_contents = read_to_string(&manifest_path)
+        .await
+        .context("Failed to read tool install manifest")?;
+    let manifest = serde_json::from_str(&manifest_contents)
+        .context("Failed to parse tool install manifest as JSON")?;
+
+    Ok(Some(manifest))
+}
+
+/// Returns the names of the binaries shipped with the tool and how many of them are required for a successful install.
+#[instrument(ret(level = "debug"))]
+pub fn get_tool_binaries(tool: Tool) -> (&'static [&'static str], usize) {
+    match tool {
+        Tool::YtDlp => (&["yt-dlp", "yt-dlp.exe"], 1),
+        // Static Linux builds do not ship ffplay, so only ffmpeg and ffprobe are required
+        Tool::Ffmpeg => (
+            &[
+                "ffmpeg",
+                "ffplay",
+                "ffprobe",
+                "ffmpeg.exe",
+                "ffplay.exe",
+                "ffprobe.exe",
+            ],
+            2,
+        ),
+        Tool::Chromaprint => (&["fpcalc", "fpcalc.exe"], 1),
+    }
+}
+
+/// Returns the arguments used to print the version of the tool's main executable.
+#[instrument(ret(level = "debug"))]
+pub fn get_tool_version_args(tool: Tool) -> &'static [&'static str] {
+    match tool {
+        Tool::YtDlp => &["--version"],
+        Tool::Ffmpeg | Tool::Chromaprint => &["-version"],
+    }
+}
+
+/// Extracts a short version string from the output of the tool's version command.
+/// The result is used as the name of the directory the version is installed to.
+#[instrument(ret(level = "debug"))]
+pub fn parse_tool_version_for_install(tool: Tool, version_output: &str) -> Option<String> {
+    let version = match tool {
+        // yt-dlp prints just the version, for example: 2024.08.06
+        Tool::YtDlp => version_output.split_whitespace().next(),
+        // ffmpeg version 7.0.2-static https://johnvansickle.com/ffmpeg/ Copyright (c) ...
+        Tool::Ffmpeg => version_output.split_whitespace().nth(2),
+        // fpcalc version 1.5.1
+        Tool::Chromaprint => version_output.split_whitespace().nth(2),
+    }?;
+
+    let sanitized_version: String = version
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if sanitized_version.is_empty() || sanitized_version.starts_with('.') {
+        None
+    } else {
+        Some(sanitized_version)
+    }
+}
+
 #[instrument(err, ret(level = "debug"))]
 pub async fn get_yt_dlp_download_url_and_output_file_name(
     os: &str,
-) -> Result<(&str, &str), anyhow::Error> {
+    update_channel: YtDlpUpdateChannels,
+    version: Option<&str>,
+) -> Result<(String, String), anyhow::Error> {
     // https://github.com/yt-dlp/yt-dlp/releases
+    // https://github.com/yt-dlp/yt-dlp-nightly-builds/releases
+    // https://github.com/yt-dlp/yt-dlp-master-builds/releases
     info!("Getting download URL and output file name for yt-dlp");
-    let url = match os {
-        "linux" => (
-            "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp",
-            "yt-dlp",
-        ),
-        "windows" => (
-            "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp.exe",
-            "yt-dlp.exe",
+    let repository = match update_channel {
+        YtDlpUpdateChannels::Stable => "yt-dlp/yt-dlp",
+        YtDlpUpdateChannels::Master => "yt-dlp/yt-dlp-master-builds",
+        YtDlpUpdateChannels::Nightly => "yt-dlp/yt-dlp-nightly-builds",
+    };
+    let (asset_name, output_file_name) = match os {
+        "linux" => ("yt-dlp", "yt-dlp"),
+        "windows" => ("yt-dlp.exe", "yt-dlp.exe"),
+        "macos" => ("yt-dlp_macos", "yt-dlp"),
+        os => anyhow::bail!("Unsupported operating system: {}", os),
+    };
+    let url = match version {
+        Some(version) => format!(
+            "https://github.com/{}/releases/download/{}/{}",
+            repository, version, asset_name
         ),
-        "macos" => (
-            "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp_macos",
-            "yt-dlp",
+        None => format!(
+            "https://github.com/{}/releases/latest/download/{}",
+            repository, asset_name
         ),
-        os => anyhow::bail!("Unsupported operating system: {}", os),
     };
 
-    Ok(url)
+    Ok((url, output_file_name.to_string()))
 }
 
 #[instrument(err, ret(level = "debug"))]
 pub async fn get_ffmpeg_download_url_and_output_file_name(
     os: &str,
-) -> Result<(&str, &str), anyhow::Error> {
+    version: Option<&str>,
+) -> Result<(String, String), anyhow::Error> {
     // https://ffmpeg.org/download.html
     info!("Getting download URL and output file name for ffmpeg");
-    let url = match os {
-        "linux" => (
-            "https://johnvansickle.com/ffmpeg/builds/ffmpeg-git-amd64-static.tar.xz",
+    let url = match (os, version) {
+        ("linux", None) => (
+            "https://johnvansickle.com/ffmpeg/builds/ffmpeg-git-amd64-static.tar.xz".to_string(),
             "ffmpeg.tar.xz",
         ),
-        "windows" => (
-            "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/ffmpeg-master-latest-win64-lgpl.zip",
+        ("linux", Some(version)) => (
+            format!(
+                "https://johnvansickle.com/ffmpeg/old-releases/ffmpeg-{}-amd64-static.tar.xz",
+                version
+            ),
+            "ffmpeg.tar.xz",
+        ),
+        ("windows", None) => (
+            "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/ffmpeg-master-latest-win64-lgpl.zip".to_string(),
             "ffmpeg.zip",
         ),
-        "macos" => ("https://evermeet.cx/ffmpeg/ffmpeg-7.0.2.7z", "ffmpeg.7z"),
-        os => anyhow::bail!("Unsupported operating system: {}", os),
+        ("windows", Some(version)) => (
+            format!(
+                "https://github.com/BtbN/FFmpeg-Builds/releases/download/latest/ffmpeg-n{}-latest-win64-lgpl-{}.zip",
+                version, version
+            ),
+            "ffmpeg.zip",
+        ),
+        ("macos", version) => (
+            format!(
+                "https://evermeet.cx/ffmpeg/ffmpeg-{}.7z",
+                version.unwrap_or(DEFAULT_FFMPEG_MACOS_VERSION)
+            ),
+            "ffmpeg.7z",
+        ),
+        (os, _) => anyhow::bail!("Unsupported operating system: {}", os),
     };
 
-    Ok(url)
+    Ok((url.0, url.1.to_string()))
 }
 
 #[instrument(err, ret(level = "debug"))]
 pub async fn get_chromaprint_download_url_and_output_file_name(
     os: &str,
-) -> Result<(&str, &str), anyhow::Error> {
+    version: Option<&str>,
+) -> Result<(String, String), anyhow::Error> {
     // https://acoustid.org/chromaprint
     info!("Getting download URL and output file name for chromaprint");
+    let version = version.unwrap_or(DEFAULT_CHROMAPRINT_VERSION);
     let url = match os {
         "linux" => (
-            "https://github.com/acoustid/chromaprint/releases/download/v1.5.1/chromaprint-fpcalc-1.5.1-linux-x86_64.tar.gz",
+            format!("https://github.com/acoustid/chromaprint/releases/download/v{}/chromaprint-fpcalc-{}-linux-x86_64.tar.gz", version, version),
             "chromaprint.tar.gz"
         ),
         "windows" => (
-            "https://github.com/acoustid/chromaprint/releases/download/v1.5.1/chromaprint-fpcalc-1.5.1-windows-x86_64.zip",
+            format!("https://github.com/acoustid/chromaprint/releases/download/v{}/chromaprint-fpcalc-{}-windows-x86_64.zip", version, version),
             "chromaprint.zip"
         ),
         "macos" => (
-            "https://github.com/acoustid/chromaprint/releases/download/v1.5.1/chromaprint-fpcalc-1.5.1-macos-universal.tar.gz",
+            format!("https://github.com/acoustid/chromaprint/releases/download/v{}/chromaprint-fpcalc-{}-macos-universal.tar.gz", version, version),
             "chromaprint.tar.gz"
         ),
         os => anyhow::bail!("Unsupported operating system: {}", os),
     };
 
-    Ok(url)
+    Ok((url.0, url.1.to_string()))
 }
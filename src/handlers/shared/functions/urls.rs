@@ -0,0 +1,386 @@
+use crate::config::UrlPolicySettings;
+use crate::handlers::errors::{ErrorCode, RequestError};
+use crate::handlers::shared::model::search::SearchSource;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use std::error::Error;
+use tracing::{instrument, warn};
+
+/// Longest URL accepted in requests. Browsers and most sites do not support longer URLs.
+const MAX_MEDIA_URL_LENGTH: usize = 2048;
+/// Most redirects followed for a media download, the same limit as the default redirect policy of reqwest.
+const MAX_MEDIA_REDIRECTS: usize = 10;
+/// yt-dlp search prefixes accepted in place of URLs, with the domain of the searched site.
+/// A prefix can be followed by the number of results or `all`, for example `ytsearch5:artist title`.
+const SEARCH_PREFIXES: &[(&str, &str)] = &[
+    ("ytsearchdate", "youtube.com"),
+    ("ytsearch", "youtube.com"),
+    ("scsearch", "soundcloud.com"),
+];
+
+/// Validates a media URL received in a request before it is passed to yt-dlp.
+/// Accepts `http` and `https` URLs and yt-dlp search prefixes such as `ytsearch1:artist title`,
+/// checks the domain against the configured allow and deny lists and returns the normalized URL.
+#[instrument(err, ret(level = "debug"), skip(url_policy_settings))]
+pub fn validate_media_url(
+    url_policy_settings: &UrlPolicySettings,
+    media_url: &str,
+) -> Result<String, RequestError> {
+    let media_url = media_url.trim();
+    if media_url.is_empty() {
+        return Err(RequestError::new(
+            ErrorCode::UrlNotAllowed,
+            "URL must not be empty",
+        ));
+    }
+    if media_url.len() > MAX_MEDIA_URL_LENGTH {
+        return Err(RequestError::new(
+            ErrorCode::UrlNotAllowed,
+            format!(
+                "URL must not be longer than {} characters",
+                MAX_MEDIA_URL_LENGTH
+            ),
+        ));
+    }
+
+    if let Some(search_domain) = get_search_prefix_domain(media_url) {
+        check_domain_policy(url_policy_settings, search_domain)?;
+        return Ok(media_url.to_string());
+    }
+
+    let url = Url::parse(media_url).map_err(|err| {
+        RequestError::new(
+            ErrorCode::UrlNotAllowed,
+            format!(
+                "URL must be an http or https URL or a search such as ytsearch1:artist title: {}",
+                err
+            ),
+        )
+    })?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(RequestError::new(
+            ErrorCode::UrlNotAllowed,
+            format!(
+                "URL scheme {} is not supported, expected http or https",
+                url.scheme()
+            ),
+        ));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| RequestError::new(ErrorCode::UrlNotAllowed, "URL must contain a host"))?;
+    check_domain_policy(url_policy_settings, host)?;
+
+    Ok(url.to_string())
+}
+
+/// Checks the domain of a search against the configured allow and deny lists.
+pub fn validate_search_source(
+    url_policy_settings: &UrlPolicySettings,
+    search_source: SearchSource,
+) -> Result<(), RequestError> {
+    let search_domain = match search_source {
+        SearchSource::Youtube => "youtube.com",
+        SearchSource::Soundcloud => "soundcloud.com",
+    };
+
+    check_domain_policy(url_policy_settings, search_domain)
+}
+
+/// Returns the arguments limiting the extractors yt-dlp can use, or no arguments if all extractors are allowed.
+/// Extractors are only known after yt-dlp matches the URL, so they are enforced by yt-dlp instead of being checked up front.
+pub fn get_extractor_policy_args(url_policy_settings: &UrlPolicySettings) -> Vec<String> {
+    let allowed_extractors = &url_policy_settings.allowed_extractors;
+    let denied_extractors = &url_policy_settings.denied_extractors;
+    if allowed_extractors.is_empty() && denied_extractors.is_empty() {
+        return Vec::new();
+    }
+
+    let mut extractors = if allowed_extractors.is_empty() {
+        vec!["default".to_string()]
+    } else {
+        allowed_extractors.clone()
+    };
+    extractors.extend(
+        denied_extractors
+            .iter()
+            .map(|extractor| format!("-{}", extractor)),
+    );
+
+    vec!["--use-extractors".to_string(), extractors.join(",")]
+}
+
+/// Checks whether the extractor allow and deny lists permit `extractor`, for downloads that do not go through yt-dlp.
+pub fn is_extractor_allowed(url_policy_settings: &UrlPolicySettings, extractor: &str) -> bool {
+    let allowed_extractors = &url_policy_settings.allowed_extractors;
+    let is_denied = url_policy_settings
+        .denied_extractors
+        .iter()
+        .any(|denied_extractor| denied_extractor == extractor);
+    let is_allowed = allowed_extractors.is_empty()
+        || allowed_extractors.iter().any(|allowed_extractor| {
+            allowed_extractor == extractor
+                || matches!(allowed_extractor.as_str(), "all" | "default")
+        });
+
+    is_allowed && !is_denied
+}
+
+/// Returns the redirect policy of the HTTP client for media downloads, which checks every redirect target against
+/// the URL policy before it is requested, so an allowed URL cannot lead to a denied host.
+/// The URL policy is read for every redirect, so reloading the config applies a changed policy.
+pub fn get_media_redirect_policy(
+    get_url_policy_settings: impl Fn() -> UrlPolicySettings + Send + Sync + 'static,
+) -> Policy {
+    Policy::custom(move |attempt| {
+        if attempt.previous().len() > MAX_MEDIA_REDIRECTS {
+            return attempt.error(format!("Stopped after {} redirects", MAX_MEDIA_REDIRECTS));
+        }
+
+        match validate_media_url(&get_url_policy_settings(), attempt.url().as_str()) {
+            Ok(_) => attempt.follow(),
+            Err(err) => attempt.error(err),
+        }
+    })
+}
+
+/// Returns the error of the media redirect policy if `err` is caused by a redirect to a URL that is not allowed.
+pub fn get_denied_redirect_error(err: &reqwest::Error) -> Option<RequestError> {
+    let mut source = err.source();
+    while let Some(err) = source {
+        if let Some(request_error) = err.downcast_ref::<RequestError>() {
+            return Some(RequestError::new(
+                request_error.code,
+                request_error.message.clone(),
+            ));
+        }
+        source = err.source();
+    }
+
+    None
+}
+
+fn get_search_prefix_domain(media_url: &str) -> Option<&'static str> {
+    let (prefix, query) = media_url.split_once(':')?;
+    if query.trim().is_empty() {
+        return None;
+    }
+
+    SEARCH_PREFIXES
+        .iter()
+        .find(|(search_prefix, _)| {
+            prefix.strip_prefix(search_prefix).is_some_and(|count| {
+                count.is_empty() || count == "all" || count.chars().all(|c| c.is_ascii_digit())
+            })
+        })
+        .map(|(_, search_domain)| *search_domain)
+}
+
+fn check_domain_policy(
+    url_policy_settings: &UrlPolicySettings,
+    host: &str,
+) -> Result<(), RequestError> {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+    if let Some(denied_domain) = url_policy_settings
+        .denied_domains
+        .iter()
+        .find(|domain| is_domain_or_subdomain(&host, domain))
+    {
+        warn!("Rejected URL with denied domain: {}", host);
+        return Err(RequestError::new(
+            ErrorCode::UrlNotAllowed,
+            format!(
+                "Domain {} is denied by the server configuration ({})",
+                host, denied_domain
+            ),
+        ));
+    }
+
+    if !url_policy_settings.allowed_domains.is_empty()
+        && !url_policy_settings
+            .allowed_domains
+            .iter()
+            .any(|domain| is_domain_or_subdomain(&host, domain))
+    {
+        warn!(
+            "Rejected URL with domain outside of the allow list: {}",
+            host
+        );
+        return Err(RequestError::new(
+            ErrorCode::UrlNotAllowed,
+            format!("Domain {} is not in the list of allowed domains", host),
+        ));
+    }
+
+    Ok(())
+}
+
+fn is_domain_or_subdomain(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::Redirect;
+    use axum::routing::get;
+    use axum::Router;
+    use reqwest::Client;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    fn url_policy(allowed_domains: &[&str], denied_domains: &[&str]) -> UrlPolicySettings {
+        UrlPolicySettings {
+            allowed_domains: allowed_domains.iter().map(ToString::to_string).collect(),
+            denied_domains: denied_domains.iter().map(ToString::to_string).collect(),
+            allowed_extractors: Vec::new(),
+            denied_extractors: Vec::new(),
+        }
+    }
+
+    fn assert_rejected(url_policy_settings: &UrlPolicySettings, media_url: &str) {
+        let err = validate_media_url(url_policy_settings, media_url).unwrap_err();
+        assert_eq!(
+            err.code,
+            ErrorCode::UrlNotAllowed,
+            "{:?} should be rejected",
+            media_url
+        );
+    }
+
+    #[test]
+    fn accepts_http_urls_and_searches_without_a_policy() {
+        let url_policy_settings = url_policy(&[], &[]);
+
+        assert_eq!(
+            validate_media_url(&url_policy_settings, "  https://YouTube.com/watch?v=id ").unwrap(),
+            "https://youtube.com/watch?v=id"
+        );
+        assert_eq!(
+            validate_media_url(&url_policy_settings, "ytsearch5:artist title").unwrap(),
+            "ytsearch5:artist title"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_and_non_http_urls() {
+        let url_policy_settings = url_policy(&[], &[]);
+
+        for media_url in [
+            "",
+            "   ",
+            "youtube.com/watch?v=id",
+            "file:///etc/passwd",
+            "ftp://example.com/song.mp3",
+            "javascript:alert(1)",
+            "ytsearch:",
+            "ytsearchx:artist title",
+            "--exec=touch /tmp/pwned",
+        ] {
+            assert_rejected(&url_policy_settings, media_url);
+        }
+        assert_rejected(
+            &url_policy_settings,
+            &format!("https://example.com/{}", "a".repeat(MAX_MEDIA_URL_LENGTH)),
+        );
+    }
+
+    #[test]
+    fn checks_hosts_against_the_allow_list() {
+        let url_policy_settings = url_policy(&["youtube.com"], &[]);
+
+        for media_url in [
+            "https://youtube.com/watch?v=id",
+            "https://music.youtube.com./watch?v=id",
+            "ytsearch1:artist title",
+        ] {
+            assert!(
+                validate_media_url(&url_policy_settings, media_url).is_ok(),
+                "{:?} should be accepted",
+                media_url
+            );
+        }
+        for media_url in [
+            "https://evilyoutube.com/watch?v=id",
+            "https://youtube.com.evil.com/watch?v=id",
+            "https://youtube.com@evil.com/watch?v=id",
+            "http://127.0.0.1:8080/",
+            "scsearch:artist title",
+        ] {
+            assert_rejected(&url_policy_settings, media_url);
+        }
+    }
+
+    #[test]
+    fn deny_list_takes_precedence_over_allow_list() {
+        let url_policy_settings = url_policy(&["youtube.com"], &["music.youtube.com"]);
+
+        assert!(validate_media_url(&url_policy_settings, "https://www.youtube.com/").is_ok());
+        assert_rejected(&url_policy_settings, "https://music.youtube.com/");
+        assert_rejected(&url_policy_settings, "https://a.music.youtube.com/");
+    }
+
+    #[test]
+    fn detects_search_prefixes() {
+        assert_eq!(
+            get_search_prefix_domain("ytsearch:artist title"),
+            Some("youtube.com")
+        );
+        assert_eq!(
+            get_search_prefix_domain("ytsearchdate10:artist title"),
+            Some("youtube.com")
+        );
+        assert_eq!(
+            get_search_prefix_domain("scsearchall:artist title"),
+            Some("soundcloud.com")
+        );
+        assert_eq!(get_search_prefix_domain("ytsearch5: "), None);
+        assert_eq!(get_search_prefix_domain("ytsearch-1:artist title"), None);
+        assert_eq!(get_search_prefix_domain("gvsearch:artist title"), None);
+        assert_eq!(get_search_prefix_domain("https://youtube.com"), None);
+    }
+
+    #[tokio::test]
+    async fn stops_redirects_from_allowed_urls_to_denied_hosts() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let denied_requests = Arc::new(AtomicUsize::new(0));
+        let counted_requests = denied_requests.clone();
+        let router = Router::new()
+            .route(
+                "/audio.mp3",
+                get(move || async move {
+                    Redirect::temporary(&format!("http://localhost:{}/denied.mp3", port))
+                }),
+            )
+            .route(
+                "/denied.mp3",
+                get(move || async move {
+                    counted_requests.fetch_add(1, Ordering::SeqCst);
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let http_client = Client::builder()
+            .no_proxy()
+            .redirect(get_media_redirect_policy(|| {
+                url_policy(&[], &["localhost"])
+            }))
+            .build()
+            .unwrap();
+        let err = http_client
+            .get(format!("http://127.0.0.1:{}/audio.mp3", port))
+            .send()
+            .await
+            .unwrap_err();
+
+        let request_error = get_denied_redirect_error(&err).unwrap();
+        assert_eq!(request_error.code, ErrorCode::UrlNotAllowed);
+        assert_eq!(denied_requests.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,579 @@
+use crate::handlers::errors::{ErrorCode, RequestError};
+use crate::handlers::shared::functions::commands::run_command_with_secrets;
+use crate::handlers::shared::functions::tools::{
+    get_ffmpeg_executable_path, get_yt_dlp_executable_path,
+};
+use crate::handlers::shared::functions::urls::get_extractor_policy_args;
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use crate::handlers::shared::model::media::{
+    AudioDownloadOptions, AudioFormat, SponsorBlockCategory, SubtitleLanguage, VideoCodec,
+    VideoDownloadOptions, YtDlpNetworkOptions,
+};
+use crate::handlers::shared::model::search::{SearchQuery, SearchResultCount, SearchSource};
+use crate::handlers::shared::model::yt_dlp::{YtDlpPlaylistEntry, YtDlpPlaylistInfo};
+use crate::AppState;
+use anyhow::Context;
+use std::io::Write;
+use std::path::Path;
+use tempfile::NamedTempFile;
+use tokio::fs::create_dir_all;
+use tokio::task::spawn_blocking;
+use tracing::{info, instrument, warn};
+
+/// Availability values reported by yt-dlp for entries that cannot be downloaded without extra access.
+const UNAVAILABLE_ENTRY_AVAILABILITIES: &[&str] =
+    &["private", "premium_only", "subscriber_only", "needs_auth"];
+/// Crops video thumbnails to a centered square so they can be used as album artwork.
+/// The inner quotes are kept for ffmpeg so commas in the crop expression are not treated as filter separators.
+const SQUARE_THUMBNAIL_POSTPROCESSOR_ARGS: &str = r#"ThumbnailsConvertor+FFmpeg_o:-c:v mjpeg -vf crop="'if(gt(ih,iw),iw,ih)':'if(gt(iw,ih),ih,iw)'""#;
+/// Titles YouTube uses for entries that were removed or made private.
+const UNAVAILABLE_ENTRY_TITLES: &[&str] = &["[Private video]", "[Deleted video]"];
+/// Name of a downloaded media file after the download ID prefix. Library file names are rendered from the info JSON instead.
+pub const DOWNLOADED_MEDIA_FILE_STEM: &str = "media";
+/// Prefix of chapter files after the download ID prefix. It is followed by the 3 digit chapter number.
+pub const DOWNLOADED_CHAPTER_FILE_PREFIX: &str = "chapter-";
+/// Subdirectory of the data directory for cookies sent with a single request.
+const REQUEST_COOKIES_SUBDIR: &str = "cookies";
+
+/// Network arguments of yt-dlp resolved from the server settings and the request overrides.
+struct YtDlpNetworkArgs {
+    args: Vec<String>,
+    /// Values hidden from the logs and from the command output returned to clients.
+    secrets: Vec<String>,
+    /// Cookies file written for a single command. Removed when dropped, also if the request is cancelled.
+    _request_cookies_file: Option<NamedTempFile>,
+}
+
+/// Runs yt-dlp for `media_url` with the given options and the network arguments resolved from the server settings and `network_options`.
+/// The URL is always passed after `--`, so it cannot be interpreted as an option even if it starts with `-`.
+/// Cookies sent with the request are only stored for the duration of the command, and secrets are removed from the logs and the returned output.
+#[instrument(err, ret(level = "debug"), skip(app_state, args))]
+pub async fn run_yt_dlp(
+    app_state: &AppState,
+    args: Vec<String>,
+    media_url: &str,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<CommandExecutionResults, anyhow::Error> {
+    let yt_dlp_executable_path = get_yt_dlp_executable_path(app_state)
+        .await
+        .context("Failed to get yt-dlp executable path")?;
+
+    let network_args = get_network_args(app_state, network_options).await?;
+    let extractor_policy_args = get_extractor_policy_args(&app_state.config().url_policy_settings);
+    let all_args: Vec<&str> = network_args
+        .args
+        .iter()
+        .chain(extractor_policy_args.iter())
+        .chain(args.iter())
+        .map(String::as_str)
+        .chain(["--", media_url])
+        .collect();
+
+    run_command_with_secrets(&yt_dlp_executable_path, &all_args, &network_args.secrets).await
+}
+
+/// Resolves the network arguments. Options set in the request take precedence over the server settings.
+async fn get_network_args(
+    app_state: &AppState,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<YtDlpNetworkArgs, anyhow::Error> {
+    let yt_dlp_settings = &app_state.config().yt_dlp_settings;
+    let mut network_args = YtDlpNetworkArgs {
+        args: Vec::new(),
+        secrets: Vec::new(),
+        _request_cookies_file: None,
+    };
+
+    if let Some(cookies) = &network_options.cookies {
+        let cookies_file = write_request_cookies_file(app_state, cookies.as_str()).await?;
+        let cookies_file_path = cookies_file.path().to_string_lossy().to_string();
+        network_args.args.push("--cookies".to_string());
+        network_args.args.push(cookies_file_path.clone());
+        network_args.secrets.push(cookies_file_path);
+        network_args._request_cookies_file = Some(cookies_file);
+    } else if let Some(cookies_file) = &yt_dlp_settings.cookies_file {
+        network_args.args.push("--cookies".to_string());
+        network_args.args.push(cookies_file.clone());
+        network_args.secrets.push(cookies_file.clone());
+    }
+
+    if network_options.proxy.is_some() && !yt_dlp_settings.allow_request_proxy {
+        return Err(RequestError::new(
+            ErrorCode::InvalidRequest,
+            "The server does not allow requests to set the proxy. It can be allowed with the allow_request_proxy setting",
+        )
+        .into());
+    }
+    if let Some(proxy) = network_options
+        .proxy
+        .as_ref()
+        .or(yt_dlp_settings.proxy.as_ref())
+    {
+        network_args.args.push("--proxy".to_string());
+        network_args.args.push(proxy.as_str().to_string());
+        network_args.secrets.push(proxy.as_str().to_string());
+    }
+
+    if let Some(limit_rate) = network_options
+        .limit_rate
+        .as_ref()
+        .or(yt_dlp_settings.limit_rate.as_ref())
+    {
+        network_args.args.push("--limit-rate".to_string());
+        network_args.args.push(limit_rate.as_str().to_string());
+    }
+
+    let sleep_interval = network_options
+        .sleep_interval
+        .or(yt_dlp_settings.sleep_interval);
+    let max_sleep_interval = network_options
+        .max_sleep_interval
+        .or(yt_dlp_settings.max_sleep_interval);
+    match (sleep_interval, max_sleep_interval) {
+        (Some(sleep_interval), max_sleep_interval) => {
+            network_args.args.push("--sleep-interval".to_string());
+            network_args
+                .args
+                .push(f64::from(sleep_interval).to_string());
+
+            if let Some(max_sleep_interval) = max_sleep_interval {
+                if f64::from(max_sleep_interval) < f64::from(sleep_interval) {
+                    return Err(RequestError::new(
+                        ErrorCode::InvalidRequest,
+                        "Maximum sleep interval must not be lower than the sleep interval",
+                    )
+                    .into());
+                }
+                network_args.args.push("--max-sleep-interval".to_string());
+                network_args
+                    .args
+                    .push(f64::from(max_sleep_interval).to_string());
+            }
+        }
+        (None, Some(_)) => {
+            return Err(RequestError::new(
+                ErrorCode::InvalidRequest,
+                "Maximum sleep interval requires a sleep interval",
+            )
+            .into());
+        }
+        (None, None) => {}
+    }
+
+    if let Some(sleep_requests) = network_options
+        .sleep_requests
+        .or(yt_dlp_settings.sleep_requests)
+    {
+        network_args.args.push("--sleep-requests".to_string());
+        network_args
+            .args
+            .push(f64::from(sleep_requests).to_string());
+    }
+
+    if let Some(retries) = network_options.retries.or(yt_dlp_settings.retries) {
+        network_args.args.push("--retries".to_string());
+        network_args.args.push(retries.to_string());
+    }
+
+    Ok(network_args)
+}
+
+/// Writes cookies sent with a request to a new file in the data directory that only the server user can read.
+/// The file is removed when the returned handle is dropped.
+async fn write_request_cookies_file(
+    app_state: &AppState,
+    cookies: &str,
+) -> Result<NamedTempFile, anyhow::Error> {
+    let cookies_dir =
+        Path::new(&app_state.config().server_settings.data_dir).join(REQUEST_COOKIES_SUBDIR);
+    create_dir_all(&cookies_dir)
+        .await
+        .context("Failed to create directory for request cookies")?;
+    let cookies_dir = cookies_dir
+        .canonicalize()
+        .context("Failed to canonicalize directory for request cookies")?;
+
+    let cookies = cookies.to_string();
+    spawn_blocking(move || -> Result<NamedTempFile, anyhow::Error> {
+        // Temporary files are created with permissions for the owner only
+        let mut cookies_file = tempfile::Builder::new()
+            .suffix(".txt")
+            .tempfile_in(&cookies_dir)
+            .context("Failed to create request cookies file")?;
+        cookies_file
+            .write_all(cookies.as_bytes())
+            .context("Failed to write request cookies file")?;
+        cookies_file
+            .flush()
+            .context("Failed to write request cookies file")?;
+
+        Ok(cookies_file)
+    })
+    .await
+    .context("Failed to execute request cookies file task")?
+}
+
+/// Logs a warning if the server cookies file can be read by other users.
+#[instrument(skip(app_state))]
+pub async fn warn_if_cookies_file_is_shared(app_state: &AppState) {
+    let Some(cookies_file) = &app_state.config().yt_dlp_settings.cookies_file else {
+        return;
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        match tokio::fs::metadata(cookies_file).await {
+            Ok(metadata) if metadata.permissions().mode() & 0o077 != 0 => {
+                warn!("Cookies file can be accessed by other users. Restrict its permissions to the server user, for example with chmod 600");
+            }
+            Ok(_) => {}
+            Err(err) => warn!("Failed to read cookies file metadata: {:#}", err),
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = cookies_file;
+}
+
+/// Downloads the audio of a single media URL to the audio download directory. Every produced file name starts with `download_id`
+/// followed by `DOWNLOADED_MEDIA_FILE_STEM` or, for split chapters, `DOWNLOADED_CHAPTER_FILE_PREFIX`.
+/// The managed ffmpeg is used for extracting and converting the audio when it is installed.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn download_audio_with_yt_dlp(
+    app_state: &AppState,
+    audio_url: &str,
+    download_id: &str,
+    audio_download_options: &AudioDownloadOptions,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<CommandExecutionResults, anyhow::Error> {
+    let output_path = Path::new(&app_state.config().audio_download_settings.download_dir)
+        .canonicalize()
+        .context("Failed to canonicalize audio output path")?;
+    let output_path_str = output_path
+        .join(format!(
+            "{}{}.%(ext)s",
+            download_id, DOWNLOADED_MEDIA_FILE_STEM
+        ))
+        .to_string_lossy()
+        .to_string();
+
+    info!("Using output path: {:#?}", output_path_str);
+
+    let mut args = vec![
+        "-x".to_string(),
+        "--no-playlist".to_string(),
+        "--audio-format".to_string(),
+        get_yt_dlp_audio_format(audio_download_options.audio_format).to_string(),
+        "--embed-metadata".to_string(),
+        "--embed-chapters".to_string(),
+        "--write-info-json".to_string(),
+        "--embed-thumbnail".to_string(),
+        "--convert-thumbnails".to_string(),
+        "jpg".to_string(),
+        "--postprocessor-args".to_string(),
+        SQUARE_THUMBNAIL_POSTPROCESSOR_ARGS.to_string(),
+    ];
+
+    if let Some(audio_quality) = &audio_download_options.audio_quality {
+        args.push("--audio-quality".to_string());
+        args.push(audio_quality.as_str().to_string());
+    }
+
+    if let Some(sample_rate) = audio_download_options.sample_rate {
+        args.push("--postprocessor-args".to_string());
+        args.push(format!("ExtractAudio:-ar {}", u32::from(sample_rate)));
+    }
+
+    if audio_download_options.split_chapters {
+        // Chapter files keep the download ID prefix so they are moved to the library with the full file
+        args.push("--split-chapters".to_string());
+        args.push("-o".to_string());
+        args.push(format!(
+            "chapter:{}",
+            output_path
+                .join(format!(
+                    "{}{}%(section_number)03d.%(ext)s",
+                    download_id, DOWNLOADED_CHAPTER_FILE_PREFIX
+                ))
+                .to_string_lossy()
+        ));
+    }
+
+    args.extend(get_sponsorblock_args(
+        app_state,
+        &audio_download_options.sponsorblock_remove,
+    ));
+    args.extend(get_ffmpeg_location_args(app_state).await);
+
+    args.extend([
+        "-o".to_string(),
+        output_path_str,
+        "--no-progress".to_string(),
+    ]);
+
+    run_yt_dlp(app_state, args, audio_url, network_options)
+        .await
+        .context("Failed to download audio using yt-dlp")
+}
+
+/// Downloads a video to the video download directory. Every produced file name, including subtitle files, starts with `download_id`.
+/// The managed ffmpeg is used for merging video and audio streams when it is installed.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn download_video_with_yt_dlp(
+    app_state: &AppState,
+    video_url: &str,
+    download_id: &str,
+    video_download_options: &VideoDownloadOptions,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<CommandExecutionResults, anyhow::Error> {
+    let output_path = Path::new(&app_state.config().video_download_settings.download_dir)
+        .canonicalize()
+        .context("Failed to canonicalize video output path")?;
+    let output_path_str = output_path
+        .join(format!("{}%(title)s.%(ext)s", download_id))
+        .to_string_lossy()
+        .to_string();
+
+    info!("Using output path: {:#?}", output_path_str);
+
+    let mut args = vec![
+        "--no-playlist".to_string(),
+        "-f".to_string(),
+        "bv*+ba/b".to_string(),
+        "--merge-output-format".to_string(),
+        video_download_options.container.to_string(),
+    ];
+
+    // Format sorting prefers the requested resolution and codec but falls back to other formats if they are not available
+    let mut format_sort = Vec::new();
+    if let Some(max_resolution) = video_download_options.max_resolution {
+        format_sort.push(format!("res:{}", max_resolution.height()));
+    }
+    if let Some(video_codec) = video_download_options.video_codec {
+        format_sort.push(format!("vcodec:{}", get_yt_dlp_video_codec(video_codec)));
+    }
+    if !format_sort.is_empty() {
+        args.push("-S".to_string());
+        args.push(format_sort.join(","));
+    }
+
+    if !video_download_options.subtitle_languages.is_empty() {
+        args.push("--write-subs".to_string());
+        if video_download_options.include_auto_subtitles {
+            args.push("--write-auto-subs".to_string());
+        }
+        args.push("--sub-langs".to_string());
+        args.push(
+            video_download_options
+                .subtitle_languages
+                .iter()
+                .map(SubtitleLanguage::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        if video_download_options.embed_subtitles {
+            args.push("--embed-subs".to_string());
+        }
+    }
+
+    args.extend(get_ffmpeg_location_args(app_state).await);
+
+    args.extend([
+        "-o".to_string(),
+        output_path_str,
+        "--no-progress".to_string(),
+    ]);
+
+    run_yt_dlp(app_state, args, video_url, network_options)
+        .await
+        .context("Failed to download video using yt-dlp")
+}
+
+/// Returns the arguments pointing yt-dlp to the managed ffmpeg, or no arguments if it is not installed.
+#[instrument(ret(level = "debug"), skip(app_state))]
+async fn get_ffmpeg_location_args(app_state: &AppState) -> Vec<String> {
+    match get_ffmpeg_executable_path(app_state).await {
+        Ok(ffmpeg_executable_path) => vec![
+            "--ffmpeg-location".to_string(),
+            ffmpeg_executable_path.to_string_lossy().to_string(),
+        ],
+        Err(_) => {
+            warn!("Managed ffmpeg is not installed, yt-dlp will look for ffmpeg on PATH");
+            Vec::new()
+        }
+    }
+}
+
+/// Returns the arguments cutting the given SponsorBlock categories, or no arguments if no categories are given.
+fn get_sponsorblock_args(
+    app_state: &AppState,
+    sponsorblock_categories: &[SponsorBlockCategory],
+) -> Vec<String> {
+    if sponsorblock_categories.is_empty() {
+        return Vec::new();
+    }
+
+    let mut args = vec![
+        "--sponsorblock-remove".to_string(),
+        sponsorblock_categories
+            .iter()
+            .map(SponsorBlockCategory::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+    ];
+    if let Some(sponsorblock_api_url) = &app_state.config().yt_dlp_settings.sponsorblock_api_url {
+        args.push("--sponsorblock-api".to_string());
+        args.push(sponsorblock_api_url.clone());
+    }
+
+    args
+}
+
+/// Maps the preferred video codec to the codec name used by yt-dlp's format sorting.
+pub fn get_yt_dlp_video_codec(video_codec: VideoCodec) -> &'static str {
+    match video_codec {
+        VideoCodec::H264 => "h264",
+        VideoCodec::Vp9 => "vp9",
+        VideoCodec::Av1 => "av01",
+    }
+}
+
+/// Maps the requested audio format to the value of yt-dlp's `--audio-format` option.
+pub fn get_yt_dlp_audio_format(audio_format: AudioFormat) -> &'static str {
+    match audio_format {
+        AudioFormat::Mp3 => "mp3",
+        AudioFormat::Opus => "opus",
+        AudioFormat::M4a => "m4a",
+        AudioFormat::Flac => "flac",
+        AudioFormat::BestOriginal => "best",
+    }
+}
+
+/// Resolves the extractor name and media ID of a single media URL without downloading it.
+/// Returns the command results along with the extractor name and media ID if they could be resolved.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn fetch_media_source_id(
+    app_state: &AppState,
+    media_url: &str,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<(CommandExecutionResults, Option<(String, String)>), anyhow::Error> {
+    info!("Resolving extractor and media ID using yt-dlp");
+
+    let command_execution_results = run_yt_dlp(
+        app_state,
+        vec![
+            "--simulate".to_string(),
+            "--no-playlist".to_string(),
+            "--print".to_string(),
+            "%(extractor)s %(id)s".to_string(),
+        ],
+        media_url,
+        network_options,
+    )
+    .await
+    .context("Failed to resolve extractor and media ID using yt-dlp")?;
+
+    // A playlist URL prints one line per entry and cannot be matched with a single library entry
+    let source_id = match command_execution_results.stdout.as_deref().map(str::lines) {
+        Some(mut lines) if command_execution_results.command_completed_successfully => {
+            match (lines.next(), lines.next()) {
+                (Some(line), None) => line
+                    .trim()
+                    .split_once(' ')
+                    .map(|(extractor, media_id)| (extractor.to_string(), media_id.to_string())),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    Ok((command_execution_results, source_id))
+}
+
+/// Lists the entries of a playlist or channel without downloading them, optionally limited to the first `max_items` entries.
+/// Returns the command results along with the parsed info if the command succeeded.
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn fetch_playlist_info(
+    app_state: &AppState,
+    playlist_url: &str,
+    max_items: Option<usize>,
+    network_options: &YtDlpNetworkOptions,
+) -> Result<(CommandExecutionResults, Option<YtDlpPlaylistInfo>), anyhow::Error> {
+    info!("Fetching playlist entries using yt-dlp");
+
+    let mut args = vec![
+        "--flat-playlist".to_string(),
+        "--dump-single-json".to_string(),
+    ];
+    if let Some(max_items) = max_items {
+        args.push("--playlist-end".to_string());
+        args.push(max_items.to_string());
+    }
+
+    let command_execution_results = run_yt_dlp(app_state, args, playlist_url, network_options)
+        .await
+        .context("Failed to fetch playlist entries using yt-dlp")?;
+
+    if !command_execution_results.command_completed_successfully {
+        return Ok((command_execution_results, None));
+    }
+
+    let playlist_info = serde_json::from_str(
+        command_execution_results
+            .stdout
+            .as_deref()
+            .context("yt-dlp did not print playlist info")?,
+    )
+    .context("Failed to parse playlist info printed by yt-dlp")?;
+
+    Ok((command_execution_results, Some(playlist_info)))
+}
+
+/// Builds the yt-dlp search URL returning the first `result_count` results for the query, for example `ytsearch5:artist title`.
+pub fn get_yt_dlp_search_url(
+    search_source: SearchSource,
+    result_count: SearchResultCount,
+    search_query: &SearchQuery,
+) -> String {
+    let search_prefix = match search_source {
+        SearchSource::Youtube => "ytsearch",
+        SearchSource::Soundcloud => "scsearch",
+    };
+
+    format!(
+        "{}{}:{}",
+        search_prefix,
+        usize::from(result_count),
+        search_query.as_str()
+    )
+}
+
+/// Flattens nested entries (for example channel tabs) into a single list, dropping entries yt-dlp failed to extract.
+pub fn flatten_playlist_entries(
+    entries: Vec<Option<YtDlpPlaylistEntry>>,
+) -> Vec<YtDlpPlaylistEntry> {
+    let mut flat_entries = Vec::new();
+    for mut entry in entries.into_iter().flatten() {
+        if entry.entries.is_empty() {
+            flat_entries.push(entry);
+        } else {
+            flat_entries.extend(flatten_playlist_entries(std::mem::take(&mut entry.entries)));
+        }
+    }
+
+    flat_entries
+}
+
+/// Returns true if the extractor reported the entry as private, deleted or otherwise not downloadable.
+pub fn is_playlist_entry_unavailable(entry: &YtDlpPlaylistEntry) -> bool {
+    entry.url.is_none()
+        || entry
+            .availability
+            .as_deref()
+            .is_some_and(|availability| UNAVAILABLE_ENTRY_AVAILABILITIES.contains(&availability))
+        || entry
+            .title
+            .as_deref()
+            .is_some_and(|title| UNAVAILABLE_ENTRY_TITLES.contains(&title))
+}
@@ -0,0 +1,119 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use strum_macros::Display;
+use time::OffsetDateTime;
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+/// Sessions of logged in users, keyed by the session ID stored in the session cookie.
+/// Sessions are kept in memory until they expire or the user logs out, so users log in again after a restart.
+/// Expired sessions are removed when they are used and when a user logs in.
+pub type SessionRegistry = Arc<RwLock<HashMap<String, Session>>>;
+
+/// What an API token can be used for. Logged in users can use every endpoint.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Display, Deserialize, Serialize, ValueEnum, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TokenScope {
+    /// Reading the library, playing files and checking statuses, which are all `GET` requests.
+    Read,
+    /// Downloading, identifying, converting and splitting media and managing subscriptions.
+    Write,
+    /// Installing, updating and rolling back tools.
+    Tools,
+    /// Server administration, such as reloading the config.
+    Admin,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 4] = [
+        TokenScope::Read,
+        TokenScope::Write,
+        TokenScope::Tools,
+        TokenScope::Admin,
+    ];
+}
+
+/// Users and API tokens loaded from the data directory. The server reloads them when the file changes, for example after running the admin command.
+pub type SharedAuthStore = Arc<RwLock<AuthStore>>;
+
+/// Users and API tokens that can access the API, stored in the data directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthStore {
+    pub users: Vec<StoredUser>,
+    pub tokens: Vec<StoredToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredUser {
+    pub username: String,
+    /// Argon2 hash of the password in the PHC string format.
+    pub password_hash: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub id: String,
+    /// Name describing what the token is used for, such as the script using it.
+    pub name: String,
+    /// SHA-256 hash of the token. Tokens are long random strings, so a fast hash is enough to store them safely.
+    pub token_hash: String,
+    pub scopes: Vec<TokenScope>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub username: String,
+    pub expires_at: OffsetDateTime,
+}
+
+/// Caller of an authenticated request, added to the request extensions by the authentication middleware.
+#[derive(Debug, Clone)]
+pub enum Principal {
+    User {
+        username: String,
+    },
+    Token {
+        name: String,
+        scopes: Vec<TokenScope>,
+    },
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// Leaves out the password, so it is never logged.
+impl std::fmt::Debug for LoginRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoginRequest")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Who the request is authenticated as.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthSessionResponse {
+    /// Whether the server requires authentication. When it does not, the other fields are empty.
+    pub auth_enabled: bool,
+    /// The logged in user.
+    pub username: Option<String>,
+    /// Name of the API token used for the request.
+    pub token_name: Option<String>,
+    /// What the request can access.
+    pub scopes: Vec<TokenScope>,
+    /// When the session of the logged in user expires.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+}
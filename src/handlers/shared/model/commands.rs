@@ -1,7 +1,8 @@
 use serde::Serialize;
+use utoipa::ToSchema;
 
 /// Represents the results of executing a command.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct CommandExecutionResults {
     /// Indicates whether the command completed successfully.
     pub command_completed_successfully: bool,
@@ -0,0 +1,14 @@
+/// Audio file that can be downloaded over HTTP without yt-dlp, detected from the response to a HEAD request.
+#[derive(Debug, Clone)]
+pub struct DirectAudioSource {
+    /// URL of the file after following redirects.
+    pub url: String,
+    /// Content type reported by the server, without parameters.
+    pub content_type: String,
+    /// Extension of the downloaded file, based on the content type or the URL.
+    pub file_ext: String,
+    /// Size of the file in bytes, if reported by the server.
+    pub content_length: Option<u64>,
+    /// Title taken from the file name in the `Content-Disposition` header or the URL, if there is one.
+    pub title: Option<String>,
+}
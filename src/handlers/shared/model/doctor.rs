@@ -0,0 +1,71 @@
+use crate::handlers::shared::model::tools::Tool;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Represents the result of all health checks performed by the doctor.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DoctorResponse {
+    /// Indicates whether all checks passed.
+    pub healthy: bool,
+    /// Architecture of the machine the server runs on.
+    pub host_arch: String,
+    /// Operating system of the machine the server runs on.
+    pub host_os: String,
+    /// Results of checking the managed tools.
+    pub tools: Vec<ToolDoctorReport>,
+    /// Results of checking the directories used by the server.
+    pub directories: Vec<DirectoryDoctorReport>,
+    /// Results of checking DNS resolution and outbound connectivity.
+    pub network: Vec<NetworkDoctorReport>,
+}
+
+/// Represents the health of a single managed tool.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ToolDoctorReport {
+    /// The checked tool.
+    pub tool: Tool,
+    /// Indicates whether the tool executable exists.
+    pub present: bool,
+    /// Indicates whether the tool version command ran successfully.
+    pub runnable: bool,
+    /// The local file system path to the tool executable, if found.
+    pub path: Option<String>,
+    /// The version reported by the tool, if available.
+    pub version: Option<String>,
+    /// The architecture the executable was built for, detected from its file header.
+    pub arch: Option<String>,
+    /// The error encountered while checking the tool, if any.
+    pub error: Option<String>,
+}
+
+/// Represents the health of a directory used by the server.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DirectoryDoctorReport {
+    /// Name of the setting the directory comes from.
+    pub name: String,
+    /// The configured local file system path.
+    pub path: String,
+    /// Indicates whether the directory exists.
+    pub exists: bool,
+    /// Indicates whether a file could be created in the directory.
+    pub writable: bool,
+    /// Free disk space available to the server on the volume containing the directory, in bytes.
+    pub free_space_bytes: Option<u64>,
+    /// The error encountered while checking the directory, if any.
+    pub error: Option<String>,
+}
+
+/// Represents the result of a connectivity check.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NetworkDoctorReport {
+    /// Name of the check.
+    pub name: String,
+    /// The hostname or URL that was checked.
+    pub target: String,
+    /// Indicates whether the target was reachable. `None` if the check was skipped.
+    pub reachable: Option<bool>,
+    /// Additional details, for example resolved addresses or the HTTP status.
+    pub details: Option<String>,
+    /// The error encountered while checking the target, if any.
+    pub error: Option<String>,
+}
@@ -0,0 +1,34 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+/// Shared registry of download progress entries, keyed by the download identifier (for example tool name).
+pub type DownloadProgressRegistry = Arc<RwLock<HashMap<String, DownloadProgress>>>;
+
+/// Represents the state of a streamed file download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    InProgress,
+    Completed,
+    Failed,
+}
+
+/// Represents the progress of a streamed file download.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DownloadProgress {
+    /// The URL the file is downloaded from.
+    pub download_url: String,
+    /// Current state of the download.
+    pub state: DownloadState,
+    /// Number of bytes already written to disk, including bytes from a resumed partial download.
+    pub downloaded_bytes: u64,
+    /// Total size of the file in bytes, if reported by the server.
+    pub total_bytes: Option<u64>,
+    /// Number of bytes that were already present on disk when the download was resumed.
+    pub resumed_from_bytes: u64,
+    /// The error message if the download failed.
+    pub error: Option<String>,
+}
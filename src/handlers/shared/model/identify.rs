@@ -0,0 +1,37 @@
+use crate::handlers::shared::model::acoustid::AcoustIDApiLookupResponse;
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use crate::handlers::shared::model::musicbrainz::MusicbrainzAPIRecordingResponse;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Results of fingerprinting an audio file and looking up the fingerprint in AcoustID and MusicBrainz.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AudioIdentification {
+    pub musicbrainz_response: Option<MusicbrainzAPIRecordingResponse>,
+    pub acoustid_response: Option<AcoustIDApiLookupResponse>,
+    pub fpcalc_fingerprint: Option<FpcalcFingerprintingResult>,
+    pub fingerprinting_command_result: CommandExecutionResults,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FpcalcFingerprintingResult {
+    pub duration: f64,
+    pub fingerprint: String,
+}
+
+/// Track metadata of an identified library file, stored in the library index.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IdentifiedMetadata {
+    /// ID of the MusicBrainz recording matching the fingerprint.
+    pub musicbrainz_recording_id: String,
+    /// Score of the AcoustID match, from 0 to 1.
+    pub acoustid_score: f64,
+    /// Title of the recording.
+    pub title: String,
+    /// Credited artists of the recording, joined as MusicBrainz displays them.
+    pub artist: Option<String>,
+    /// Title of the first release of the recording.
+    pub album: Option<String>,
+    /// Date of the first release of the recording, for example `2004-03-01`.
+    pub release_date: Option<String>,
+}
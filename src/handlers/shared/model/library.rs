@@ -0,0 +1,52 @@
+use crate::handlers::shared::model::identify::IdentifiedMetadata;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Index of library files with the metadata of the source they were downloaded from.
+/// Stored as JSON in the library directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct LibraryIndex {
+    /// Index entries keyed by the library file name.
+    #[serde(default)]
+    pub entries: BTreeMap<String, LibraryIndexEntry>,
+}
+
+/// Metadata of a single library file.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LibraryIndexEntry {
+    /// Name of the file in the library directory.
+    pub file_name: String,
+    /// ID of the download operation that added the file.
+    pub download_id: String,
+    /// Name of the extractor used to download the file, for example `youtube`.
+    pub extractor: Option<String>,
+    /// ID of the media assigned by the extractor.
+    pub media_id: Option<String>,
+    /// Title of the media.
+    pub title: Option<String>,
+    /// Name of the uploader or artist.
+    pub uploader: Option<String>,
+    /// Upload date in the YYYYMMDD format.
+    pub upload_date: Option<String>,
+    /// URL of the page the media was downloaded from.
+    pub source_url: Option<String>,
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+    /// Chapters of the media.
+    #[serde(default)]
+    pub chapters: Vec<LibraryChapter>,
+    /// Track metadata identified from the audio fingerprint, if the file was identified.
+    pub identification: Option<IdentifiedMetadata>,
+}
+
+/// Single chapter of a library file.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LibraryChapter {
+    /// Title of the chapter.
+    pub title: Option<String>,
+    /// Start of the chapter in seconds.
+    pub start_time: f64,
+    /// End of the chapter in seconds.
+    pub end_time: f64,
+}
@@ -1,15 +1,522 @@
 use crate::handlers::shared::model::commands::CommandExecutionResults;
-use serde::Serialize;
+use crate::handlers::shared::model::library::LibraryIndexEntry;
+use crate::handlers::shared::model::postprocessing::PostProcessingStepResult;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+use strum_macros::Display;
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+/// Target audio format of a download. The format is converted by yt-dlp using ffmpeg.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Display, Deserialize, Serialize, ValueEnum, ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum AudioFormat {
+    Mp3,
+    Opus,
+    M4a,
+    Flac,
+    /// Keep the best audio format served by the site without converting it.
+    #[default]
+    BestOriginal,
+}
+
+/// Audio quality passed to yt-dlp. Either a VBR quality from 0 (best) to 10 (worst) or a bitrate such as `192K`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct AudioQuality(String);
+
+impl TryFrom<String> for AudioQuality {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let is_vbr_quality = value.parse::<u8>().is_ok_and(|quality| quality <= 10);
+        let is_bitrate = value
+            .strip_suffix(['K', 'k'])
+            .is_some_and(|bitrate| bitrate.parse::<u16>().is_ok_and(|bitrate| bitrate > 0));
+
+        if is_vbr_quality || is_bitrate {
+            Ok(Self(value))
+        } else {
+            Err(format!(
+                "Invalid audio quality: {}. Expected a number from 0 to 10 or a bitrate such as 192K",
+                value
+            ))
+        }
+    }
+}
+
+impl From<AudioQuality> for String {
+    fn from(value: AudioQuality) -> Self {
+        value.0
+    }
+}
+
+impl AudioQuality {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Audio sample rate in Hz used when converting downloaded audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct AudioSampleRate(u32);
+
+impl TryFrom<u32> for AudioSampleRate {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if (8_000..=192_000).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(format!(
+                "Invalid sample rate: {}. Expected a value from 8000 to 192000 Hz",
+                value
+            ))
+        }
+    }
+}
+
+impl From<AudioSampleRate> for u32 {
+    fn from(value: AudioSampleRate) -> Self {
+        value.0
+    }
+}
+
+/// Proxy schemes supported by yt-dlp.
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks4", "socks4a", "socks5", "socks5h"];
+
+/// Proxy URL passed to yt-dlp, for example `socks5://127.0.0.1:1080`. An empty URL disables the proxy.
+/// The URL can contain credentials, so it is hidden in debug output.
+#[derive(Clone, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(try_from = "String")]
+pub struct ProxyUrl(String);
+
+impl TryFrom<String> for ProxyUrl {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Ok(Self(value));
+        }
+
+        match value.split_once("://") {
+            Some((scheme, address))
+                if PROXY_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str())
+                    && !address.is_empty() =>
+            {
+                Ok(Self(value))
+            }
+            _ => Err(format!(
+                "Invalid proxy URL. Expected an empty URL or a URL with one of the schemes: {}",
+                PROXY_SCHEMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Debug for ProxyUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProxyUrl(<redacted>)")
+    }
+}
+
+impl ProxyUrl {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Maximum download rate in bytes per second, for example `50K` or `4.2M`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(try_from = "String")]
+pub struct RateLimit(String);
+
+impl TryFrom<String> for RateLimit {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let rate = value
+            .strip_suffix(['K', 'k', 'M', 'm', 'G', 'g'])
+            .unwrap_or(&value);
+
+        if rate
+            .parse::<f64>()
+            .is_ok_and(|rate| rate.is_finite() && rate > 0.0)
+        {
+            Ok(Self(value))
+        } else {
+            Err(format!(
+                "Invalid rate limit: {}. Expected a rate in bytes per second such as 50K or 4.2M",
+                value
+            ))
+        }
+    }
+}
+
+impl RateLimit {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Number of seconds to sleep between yt-dlp requests or downloads.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(try_from = "f64")]
+pub struct SleepInterval(f64);
+
+impl TryFrom<f64> for SleepInterval {
+    type Error = String;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if value.is_finite() && value >= 0.0 {
+            Ok(Self(value))
+        } else {
+            Err(format!(
+                "Invalid sleep interval: {}. Expected a non-negative number of seconds",
+                value
+            ))
+        }
+    }
+}
+
+impl From<SleepInterval> for f64 {
+    fn from(value: SleepInterval) -> Self {
+        value.0
+    }
+}
+
+/// Cookies in the Netscape `cookies.txt` format. They are never serialized and are hidden in debug output.
+#[derive(Clone, Deserialize, ToSchema)]
+#[serde(transparent)]
+pub struct Cookies(String);
+
+impl fmt::Debug for Cookies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cookies(<redacted>)")
+    }
+}
+
+impl Cookies {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Network and authentication options of yt-dlp. Each option set in a request overrides the server setting.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct YtDlpNetworkOptions {
+    /// Cookies used instead of the server cookies file. They are written to a private file that is removed after the command finishes.
+    pub cookies: Option<Cookies>,
+    /// Proxy used for all requests. An empty URL disables the server proxy. Only accepted when the server allows request proxies.
+    pub proxy: Option<ProxyUrl>,
+    /// Maximum download rate.
+    pub limit_rate: Option<RateLimit>,
+    /// Minimum number of seconds to sleep before each download.
+    pub sleep_interval: Option<SleepInterval>,
+    /// Maximum number of seconds to sleep before each download. Sleep times are randomized between the minimum and this value.
+    pub max_sleep_interval: Option<SleepInterval>,
+    /// Number of seconds to sleep between requests during data extraction.
+    pub sleep_requests: Option<SleepInterval>,
+    /// Number of retries of failed downloads.
+    pub retries: Option<u32>,
+}
+
+/// SponsorBlock category of segments that can be cut from downloaded media.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum SponsorBlockCategory {
+    Sponsor,
+    Intro,
+    Outro,
+    Selfpromo,
+    Preview,
+    Filler,
+    Interaction,
+    /// Non-music parts of music videos.
+    MusicOfftopic,
+}
+
+/// Format and quality preferences for audio downloads.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct AudioDownloadOptions {
+    /// Target audio format. Defaults to the best format served by the site.
+    #[serde(default)]
+    pub audio_format: AudioFormat,
+    /// Target audio quality or bitrate. Defaults to yt-dlp's default quality.
+    pub audio_quality: Option<AudioQuality>,
+    /// Target sample rate. Defaults to the sample rate of the source.
+    pub sample_rate: Option<AudioSampleRate>,
+    /// Also split the audio into separate tracks using the chapters of the media, if it has any.
+    #[serde(default)]
+    pub split_chapters: bool,
+    /// SponsorBlock categories of segments cut from the audio. Nothing is cut by default.
+    #[serde(default)]
+    pub sponsorblock_remove: Vec<SponsorBlockCategory>,
+}
+
+impl AudioDownloadOptions {
+    /// Indicates whether the options need yt-dlp's post-processing, such as converting the audio or cutting segments.
+    pub fn requires_post_processing(&self) -> bool {
+        self.audio_format != AudioFormat::BestOriginal
+            || self.audio_quality.is_some()
+            || self.sample_rate.is_some()
+            || self.split_chapters
+            || !self.sponsorblock_remove.is_empty()
+    }
+}
+
+/// How the media of a download is fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadMethod {
+    /// Downloaded and post-processed by yt-dlp.
+    YtDlp,
+    /// Direct link to an audio file downloaded over HTTP and kept in the format served by the site.
+    Direct,
+}
 
 /// Represents the response for a media download operation.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct MediaDownloadResponse {
     /// ID of the download operation. Will be used to move files from the download directory to the library directory.
     pub download_id: String,
     /// The URL from which the media was downloaded.
     pub requested_url: String,
+    /// How the media was downloaded.
+    pub download_method: DownloadMethod,
     /// The local file system path to the media library the file will be moved to after successful download.
     pub library_dir: String,
+    /// Names of the files moved to the library.
+    pub library_files: Vec<String>,
+    /// Indicates that the media was downloaded before and the download was skipped.
+    pub already_in_library: bool,
+    /// The library entry of the previously downloaded media, if the download was skipped.
+    pub existing_library_entry: Option<LibraryIndexEntry>,
     /// The results of executing the command to download the media.
     pub command_execution_results: CommandExecutionResults,
+    /// Results of the post-processing steps run on the downloaded files.
+    pub post_processing: Vec<PostProcessingStepResult>,
+}
+
+/// Maximum vertical resolution of a downloaded video.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub enum VideoResolution {
+    #[serde(rename = "360p")]
+    P360,
+    #[serde(rename = "480p")]
+    P480,
+    #[serde(rename = "720p")]
+    P720,
+    #[serde(rename = "1080p")]
+    P1080,
+    #[serde(rename = "1440p")]
+    P1440,
+    #[serde(rename = "2160p")]
+    P2160,
+}
+
+impl VideoResolution {
+    pub fn height(self) -> u32 {
+        match self {
+            VideoResolution::P360 => 360,
+            VideoResolution::P480 => 480,
+            VideoResolution::P720 => 720,
+            VideoResolution::P1080 => 1080,
+            VideoResolution::P1440 => 1440,
+            VideoResolution::P2160 => 2160,
+        }
+    }
+}
+
+/// Preferred video codec of a downloaded video.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum VideoCodec {
+    H264,
+    Vp9,
+    Av1,
+}
+
+/// Container the downloaded video and audio streams are merged into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum VideoContainer {
+    #[default]
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+/// Subtitle language code or pattern accepted by yt-dlp, for example `en`, `de` or `en.*`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct SubtitleLanguage(String);
+
+impl TryFrom<String> for SubtitleLanguage {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let is_valid = !value.is_empty()
+            && !value.starts_with('-')
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '*'));
+
+        if is_valid {
+            Ok(Self(value))
+        } else {
+            Err(format!("Invalid subtitle language: {}", value))
+        }
+    }
+}
+
+impl From<SubtitleLanguage> for String {
+    fn from(value: SubtitleLanguage) -> Self {
+        value.0
+    }
+}
+
+impl SubtitleLanguage {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Resolution, codec, container and subtitle preferences for video downloads.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct VideoDownloadOptions {
+    /// Maximum resolution. Defaults to the best available resolution.
+    pub max_resolution: Option<VideoResolution>,
+    /// Preferred video codec. Other codecs are used if the preferred one is not available.
+    pub video_codec: Option<VideoCodec>,
+    /// Container of the resulting file. Defaults to mp4.
+    #[serde(default)]
+    pub container: VideoContainer,
+    /// Languages of subtitles to download. No subtitles are downloaded if empty.
+    #[serde(default)]
+    pub subtitle_languages: Vec<SubtitleLanguage>,
+    /// Also download subtitles generated automatically by the site.
+    #[serde(default)]
+    pub include_auto_subtitles: bool,
+    /// Embed subtitles into the video file instead of keeping them as separate files.
+    #[serde(default)]
+    pub embed_subtitles: bool,
+}
+
+/// Options of playlist and channel downloads.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct PlaylistDownloadOptions {
+    /// Skip private, deleted and otherwise unavailable entries and continue after failed items.
+    /// When disabled, the job stops at the first failed item.
+    #[serde(default)]
+    pub skip_unavailable: bool,
+    /// Download entries even if they are already in the library.
+    #[serde(default)]
+    pub force: bool,
+    /// Only process the first entries of the playlist. For channels these are the latest uploads.
+    pub max_items: Option<usize>,
+    #[serde(flatten)]
+    pub audio_download_options: AudioDownloadOptions,
+}
+
+/// Shared registry of playlist download jobs, keyed by the download ID of the parent job.
+pub type PlaylistDownloadRegistry = Arc<RwLock<HashMap<String, PlaylistDownloadEntry>>>;
+
+/// Playlist download job kept in the registry. Finished jobs are evicted some time after they finish.
+#[derive(Debug, Clone)]
+pub struct PlaylistDownloadEntry {
+    pub job: PlaylistDownloadJob,
+    /// When the job left the in progress state.
+    pub finished_at: Option<Instant>,
+}
+
+/// Represents the state of a playlist download job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaylistDownloadState {
+    InProgress,
+    Completed,
+    CompletedWithErrors,
+    Failed,
+}
+
+/// Represents the state of a single item of a playlist download job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaylistItemState {
+    Pending,
+    InProgress,
+    Completed,
+    Failed,
+    Skipped,
+    AlreadyInLibrary,
+}
+
+/// Represents a playlist or channel download. Each entry is downloaded as a separate child item.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PlaylistDownloadJob {
+    /// ID of the parent download job.
+    pub download_id: String,
+    /// The playlist or channel URL requested for download.
+    pub requested_url: String,
+    /// ID of the playlist or channel assigned by the extractor, if available.
+    pub playlist_id: Option<String>,
+    /// Title of the playlist or channel, if available.
+    pub playlist_title: Option<String>,
+    /// The local file system path to the media library the files are moved to after successful download.
+    pub library_dir: String,
+    /// Current state of the job.
+    pub state: PlaylistDownloadState,
+    /// Number of items downloaded and moved to the library.
+    pub completed_items: usize,
+    /// Number of items that failed to download.
+    pub failed_items: usize,
+    /// Number of items that were not downloaded, including items already in the library.
+    pub skipped_items: usize,
+    /// The items of the playlist in playlist order.
+    pub items: Vec<PlaylistItemDownload>,
+    /// The error message if the playlist entries could not be listed.
+    pub error: Option<String>,
+    /// The results of executing the command listing the playlist entries.
+    pub command_execution_results: Option<CommandExecutionResults>,
+}
+
+/// Represents the download of a single playlist entry.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PlaylistItemDownload {
+    /// Position of the entry in the playlist, starting from 1.
+    pub index: usize,
+    /// ID of the entry assigned by the extractor, if available.
+    pub entry_id: Option<String>,
+    /// Name of the extractor of the entry, if available.
+    pub extractor: Option<String>,
+    /// Title of the entry, if available.
+    pub title: Option<String>,
+    /// URL of the entry, if available.
+    pub url: Option<String>,
+    /// ID of the child download. Used to move files from the download directory to the library directory.
+    pub download_id: String,
+    /// Current state of the item.
+    pub state: PlaylistItemState,
+    /// Names of the files moved to the library.
+    pub library_files: Vec<String>,
+    /// The reason the item failed or was skipped.
+    pub error: Option<String>,
+    /// The library entry of the previously downloaded entry, if the item was already in the library.
+    pub existing_library_entry: Option<LibraryIndexEntry>,
+    /// The results of executing the command to download the item.
+    pub command_execution_results: Option<CommandExecutionResults>,
+    /// Results of the post-processing steps run on the downloaded files.
+    pub post_processing: Vec<PostProcessingStepResult>,
 }
@@ -1,23 +1,34 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct MusicbrainzAPIRecordingResponse {
-    id: String,
-    title: String,
-    #[serde(rename = "first-release-date")]
-    first_release_date: String,
+    pub id: String,
+    pub title: String,
+    #[serde(rename = "first-release-date", default)]
+    pub first_release_date: String,
     #[serde(rename = "artist-credit")]
-    artist_credit: Vec<ArtistCredit>,
+    pub artist_credit: Vec<ArtistCredit>,
+    #[serde(default)]
+    pub releases: Vec<Release>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ArtistCredit {
-    name: String,
-    artist: Artist,
+    pub name: String,
+    #[serde(default)]
+    pub joinphrase: String,
+    pub artist: Artist,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Artist {
-    id: String,
-    name: String,
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Release {
+    pub id: String,
+    pub title: String,
 }
@@ -0,0 +1,209 @@
+use std::fmt;
+
+/// Default template keeps the naming used before templates were configurable.
+pub const DEFAULT_FILE_NAME_TEMPLATE: &str = "{title}.{ext}";
+
+/// Field of the media metadata that can be used in a file name template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateField {
+    Artist,
+    Album,
+    Track,
+    Title,
+    Uploader,
+    Id,
+    Extractor,
+    UploadDate,
+    Ext,
+}
+
+impl TemplateField {
+    const ALL: [TemplateField; 9] = [
+        TemplateField::Artist,
+        TemplateField::Album,
+        TemplateField::Track,
+        TemplateField::Title,
+        TemplateField::Uploader,
+        TemplateField::Id,
+        TemplateField::Extractor,
+        TemplateField::UploadDate,
+        TemplateField::Ext,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TemplateField::Artist => "artist",
+            TemplateField::Album => "album",
+            TemplateField::Track => "track",
+            TemplateField::Title => "title",
+            TemplateField::Uploader => "uploader",
+            TemplateField::Id => "id",
+            TemplateField::Extractor => "extractor",
+            TemplateField::UploadDate => "upload_date",
+            TemplateField::Ext => "ext",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+}
+
+/// Part of a parsed file name template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSegment {
+    /// Text copied to the file name as is. A `/` starts a new directory.
+    Literal(String),
+    /// Field replaced with a sanitized metadata value. Numbers are zero padded to `width` digits, for example `{track:02}`.
+    Field {
+        field: TemplateField,
+        width: Option<usize>,
+    },
+}
+
+/// Template used to name files moved to the library, for example `{artist}/{album}/{track:02} {title}.{ext}`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct FileNameTemplate {
+    template: String,
+    segments: Vec<TemplateSegment>,
+}
+
+impl TryFrom<String> for FileNameTemplate {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let segments = parse_file_name_template(&value)?;
+
+        if !value.ends_with(".{ext}") {
+            return Err("File name template must end with .{ext}".to_string());
+        }
+        if value.starts_with('/') || value.contains('\\') {
+            return Err(
+                "File name template must be a relative path using / as the separator".to_string(),
+            );
+        }
+        if value
+            .split('/')
+            .any(|component| component.trim().is_empty() || component == "." || component == "..")
+        {
+            return Err(
+                "File name template must not contain empty, . or .. path components".to_string(),
+            );
+        }
+
+        Ok(Self {
+            template: value,
+            segments,
+        })
+    }
+}
+
+impl From<FileNameTemplate> for String {
+    fn from(value: FileNameTemplate) -> Self {
+        value.template
+    }
+}
+
+impl fmt::Debug for FileNameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FileNameTemplate({:?})", self.template)
+    }
+}
+
+impl Default for FileNameTemplate {
+    fn default() -> Self {
+        Self::try_from(DEFAULT_FILE_NAME_TEMPLATE.to_string())
+            .expect("Default file name template is valid")
+    }
+}
+
+impl FileNameTemplate {
+    pub fn segments(&self) -> &[TemplateSegment] {
+        &self.segments
+    }
+}
+
+/// Metadata values used to render a file name template. Values that are not known are rendered using placeholders such as `Unknown Artist`.
+#[derive(Debug, Clone, Default)]
+pub struct FileNameValues {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track: Option<u32>,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub id: Option<String>,
+    pub extractor: Option<String>,
+    pub upload_date: Option<String>,
+    pub ext: String,
+}
+
+fn parse_file_name_template(template: &str) -> Result<Vec<TemplateSegment>, String> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err("Unclosed { in file name template".to_string()),
+                    }
+                }
+
+                let (name, format_spec) = match placeholder.split_once(':') {
+                    Some((name, format_spec)) => (name, Some(format_spec)),
+                    None => (placeholder.as_str(), None),
+                };
+                let field = TemplateField::from_name(name).ok_or_else(|| {
+                    format!(
+                        "Unknown file name template field: {}. Expected one of: {}",
+                        name,
+                        TemplateField::ALL
+                            .iter()
+                            .map(|field| field.name())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+                let width = match format_spec {
+                    Some(format_spec) if field == TemplateField::Track => Some(
+                        format_spec
+                            .strip_prefix('0')
+                            .and_then(|width| width.parse::<usize>().ok())
+                            .filter(|width| (1..=9).contains(width))
+                            .ok_or_else(|| {
+                                format!(
+                                    "Invalid format of the track field: {}. Expected zero padding such as {{track:02}}",
+                                    format_spec
+                                )
+                            })?,
+                    ),
+                    Some(_) => {
+                        return Err(format!(
+                            "Only the track field supports a format, found one for {}",
+                            name
+                        ))
+                    }
+                    None => None,
+                };
+
+                if !literal.is_empty() {
+                    segments.push(TemplateSegment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(TemplateSegment::Field { field, width });
+            }
+            '}' => return Err("Unmatched } in file name template".to_string()),
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(TemplateSegment::Literal(literal));
+    }
+
+    Ok(segments)
+}
@@ -0,0 +1,59 @@
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+use utoipa::ToSchema;
+
+/// Steps run on audio files after they are downloaded to the library. Enabled steps always run in the order listed here.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Display, Deserialize, Serialize, ValueEnum, ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum PostProcessingStep {
+    /// Fingerprint the audio and look it up in AcoustID and MusicBrainz.
+    Identify,
+    /// Write the identified metadata to the tags of the file.
+    Tag,
+    /// Normalize the loudness of the audio to the configured target.
+    Normalize,
+    /// Convert the audio to the preferred format.
+    Convert,
+    /// Rename the file using the library file name template and the identified metadata.
+    Organize,
+}
+
+impl PostProcessingStep {
+    /// All steps in the order they run.
+    pub const ALL: [PostProcessingStep; 5] = [
+        PostProcessingStep::Identify,
+        PostProcessingStep::Tag,
+        PostProcessingStep::Normalize,
+        PostProcessingStep::Convert,
+        PostProcessingStep::Organize,
+    ];
+}
+
+/// Outcome of a post-processing step for a single library file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PostProcessingStepState {
+    Completed,
+    /// The step was skipped by the request or had nothing to do.
+    Skipped,
+    /// The step failed. The file is left as it was before the step and the remaining steps still run.
+    Failed,
+}
+
+/// Result of a post-processing step for a single library file.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PostProcessingStepResult {
+    /// Name of the library file the step ran on, before the step renamed it.
+    pub library_file: String,
+    pub step: PostProcessingStep,
+    pub state: PostProcessingStepState,
+    /// Details of the outcome, such as the reason the step was skipped or the error it failed with.
+    pub message: Option<String>,
+    /// The results of the commands run by the step.
+    pub command_execution_results: Vec<CommandExecutionResults>,
+}
@@ -0,0 +1,13 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Result of reloading the config file while the server is running.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigReloadResponse {
+    /// The config file the settings were read from, if one was found.
+    pub config_file: Option<String>,
+    /// Settings applied by the reload, named as in the config file, for example `library.dir`.
+    pub applied_settings: Vec<String>,
+    /// Settings that changed but keep their current values until the server is restarted, for example `server.port`.
+    pub restart_required_settings: Vec<String>,
+}
@@ -0,0 +1,118 @@
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Default number of search results.
+pub const DEFAULT_SEARCH_RESULT_COUNT: usize = 5;
+/// Search queries are short text, longer values are most likely pasted by mistake.
+const MAX_SEARCH_QUERY_CHARS: usize = 200;
+
+/// Site searched by yt-dlp.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSource {
+    #[default]
+    Youtube,
+    Soundcloud,
+}
+
+/// Text searched for, for example the artist and title of a track.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+pub struct SearchQuery(String);
+
+impl TryFrom<String> for SearchQuery {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let value = value.trim().to_string();
+        if value.is_empty() {
+            Err("Search query must not be empty".to_string())
+        } else if value.chars().count() > MAX_SEARCH_QUERY_CHARS {
+            Err(format!(
+                "Search query must not be longer than {} characters",
+                MAX_SEARCH_QUERY_CHARS
+            ))
+        } else {
+            Ok(Self(value))
+        }
+    }
+}
+
+impl From<SearchQuery> for String {
+    fn from(value: SearchQuery) -> Self {
+        value.0
+    }
+}
+
+impl SearchQuery {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Number of search results from 1 to 25.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(try_from = "usize", into = "usize")]
+pub struct SearchResultCount(usize);
+
+impl TryFrom<usize> for SearchResultCount {
+    type Error = String;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        if (1..=25).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(format!(
+                "Invalid number of search results: {}. Expected a value from 1 to 25",
+                value
+            ))
+        }
+    }
+}
+
+impl From<SearchResultCount> for usize {
+    fn from(value: SearchResultCount) -> Self {
+        value.0
+    }
+}
+
+impl Default for SearchResultCount {
+    fn default() -> Self {
+        Self(DEFAULT_SEARCH_RESULT_COUNT)
+    }
+}
+
+/// Single search result. Its URL can be downloaded using the audio, video or playlist download endpoints.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchCandidate {
+    /// Position of the result, starting from 1.
+    pub index: usize,
+    /// ID of the media assigned by the extractor.
+    pub id: Option<String>,
+    /// Title of the media.
+    pub title: Option<String>,
+    /// Name of the uploader or channel.
+    pub uploader: Option<String>,
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+    /// URL of the largest thumbnail.
+    pub thumbnail: Option<String>,
+    /// URL of the media.
+    pub url: Option<String>,
+    /// Name of the extractor handling the URL, in lowercase.
+    pub extractor: Option<String>,
+}
+
+/// Represents the response for searching media without downloading it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MediaSearchResponse {
+    /// The searched text.
+    pub query: SearchQuery,
+    /// The searched site.
+    pub source: SearchSource,
+    /// Results in the order returned by the site.
+    pub candidates: Vec<SearchCandidate>,
+    /// The results of executing the search command.
+    pub command_execution_results: CommandExecutionResults,
+}
@@ -0,0 +1,76 @@
+use crate::handlers::shared::model::commands::CommandExecutionResults;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Source of the track boundaries used to split a library file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitSource {
+    /// Chapters stored in the library index or embedded in the file.
+    Chapters,
+    /// Cue sheet supplied with the request.
+    CueSheet,
+}
+
+/// Part of an audio file that is extracted as a separate track.
+#[derive(Debug, Clone)]
+pub struct TrackSegment {
+    /// Title of the track, if known.
+    pub title: Option<String>,
+    /// Performer of the track, if known.
+    pub performer: Option<String>,
+    /// Start of the track in seconds.
+    pub start_time: f64,
+    /// End of the track in seconds. The track ends with the file if not set.
+    pub end_time: Option<f64>,
+}
+
+/// Track created by splitting a library file.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SplitTrack {
+    /// Number of the track, starting from 1.
+    pub track_number: usize,
+    /// Title the track was tagged with.
+    pub title: String,
+    /// Start of the track in the source file in seconds.
+    pub start_time: f64,
+    /// End of the track in the source file in seconds, if the track does not end with the file.
+    pub end_time: Option<f64>,
+    /// Name of the track file in the library directory.
+    pub file_name: String,
+}
+
+/// Represents the response for splitting a library file into separate tracks.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LibrarySplitResponse {
+    /// Name of the library file that was split.
+    pub library_file_name: String,
+    /// Source of the track boundaries.
+    pub source: SplitSource,
+    /// Tracks created in the library directory.
+    pub tracks: Vec<SplitTrack>,
+    /// The results of executing ffmpeg for each track.
+    pub command_execution_results: Vec<CommandExecutionResults>,
+}
+
+/// Subset of the JSON printed by `ffprobe -show_chapters`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FfprobeChapters {
+    #[serde(default)]
+    pub chapters: Vec<FfprobeChapter>,
+}
+
+/// Single chapter printed by ffprobe. Times are printed as strings.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FfprobeChapter {
+    pub start_time: String,
+    pub end_time: String,
+    #[serde(default)]
+    pub tags: FfprobeChapterTags,
+}
+
+/// Tags of a chapter printed by ffprobe.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct FfprobeChapterTags {
+    pub title: Option<String>,
+}
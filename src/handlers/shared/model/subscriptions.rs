@@ -0,0 +1,105 @@
+use crate::handlers::shared::functions::schedule::parse_cron_expression;
+use crate::handlers::shared::model::media::{AudioDownloadOptions, PlaylistDownloadState};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// Cron-like schedule with five fields: minute, hour, day of month, month and day of week.
+/// Supports `*`, values, ranges, lists, steps (for example `*/15` or `1-5`) and aliases such as `@daily`. Times are in UTC.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    /// The expression the schedule was parsed from.
+    pub expression: String,
+    /// Bit masks of the values matched by each field.
+    pub fields: CronFields,
+}
+
+/// Parsed fields of a cron schedule. Bit `n` of a mask is set if value `n` matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CronFields {
+    pub minutes: u64,
+    pub hours: u64,
+    pub days_of_month: u64,
+    pub months: u64,
+    /// Sunday is 0.
+    pub days_of_week: u64,
+    /// Whether the day of month field was not `*`.
+    pub days_of_month_restricted: bool,
+    /// Whether the day of week field was not `*`.
+    pub days_of_week_restricted: bool,
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let fields = parse_cron_expression(&value)?;
+
+        Ok(Self {
+            expression: value,
+            fields,
+        })
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(value: CronSchedule) -> Self {
+        value.expression
+    }
+}
+
+/// Subscription to a channel or playlist whose new items are downloaded on a schedule.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Subscription {
+    /// ID of the subscription.
+    pub id: String,
+    /// The channel or playlist URL.
+    pub url: String,
+    /// When the subscription is synced.
+    #[schema(value_type = String, example = "@daily")]
+    pub schedule: CronSchedule,
+    /// Number of latest entries checked for new items on each sync.
+    pub max_items_per_sync: usize,
+    /// Format and quality of the downloaded audio.
+    #[serde(flatten)]
+    pub audio_download_options: AudioDownloadOptions,
+    /// When the subscription was created.
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// When the subscription is synced next, if the schedule has any upcoming time.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub next_sync_at: Option<OffsetDateTime>,
+    /// Result of the latest sync, if the subscription was synced already.
+    pub last_sync: Option<SubscriptionSync>,
+}
+
+/// Result of syncing a subscription.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubscriptionSync {
+    /// When the sync started.
+    #[serde(with = "time::serde::rfc3339")]
+    pub started_at: OffsetDateTime,
+    /// When the sync finished.
+    #[serde(with = "time::serde::rfc3339")]
+    pub finished_at: OffsetDateTime,
+    /// ID of the playlist download job used for the sync, if it was started.
+    pub download_id: Option<String>,
+    /// Final state of the playlist download job.
+    pub state: PlaylistDownloadState,
+    /// Number of new items downloaded.
+    pub completed_items: usize,
+    /// Number of items that failed to download.
+    pub failed_items: usize,
+    /// Number of items that were skipped, including items already in the library.
+    pub skipped_items: usize,
+    /// The error message if the sync failed.
+    pub error: Option<String>,
+}
+
+/// Persisted subscriptions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct SubscriptionStore {
+    #[serde(default)]
+    pub subscriptions: Vec<Subscription>,
+}
@@ -1,17 +1,108 @@
 use crate::handlers::shared::model::commands::CommandExecutionResults;
-use serde::Serialize;
+use crate::handlers::shared::model::downloads::DownloadProgress;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use strum_macros::Display;
+use utoipa::ToSchema;
+
+/// Tools managed by the application.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Display, Deserialize, Serialize, ValueEnum, ToSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Tool {
+    YtDlp,
+    Ffmpeg,
+    Chromaprint,
+}
+
+/// Release channels of yt-dlp. Each channel is published from a separate GitHub repository.
+#[derive(Debug, Clone, Copy, Default, Display, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum YtDlpUpdateChannels {
+    #[default]
+    Stable,
+    Master,
+    Nightly,
+}
 
 /// Represents the response for a successful tool download operation.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ToolDownloadResponse {
     /// The URL from which the tool was downloaded.
     pub download_url: String,
     /// The local file system directory where the downloaded tool is stored.
     pub tools_dir_path: String,
+    /// The version of the tool that was installed and is now active.
+    pub installed_version: String,
+}
+
+/// Represents the response for a successful tool update operation.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ToolUpdateResponse {
+    /// The tool that was updated.
+    pub tool: Tool,
+    /// The URL from which the new version was downloaded.
+    pub download_url: String,
+    /// The version that is active after the update.
+    pub installed_version: String,
+    /// The version that was active before the update and can be rolled back to, if any.
+    pub previous_version: Option<String>,
+    /// The local file system directory where the new version is installed.
+    pub install_dir_path: String,
+}
+
+/// Represents the response for a successful offline tool import operation.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ToolImportResponse {
+    /// The tool that was imported.
+    pub tool: Tool,
+    /// Name of the imported file or directory.
+    pub source: String,
+    /// The version that is active after the import.
+    pub installed_version: String,
+    /// The version that was active before the import and can be rolled back to, if any.
+    pub previous_version: Option<String>,
+    /// The local file system directory where the imported version is installed.
+    pub install_dir_path: String,
+}
+
+/// Represents the response for a successful tool rollback operation.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ToolRollbackResponse {
+    /// The tool that was rolled back.
+    pub tool: Tool,
+    /// The version that is active after the rollback.
+    pub active_version: String,
+    /// The version that was active before the rollback.
+    pub rolled_back_from_version: String,
+}
+
+/// Manifest stored in the tool directory describing which installed version is active.
+/// Replacing the manifest switches versions atomically.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ToolInstallManifest {
+    /// The version used when running the tool.
+    pub current_version: String,
+    /// The previously active version kept for rollback, if any.
+    pub previous_version: Option<String>,
+}
+
+/// Result of installing a tool version side-by-side with the existing ones.
+#[derive(Debug)]
+pub struct ToolInstallation {
+    /// The version that is active after the install.
+    pub installed_version: String,
+    /// The version that was active before the install, if any.
+    pub previous_version: Option<String>,
+    /// The directory the version was installed to.
+    pub install_dir: PathBuf,
 }
 
 /// Represents the response for a tool status check operation. This usually will use the version command of the tool to determine its status.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ToolStatusResponse {
     /// The local file system path to the tool executable.
     pub path: String,
@@ -20,3 +111,12 @@ pub struct ToolStatusResponse {
     /// The results of executing the version command on the tool.
     pub command_execution_results: CommandExecutionResults,
 }
+
+/// Represents the response for a tool download progress check.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ToolDownloadProgressResponse {
+    /// The tool the progress is reported for.
+    pub tool: Tool,
+    /// The progress of the latest download of the tool, if there was any since the server started.
+    pub progress: Option<DownloadProgress>,
+}
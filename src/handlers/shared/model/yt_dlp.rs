@@ -0,0 +1,93 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Subset of the JSON printed by `yt-dlp --flat-playlist --dump-single-json`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct YtDlpPlaylistInfo {
+    /// ID of the playlist or channel assigned by the extractor.
+    pub id: Option<String>,
+    /// Title of the playlist or channel.
+    pub title: Option<String>,
+    /// Name of the extractor, for example `youtube:tab` for channels or `youtube` for single media.
+    pub extractor: Option<String>,
+    /// Type of the result. `playlist` for playlists and channels, missing or `video` for single media.
+    #[serde(rename = "_type")]
+    pub info_type: Option<String>,
+    /// Entries of the playlist. Entries can be null when yt-dlp fails to extract them.
+    #[serde(default)]
+    pub entries: Vec<Option<YtDlpPlaylistEntry>>,
+}
+
+/// Single flat entry of a playlist or channel.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct YtDlpPlaylistEntry {
+    /// ID of the entry assigned by the extractor.
+    pub id: Option<String>,
+    /// Title of the entry.
+    pub title: Option<String>,
+    /// URL of the entry.
+    pub url: Option<String>,
+    /// Key of the extractor that handles the entry URL, for example `Youtube`.
+    pub ie_key: Option<String>,
+    /// Availability reported by the extractor, for example `public`, `private` or `needs_auth`.
+    pub availability: Option<String>,
+    /// Name of the uploader, if the extractor lists it without extracting the entry.
+    pub uploader: Option<String>,
+    /// Name of the channel, used when the uploader is not listed.
+    pub channel: Option<String>,
+    /// Duration in seconds, if the extractor lists it without extracting the entry.
+    pub duration: Option<f64>,
+    /// Thumbnails of the entry, usually ordered from the smallest to the largest.
+    #[serde(default)]
+    pub thumbnails: Vec<YtDlpThumbnail>,
+    /// Nested entries, for example channel tabs.
+    #[serde(default)]
+    pub entries: Vec<Option<YtDlpPlaylistEntry>>,
+}
+
+/// Thumbnail of a media item.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct YtDlpThumbnail {
+    /// URL of the thumbnail image.
+    pub url: String,
+}
+
+/// Subset of the info JSON written by yt-dlp's `--write-info-json` for a single media item.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct YtDlpMediaInfo {
+    /// ID of the media assigned by the extractor.
+    pub id: Option<String>,
+    /// Title of the media.
+    pub title: Option<String>,
+    /// Name of the uploader or artist.
+    pub uploader: Option<String>,
+    /// Artist of the track, if the site provides music metadata.
+    pub artist: Option<String>,
+    /// Album of the track, if the site provides music metadata.
+    pub album: Option<String>,
+    /// Title of the track, if the site provides music metadata.
+    pub track: Option<String>,
+    /// Number of the track on the album, if the site provides music metadata.
+    pub track_number: Option<u32>,
+    /// Upload date in the YYYYMMDD format.
+    pub upload_date: Option<String>,
+    /// URL of the page the media was downloaded from.
+    pub webpage_url: Option<String>,
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+    /// Chapters of the media, if the site provides them.
+    pub chapters: Option<Vec<YtDlpChapter>>,
+    /// Name of the extractor, for example `youtube`.
+    pub extractor: Option<String>,
+}
+
+/// Single chapter of a media item.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct YtDlpChapter {
+    /// Title of the chapter.
+    pub title: Option<String>,
+    /// Start of the chapter in seconds.
+    pub start_time: f64,
+    /// End of the chapter in seconds.
+    pub end_time: f64,
+}
@@ -0,0 +1,74 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::schedule::get_next_schedule_time;
+use crate::handlers::shared::functions::subscriptions::update_subscriptions;
+use crate::handlers::shared::functions::urls::validate_media_url;
+use crate::handlers::shared::model::media::AudioDownloadOptions;
+use crate::handlers::shared::model::subscriptions::{CronSchedule, Subscription};
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_MAX_ITEMS_PER_SYNC: usize = 25;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSubscriptionRequest {
+    url: String,
+    #[schema(value_type = String, example = "0 */6 * * *")]
+    schedule: CronSchedule,
+    max_items_per_sync: Option<usize>,
+    #[serde(flatten)]
+    audio_download_options: AudioDownloadOptions,
+}
+
+#[utoipa::path(
+    post,
+    path = "/subscriptions",
+    operation_id = "create_subscription",
+    tag = "subscriptions",
+    request_body = CreateSubscriptionRequest,
+    responses(
+        (status = 201, description = "Subscription created", body = Subscription),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_create_subscription(
+    State(app_state): State<AppState>,
+    Json(payload): Json<CreateSubscriptionRequest>,
+) -> Result<(StatusCode, Json<Subscription>), ServerError> {
+    debug!("Handling creation of subscription");
+
+    let url = validate_media_url(&app_state.config().url_policy_settings, &payload.url)?;
+
+    let created_at = OffsetDateTime::now_utc();
+    let subscription = Subscription {
+        id: Uuid::new_v4().to_string(),
+        url,
+        next_sync_at: get_next_schedule_time(&payload.schedule, created_at),
+        schedule: payload.schedule,
+        max_items_per_sync: payload
+            .max_items_per_sync
+            .unwrap_or(DEFAULT_MAX_ITEMS_PER_SYNC),
+        audio_download_options: payload.audio_download_options,
+        created_at,
+        last_sync: None,
+    };
+
+    update_subscriptions(&app_state, |subscription_store| {
+        subscription_store.subscriptions.push(subscription.clone())
+    })
+    .await?;
+
+    info!(
+        "Created subscription {}, next sync at: {:?}",
+        subscription.id, subscription.next_sync_at
+    );
+
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
@@ -0,0 +1,48 @@
+use crate::handlers::errors::{ErrorCode, ErrorResponses, RequestError, ServerError};
+use crate::handlers::shared::functions::subscriptions::update_subscriptions;
+use crate::handlers::shared::model::subscriptions::Subscription;
+use crate::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use tracing::{debug, info, instrument};
+
+#[utoipa::path(
+    delete,
+    path = "/subscriptions/{subscription_id}",
+    operation_id = "delete_subscription",
+    tag = "subscriptions",
+    params(
+        ("subscription_id" = String, Path, description = "ID of the subscription")
+    ),
+    responses(
+        (status = 200, description = "Subscription deleted", body = Subscription),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_delete_subscription(
+    Path(subscription_id): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<Subscription>), ServerError> {
+    debug!("Handling deletion of subscription");
+
+    let deleted_subscription = update_subscriptions(&app_state, |subscription_store| {
+        let subscription_index = subscription_store
+            .subscriptions
+            .iter()
+            .position(|subscription| subscription.id == subscription_id)?;
+        Some(subscription_store.subscriptions.remove(subscription_index))
+    })
+    .await?
+    .ok_or_else(|| {
+        RequestError::new(
+            ErrorCode::NotFound,
+            format!("No subscription with the ID {}", subscription_id),
+        )
+    })?;
+
+    info!("Deleted subscription {}", subscription_id);
+
+    Ok((StatusCode::OK, Json(deleted_subscription)))
+}
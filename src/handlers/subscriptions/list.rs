@@ -0,0 +1,31 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::subscriptions::read_subscriptions;
+use crate::handlers::shared::model::subscriptions::SubscriptionStore;
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use std::path::Path;
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    get,
+    path = "/subscriptions",
+    operation_id = "list_subscriptions",
+    tag = "subscriptions",
+    responses(
+        (status = 200, description = "All subscriptions", body = SubscriptionStore),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_list_subscriptions(
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<SubscriptionStore>), ServerError> {
+    debug!("Handling listing of subscriptions");
+
+    let subscription_store =
+        read_subscriptions(Path::new(&app_state.config().server_settings.data_dir)).await?;
+
+    Ok((StatusCode::OK, Json(subscription_store)))
+}
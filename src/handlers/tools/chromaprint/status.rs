@@ -1,4 +1,4 @@
-use crate::handlers::errors::ServerError;
+use crate::handlers::errors::{ErrorResponses, ServerError};
 use crate::handlers::shared::functions::commands::run_command;
 use crate::handlers::shared::functions::tools::get_chromaprint_fpcalc_executable_path;
 use crate::handlers::shared::model::tools::ToolStatusResponse;
@@ -9,6 +9,17 @@ use axum::http::StatusCode;
 use axum::Json;
 use tracing::{debug, instrument};
 
+#[utoipa::path(
+    get,
+    path = "/tools/chromaprint/status",
+    operation_id = "chromaprint_fpcalc_status",
+    tag = "tools",
+    responses(
+        (status = 200, description = "chromaprint is installed", body = ToolStatusResponse),
+        (status = 400, description = "chromaprint is not installed or cannot be run", body = ToolStatusResponse),
+        ErrorResponses
+    )
+)]
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn handle_chromaprint_fpcalc_status(
     State(app_state): State<AppState>,
@@ -0,0 +1,67 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::install::install_tool_version;
+use crate::handlers::shared::functions::tools::get_ffmpeg_download_url_and_output_file_name;
+use crate::handlers::shared::model::tools::{Tool, ToolUpdateResponse};
+use crate::AppState;
+use anyhow::Context;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct FfmpegUpdateRequest {
+    /// Version to install. The latest available version is installed if not provided.
+    version: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/tools/ffmpeg/update",
+    operation_id = "ffmpeg_update",
+    tag = "tools",
+    request_body = FfmpegUpdateRequest,
+    responses(
+        (status = 200, description = "ffmpeg updated", body = ToolUpdateResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_ffmpeg_update(
+    State(app_state): State<AppState>,
+    Json(payload): Json<FfmpegUpdateRequest>,
+) -> Result<(StatusCode, Json<ToolUpdateResponse>), ServerError> {
+    debug!("Handling updating of ffmpeg");
+
+    let os = std::env::consts::OS;
+    let (download_url, output_file_name) =
+        get_ffmpeg_download_url_and_output_file_name(os, payload.version.as_deref()).await?;
+
+    let installation = install_tool_version(
+        &app_state,
+        Tool::Ffmpeg,
+        &download_url,
+        &output_file_name,
+        payload.version.as_deref(),
+    )
+    .await
+    .context("Failed to install ffmpeg update")?;
+
+    info!(
+        "ffmpeg updated to version {}",
+        installation.installed_version
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ToolUpdateResponse {
+            tool: Tool::Ffmpeg,
+            download_url,
+            installed_version: installation.installed_version,
+            previous_version: installation.previous_version,
+            install_dir_path: installation.install_dir.to_string_lossy().to_string(),
+        }),
+    ))
+}
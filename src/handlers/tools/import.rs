@@ -0,0 +1,135 @@
+use crate::handlers::errors::{ErrorCode, ErrorResponses, RequestError, ServerError};
+use crate::handlers::shared::functions::install::install_tool_from_file;
+use crate::handlers::shared::functions::tools::get_tool_dir;
+use crate::handlers::shared::model::tools::{Tool, ToolImportResponse};
+use crate::AppState;
+use anyhow::Context;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use std::path::PathBuf;
+use tokio::fs::{create_dir_all, remove_file, File};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, instrument};
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+/// Largest accepted upload. Tool archives are well below it, it only stops uploads from filling the disk.
+const MAX_TOOL_IMPORT_BYTES: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ToolImportQuery {
+    /// Name of the uploaded file. Its extension is used to detect the archive format.
+    file_name: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/tools/{tool}/import",
+    operation_id = "tool_import",
+    tag = "tools",
+    params(
+        ("tool" = Tool, Path, description = "The tool"),
+        ToolImportQuery
+    ),
+    request_body(content = Vec<u8>, description = "Archive (zip, tar, tar.gz, tar.xz, 7z) or binary of the tool, up to 1 GiB", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Tool imported", body = ToolImportResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state, body))]
+pub async fn handle_tool_import(
+    Path(tool): Path<Tool>,
+    Query(query): Query<ToolImportQuery>,
+    State(app_state): State<AppState>,
+    body: Body,
+) -> Result<(StatusCode, Json<ToolImportResponse>), ServerError> {
+    debug!("Handling import of uploaded tool archive");
+
+    let file_name = std::path::Path::new(&query.file_name)
+        .file_name()
+        .context("Uploaded file name is empty or invalid")?
+        .to_string_lossy()
+        .to_string();
+
+    let config = app_state.config();
+    let tools_download_dir = std::path::Path::new(&config.server_settings.tools_download_dir);
+    let tool_dir = get_tool_dir(tools_download_dir, tool);
+    create_dir_all(&tool_dir)
+        .await
+        .context(format!("Failed to create tool directory for {}", tool))?;
+
+    let upload_file_path = tool_dir.join(format!("upload-{}-{}", Uuid::new_v4(), file_name));
+    info!(
+        "Receiving uploaded {} file: {}",
+        tool,
+        upload_file_path.display()
+    );
+
+    if let Err(err) = write_body_to_file(body, &upload_file_path).await {
+        remove_file(&upload_file_path)
+            .await
+            .context("Failed to remove incomplete uploaded file")?;
+        return Err(err.into());
+    }
+
+    let installation = install_tool_from_file(tools_download_dir, tool, &upload_file_path)
+        .await
+        .context(format!("Failed to import {}", tool))?;
+
+    info!(
+        "{} version {} imported successfully",
+        tool, installation.installed_version
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ToolImportResponse {
+            tool,
+            source: file_name,
+            installed_version: installation.installed_version,
+            previous_version: installation.previous_version,
+            install_dir_path: installation.install_dir.to_string_lossy().to_string(),
+        }),
+    ))
+}
+
+#[instrument(err, ret(level = "debug"), skip(body))]
+async fn write_body_to_file(body: Body, file_path: &PathBuf) -> Result<u64, anyhow::Error> {
+    let mut file = File::create(file_path)
+        .await
+        .context("Failed to create uploaded file")?;
+
+    let mut written_bytes = 0;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.context("Failed to read chunk of the uploaded file")?;
+        file.write_all(&chunk)
+            .await
+            .context("Failed to write chunk of the uploaded file")?;
+        written_bytes += chunk.len() as u64;
+        if written_bytes > MAX_TOOL_IMPORT_BYTES {
+            return Err(RequestError::new(
+                ErrorCode::PayloadTooLarge,
+                format!(
+                    "Uploaded file is larger than the maximum allowed size of {} bytes",
+                    MAX_TOOL_IMPORT_BYTES
+                ),
+            )
+            .into());
+        }
+    }
+
+    file.flush()
+        .await
+        .context("Failed to flush uploaded file")?;
+
+    info!("Received {} bytes", written_bytes);
+
+    Ok(written_bytes)
+}
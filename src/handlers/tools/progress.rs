@@ -0,0 +1,45 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::model::tools::{Tool, ToolDownloadProgressResponse};
+use crate::AppState;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    get,
+    path = "/tools/{tool}/download/progress",
+    operation_id = "tool_download_progress",
+    tag = "tools",
+    params(
+        ("tool" = Tool, Path, description = "The tool")
+    ),
+    responses(
+        (status = 200, description = "Progress of the tool download", body = ToolDownloadProgressResponse),
+        (status = 404, description = "The tool is not being downloaded", body = ToolDownloadProgressResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_tool_download_progress(
+    Path(tool): Path<Tool>,
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<ToolDownloadProgressResponse>), ServerError> {
+    debug!("Handling checking of tool download progress");
+
+    let progress = app_state
+        .download_progress
+        .read()
+        .await
+        .get(&tool.to_string())
+        .cloned();
+
+    Ok((
+        if progress.is_some() {
+            StatusCode::OK
+        } else {
+            StatusCode::NOT_FOUND
+        },
+        Json(ToolDownloadProgressResponse { tool, progress }),
+    ))
+}
@@ -0,0 +1,45 @@
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::install::rollback_tool_version;
+use crate::handlers::shared::model::tools::{Tool, ToolRollbackResponse};
+use crate::AppState;
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use tracing::{debug, instrument};
+
+#[utoipa::path(
+    post,
+    path = "/tools/{tool}/rollback",
+    operation_id = "tool_rollback",
+    tag = "tools",
+    params(
+        ("tool" = Tool, Path, description = "The tool")
+    ),
+    responses(
+        (status = 200, description = "Previous version of the tool restored", body = ToolRollbackResponse),
+        ErrorResponses
+    )
+)]
+#[instrument(err, ret(level = "debug"), skip(app_state))]
+pub async fn handle_tool_rollback(
+    Path(tool): Path<Tool>,
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<ToolRollbackResponse>), ServerError> {
+    debug!("Handling rollback of tool version");
+
+    let manifest = rollback_tool_version(&app_state, tool)
+        .await
+        .context(format!("Failed to roll back {}", tool))?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ToolRollbackResponse {
+            tool,
+            active_version: manifest.current_version,
+            rolled_back_from_version: manifest
+                .previous_version
+                .context("Rolled back manifest has no previous version")?,
+        }),
+    ))
+}
@@ -1,16 +1,24 @@
-use crate::handlers::errors::ServerError;
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::install::install_tool_version;
 use crate::handlers::shared::functions::tools::get_yt_dlp_download_url_and_output_file_name;
-use crate::handlers::shared::model::tools::ToolDownloadResponse;
+use crate::handlers::shared::model::tools::{Tool, ToolDownloadResponse, YtDlpUpdateChannels};
 use crate::AppState;
 use anyhow::Context;
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::Json;
-use std::path::Path;
-use tokio::fs::{create_dir_all, File};
-use tokio::io::copy;
 use tracing::{debug, info, instrument};
 
+#[utoipa::path(
+    post,
+    path = "/tools/yt-dlp/download",
+    operation_id = "yt_dlp_download",
+    tag = "tools",
+    responses(
+        (status = 200, description = "yt-dlp downloaded and installed", body = ToolDownloadResponse),
+        ErrorResponses
+    )
+)]
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn handle_yt_dlp_download(
     State(app_state): State<AppState>,
@@ -18,44 +26,34 @@ pub async fn handle_yt_dlp_download(
     debug!("Handling downloading of yt-dlp from GitHub");
 
     let os = std::env::consts::OS;
-    let (download_url, output_file_name) = get_yt_dlp_download_url_and_output_file_name(os).await?;
-
-    info!("Downloading yt-dlp from {}", download_url);
-
-    let resp = app_state
-        .http_client
-        .get(download_url)
-        .send()
-        .await
-        .context("Error sending request to download yt-dlp from GitHub")?;
-
-    let content = resp.bytes().await.context("Failed to read response body")?;
-
-    let download_dir = Path::new(&app_state.config.server_settings.tools_download_dir);
-    create_dir_all(download_dir)
-        .await
-        .context("Failed to create download directory for yt-dlp")?;
-
-    let download_file_path = download_dir.join(output_file_name);
-    let mut file = File::create_new(&download_file_path)
-        .await
-        .context("Failed to create new yt-dlp file")?;
-    copy(&mut content.as_ref(), &mut file)
-        .await
-        .context("Failed to write yt-dlp file")?;
-
-    info!("yt-dlp downloaded successfully");
-
-    #[cfg(not(target_os = "windows"))]
-    set_executable_permissions(&file)
-        .await
-        .context("Failed to set yt-dlp executable permissions")?;
+    let (download_url, output_file_name) =
+        get_yt_dlp_download_url_and_output_file_name(os, YtDlpUpdateChannels::Stable, None).await?;
+
+    let installation = install_tool_version(
+        &app_state,
+        Tool::YtDlp,
+        &download_url,
+        &output_file_name,
+        None,
+    )
+    .await
+    .context("Failed to install yt-dlp from GitHub")?;
+
+    info!(
+        "yt-dlp version {} installed successfully",
+        installation.installed_version
+    );
 
     Ok((
         StatusCode::OK,
         Json(ToolDownloadResponse {
-            download_url: download_url.to_string(),
-            tools_dir_path: download_dir.to_string_lossy().to_string(),
+            download_url,
+            tools_dir_path: app_state
+                .config()
+                .server_settings
+                .tools_download_dir
+                .clone(),
+            installed_version: installation.installed_version,
         }),
     ))
 }
@@ -1,4 +1,4 @@
-use crate::handlers::errors::ServerError;
+use crate::handlers::errors::{ErrorResponses, ServerError};
 use crate::handlers::shared::functions::commands::run_command;
 use crate::handlers::shared::functions::tools::get_yt_dlp_executable_path;
 use crate::handlers::shared::model::tools::ToolStatusResponse;
@@ -9,6 +9,17 @@ use axum::http::StatusCode;
 use axum::Json;
 use tracing::{debug, instrument};
 
+#[utoipa::path(
+    get,
+    path = "/tools/yt-dlp/status",
+    operation_id = "yt_dlp_status",
+    tag = "tools",
+    responses(
+        (status = 200, description = "yt-dlp is installed", body = ToolStatusResponse),
+        (status = 400, description = "yt-dlp is not installed or cannot be run", body = ToolStatusResponse),
+        ErrorResponses
+    )
+)]
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn handle_yt_dlp_status(
     State(app_state): State<AppState>,
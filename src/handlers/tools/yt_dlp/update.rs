@@ -1,76 +1,74 @@
-use crate::handlers::errors::ServerError;
-use crate::handlers::shared::functions::commands::run_command;
-use crate::handlers::shared::functions::tools::get_yt_dlp_executable_path;
-use crate::handlers::shared::model::commands::CommandExecutionResults;
+use crate::handlers::errors::{ErrorResponses, ServerError};
+use crate::handlers::shared::functions::install::install_tool_version;
+use crate::handlers::shared::functions::tools::get_yt_dlp_download_url_and_output_file_name;
+use crate::handlers::shared::model::tools::{Tool, ToolUpdateResponse, YtDlpUpdateChannels};
 use crate::AppState;
 use anyhow::Context;
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::Json;
-use serde::{Deserialize, Serialize};
-use strum_macros::Display;
+use serde::Deserialize;
 use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct YtDlpUpdateRequest {
+    #[serde(default)]
     update_channel: YtDlpUpdateChannels,
+    /// Release tag to install from the update channel. The latest release is installed if not provided.
+    version: Option<String>,
 }
 
-#[derive(Debug, Display, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum YtDlpUpdateChannels {
-    Stable,
-    Master,
-    Nightly,
-}
-
-#[derive(Debug, Serialize)]
-pub struct YtDlpUpdateResponse {
-    path: String,
-    update_channel_and_tag: String,
-    update_execution_results: CommandExecutionResults,
-}
-
+#[utoipa::path(
+    post,
+    path = "/tools/yt-dlp/update",
+    operation_id = "yt_dlp_update",
+    tag = "tools",
+    request_body = YtDlpUpdateRequest,
+    responses(
+        (status = 200, description = "yt-dlp updated", body = ToolUpdateResponse),
+        ErrorResponses
+    )
+)]
 #[instrument(err, ret(level = "debug"), skip(app_state))]
 pub async fn handle_yt_dlp_update(
     State(app_state): State<AppState>,
     Json(payload): Json<YtDlpUpdateRequest>,
-) -> Result<(StatusCode, Json<YtDlpUpdateResponse>), ServerError> {
+) -> Result<(StatusCode, Json<ToolUpdateResponse>), ServerError> {
     debug!("Handling updating of yt-dlp");
 
-    let yt_dlp_executable_path = get_yt_dlp_executable_path(&app_state)
-        .await
-        .context("Failed to get yt-dlp executable path")?;
+    info!(
+        "Updating yt-dlp using channel {} and version {}",
+        payload.update_channel,
+        payload.version.as_deref().unwrap_or("latest")
+    );
 
-    let update_channel_and_tag =
-        prepare_update_channel_and_tag(&payload.update_channel.to_string()).await;
-    let command_execution_result = run_command(
-        &yt_dlp_executable_path,
-        &["--update-to", &update_channel_and_tag],
+    let os = std::env::consts::OS;
+    let (download_url, output_file_name) = get_yt_dlp_download_url_and_output_file_name(
+        os,
+        payload.update_channel,
+        payload.version.as_deref(),
+    )
+    .await?;
+
+    let installation = install_tool_version(
+        &app_state,
+        Tool::YtDlp,
+        &download_url,
+        &output_file_name,
+        payload.version.as_deref(),
     )
     .await
-    .context("Failed to run yt-dlp update command")?;
+    .context("Failed to install yt-dlp update")?;
 
     Ok((
-        if command_execution_result.command_completed_successfully {
-            StatusCode::OK
-        } else {
-            StatusCode::BAD_REQUEST
-        },
-        Json(YtDlpUpdateResponse {
-            path: yt_dlp_executable_path.to_string_lossy().to_string(),
-            update_channel_and_tag,
-            update_execution_results: command_execution_result,
+        StatusCode::OK,
+        Json(ToolUpdateResponse {
+            tool: Tool::YtDlp,
+            download_url,
+            installed_version: installation.installed_version,
+            previous_version: installation.previous_version,
+            install_dir_path: installation.install_dir.to_string_lossy().to_string(),
         }),
     ))
 }
-
-#[instrument]
-async fn prepare_update_channel_and_tag(update_channel: &str) -> String {
-    let update_channel_and_tag = format!("{}@latest", update_channel.to_lowercase());
-    info!(
-        "Update channel and tag for yt-dlp: {}",
-        &update_channel_and_tag
-    );
-    update_channel_and_tag
-}
@@ -3,128 +3,193 @@ mod config;
 mod doh;
 mod handlers;
 
-use crate::cli::{Cli, Commands};
-use crate::config::Config;
+use crate::cli::{AdminCommand, AdminSubcommands, Cli, Commands, ConfigSubcommands};
+use crate::config::{Config, ConfigSource, FileConfig, SharedConfig};
 use crate::doh::CloudflareDoHResolver;
+use crate::handlers::admin::reload::handle_config_reload;
+use crate::handlers::auth::login::handle_login;
+use crate::handlers::auth::logout::handle_logout;
+use crate::handlers::auth::session::handle_auth_session;
 use crate::handlers::convert::audio::handle_audio_conversion;
+use crate::handlers::doctor::handle_doctor;
 use crate::handlers::download::audio::handle_audio_download;
+use crate::handlers::download::playlist::{
+    handle_playlist_download, handle_playlist_download_status,
+};
+use crate::handlers::download::search::handle_media_search;
+use crate::handlers::download::video::handle_video_download;
+use crate::handlers::errors::render_problem_details;
+use crate::handlers::frontend::{handle_frontend, FRONTEND_CONTENT_SECURITY_POLICY};
 use crate::handlers::identify::audio::handle_audio_identification;
-use crate::handlers::index::handle_api_hello;
-use crate::handlers::library::list::handle_list_library_files;
-use crate::handlers::library::play::handle_play_audio;
+use crate::handlers::index::{handle_api_hello, handle_api_not_found, handle_health};
+use crate::handlers::library::index::handle_library_index;
+use crate::handlers::library::list::{handle_list_library_files, handle_list_library_videos};
+use crate::handlers::library::play::{handle_play_audio, handle_play_video};
+use crate::handlers::library::split::handle_split_library_file;
+use crate::handlers::openapi::{
+    handle_docs_page, handle_docs_script, handle_docs_stylesheet, handle_openapi_document,
+};
+use crate::handlers::shared::functions::auth::{
+    create_token, create_user, delete_user, read_auth_store, require_authentication, revoke_token,
+    spawn_auth_store_reloader, warn_about_auth_settings,
+};
+use crate::handlers::shared::functions::doctor::{log_failed_doctor_checks, run_doctor_checks};
+use crate::handlers::shared::functions::install::import_tool_from_path;
+use crate::handlers::shared::functions::reload::spawn_config_reloaders;
+use crate::handlers::shared::functions::subscriptions::spawn_subscription_scheduler;
+use crate::handlers::shared::functions::urls::get_media_redirect_policy;
+use crate::handlers::shared::functions::yt_dlp::warn_if_cookies_file_is_shared;
+use crate::handlers::shared::model::auth::{AuthStore, SessionRegistry, SharedAuthStore};
+use crate::handlers::shared::model::downloads::DownloadProgressRegistry;
+use crate::handlers::shared::model::media::PlaylistDownloadRegistry;
+use crate::handlers::subscriptions::create::handle_create_subscription;
+use crate::handlers::subscriptions::delete::handle_delete_subscription;
+use crate::handlers::subscriptions::list::handle_list_subscriptions;
 use crate::handlers::tools::chromaprint::download::handle_chromaprint_download;
 use crate::handlers::tools::chromaprint::status::handle_chromaprint_fpcalc_status;
+use crate::handlers::tools::chromaprint::update::handle_chromaprint_update;
 use crate::handlers::tools::ffmpeg::download::handle_ffmpeg_download;
 use crate::handlers::tools::ffmpeg::status::handle_ffmpeg_status;
+use crate::handlers::tools::ffmpeg::update::handle_ffmpeg_update;
+use crate::handlers::tools::import::handle_tool_import;
+use crate::handlers::tools::progress::handle_tool_download_progress;
+use crate::handlers::tools::rollback::handle_tool_rollback;
 use crate::handlers::tools::yt_dlp::download::handle_yt_dlp_download;
 use crate::handlers::tools::yt_dlp::status::handle_yt_dlp_status;
 use crate::handlers::tools::yt_dlp::update::handle_yt_dlp_update;
 use anyhow::Context;
+use axum::body::Body;
+use axum::extract::Request;
 use axum::http::header;
-use axum::routing::{get, post};
-use axum::Router;
-use clap::Parser;
-use reqwest::Client;
+use axum::routing::{delete, get, post};
+use axum::{middleware, Router};
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use reqwest::{Client, ClientBuilder};
+use std::io::IsTerminal;
+use std::path::Path;
 use std::sync::Arc;
+use time::format_description::well_known::Rfc3339;
+use tokio::sync::{Mutex, RwLock};
 use tower_http::compression::CompressionLayer;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
 use tower_http::decompression::DecompressionLayer;
-use tower_http::set_header::SetResponseHeaderLayer;
-use tower_http::trace::{
-    DefaultMakeSpan, DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, TraceLayer,
+use tower_http::request_id::{
+    MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer,
 };
+use tower_http::set_header::SetResponseHeaderLayer;
+use tower_http::trace::{DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, TraceLayer};
 use tracing::{debug, info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
 const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The API is served under this prefix, the frontend is served on all other paths.
+const API_PATH_PREFIX: &str = "/api/v1";
 
 #[derive(Debug, Clone)]
 struct AppState {
-    config: Config,
+    config: SharedConfig,
+    config_source: Arc<ConfigSource>,
+    config_reload_lock: Arc<Mutex<()>>,
     http_client: Client,
+    /// Client for media URLs from requests, which checks redirect targets against the URL policy.
+    media_http_client: Client,
+    download_progress: DownloadProgressRegistry,
+    /// Progress of direct audio downloads, kept apart from the tool downloads.
+    direct_download_progress: DownloadProgressRegistry,
+    playlist_downloads: PlaylistDownloadRegistry,
+    library_index_lock: Arc<Mutex<()>>,
+    subscriptions_lock: Arc<Mutex<()>>,
+    sessions: SessionRegistry,
+    auth_store: SharedAuthStore,
+}
+
+impl AppState {
+    /// Returns a snapshot of the current config, which can be replaced by reloading the config file.
+    fn config(&self) -> Arc<Config> {
+        self.config.current()
+    }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let run_matches = get_innermost_subcommand_matches(&matches);
     match cli.command {
         Commands::Run(run_command) => {
-            let config = config::cli_to_config(&run_command, cli.verbose)?;
-
-            let tracing_subscriber = FmtSubscriber::builder()
-                .with_max_level(config.logging_settings.level)
-                .with_thread_ids(true)
-                .with_thread_names(true)
-                .with_ansi(true)
-                .with_target(true)
-                .with_file(false)
-                .with_line_number(false)
-                .finish();
+            let config_source = ConfigSource::new(run_command, run_matches.clone(), cli.verbose);
+            let config = config_source.load()?;
 
-            tracing::subscriber::set_global_default(tracing_subscriber)
-                .context("Failed to set global default tracing subscriber")?;
+            init_tracing(config.logging_settings.level)?;
 
             info!("Parsed app config and set up tracing");
             if cli.verbose {
                 info!("Running in verbose mode. Debug logs enabled");
             }
 
+            // The request ID is logged with every request, so it can be matched with the ID of an error response
             let trace_layer = TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().include_headers(false))
+                .make_span_with(|request: &Request<Body>| {
+                    let request_id = request
+                        .extensions()
+                        .get::<RequestId>()
+                        .and_then(|request_id| request_id.header_value().to_str().ok())
+                        .unwrap_or_default();
+                    tracing::info_span!(
+                        "request",
+                        method = %request.method(),
+                        uri = %request.uri(),
+                        version = ?request.version(),
+                        request_id,
+                    )
+                })
                 .on_request(DefaultOnRequest::new().level(Level::DEBUG))
                 .on_response(DefaultOnResponse::new().level(Level::DEBUG))
                 .on_failure(DefaultOnFailure::new());
 
-            info!("Creating HTTP client");
-            let mut http_client_builder = Client::builder();
+            let auth_store = read_auth_store(Path::new(&config.server_settings.data_dir))
+                .await
+                .context("Failed to load users and API tokens")?;
+            let app_state = create_app_state(&config, config_source, auth_store)?;
 
-            if config.server_settings.disable_doh {
-                warn!(
-                    "Disabling DNS over HTTPS (DoH) for HTTP client. This may affect your privacy"
-                );
-            } else {
-                http_client_builder =
-                    http_client_builder.dns_resolver(Arc::new(CloudflareDoHResolver::default()));
-            }
+            warn_if_cookies_file_is_shared(&app_state).await;
+            warn_about_auth_settings(&app_state).await;
 
-            let http_client = http_client_builder
-                .build()
-                .context("Failed to create HTTP client")?;
+            if config.server_settings.startup_self_test {
+                let self_test_app_state = app_state.clone();
+                tokio::spawn(async move {
+                    let doctor_response = run_doctor_checks(&self_test_app_state).await;
+                    log_failed_doctor_checks(&doctor_response);
+                });
+            }
 
-            let app_state = AppState {
-                config: config.clone(),
-                http_client,
-            };
+            spawn_subscription_scheduler(app_state.clone());
+            spawn_config_reloaders(app_state.clone());
+            spawn_auth_store_reloader(app_state.clone());
 
             info!("Setting up routes and middleware");
+            let cors_layer = create_cors_layer(app_state.config.clone());
+            let api_router = create_api_router(app_state);
+
+            // The frontend sets its own caching headers, as hashed assets can be cached while pages are revalidated
+            let frontend_router =
+                Router::new()
+                    .fallback(handle_frontend)
+                    .layer(SetResponseHeaderLayer::overriding(
+                        header::CONTENT_SECURITY_POLICY,
+                        header::HeaderValue::from_static(FRONTEND_CONTENT_SECURITY_POLICY),
+                    ));
+
             let app = Router::new()
-                .route("/", get(handle_api_hello))
-                .route("/library/list", get(handle_list_library_files))
-                .route("/library/play/:library_file_name", get(handle_play_audio))
-                .route("/download/audio", post(handle_audio_download))
-                .route("/identify/audio", post(handle_audio_identification))
-                .route("/convert/audio", post(handle_audio_conversion))
-                // Tools: yt-dlp routes
-                .route("/tools/yt-dlp/download", post(handle_yt_dlp_download))
-                .route("/tools/yt-dlp/status", get(handle_yt_dlp_status))
-                .route("/tools/yt-dlp/update", post(handle_yt_dlp_update))
-                // Tools: ffmpeg routes
-                .route("/tools/ffmpeg/download", post(handle_ffmpeg_download))
-                .route("/tools/ffmpeg/status", get(handle_ffmpeg_status))
-                // Tools: chromaprint routes
-                .route(
-                    "/tools/chromaprint/download",
-                    post(handle_chromaprint_download),
-                )
-                .route(
-                    "/tools/chromaprint/status",
-                    get(handle_chromaprint_fpcalc_status),
-                )
+                .nest(API_PATH_PREFIX, api_router)
+                .fallback_service(frontend_router)
                 .layer(tower_http::catch_panic::CatchPanicLayer::new())
                 .layer(trace_layer)
+                .layer(PropagateRequestIdLayer::x_request_id())
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
                 .layer(CompressionLayer::new())
                 .layer(DecompressionLayer::new())
-                .layer(CorsLayer::permissive())
+                .layer(cors_layer)
                 .layer(SetResponseHeaderLayer::overriding(
                     header::SERVER,
                     header::HeaderValue::from_str(&format!(
@@ -134,10 +199,6 @@ async fn main() -> anyhow::Result<()> {
                     ))
                     .context("Failed to configure Server header layer")?,
                 ))
-                .layer(SetResponseHeaderLayer::overriding(
-                    header::CACHE_CONTROL,
-                    header::HeaderValue::from_static("no-store"),
-                ))
                 .layer(SetResponseHeaderLayer::overriding(
                     header::X_FRAME_OPTIONS,
                     header::HeaderValue::from_static("DENY"),
@@ -149,12 +210,7 @@ async fn main() -> anyhow::Result<()> {
                 .layer(SetResponseHeaderLayer::overriding(
                     header::REFERRER_POLICY,
                     header::HeaderValue::from_static("no-referrer"),
-                ))
-                .layer(SetResponseHeaderLayer::overriding(
-                    header::CONTENT_SECURITY_POLICY,
-                    header::HeaderValue::from_static("default-src 'none'"),
-                ))
-                .with_state(app_state);
+                ));
 
             let bind_addr = format!(
                 "{}:{}",
@@ -169,8 +225,8 @@ async fn main() -> anyhow::Result<()> {
                 ))?;
 
             info!(
-                "Ferrous Beats API version {} is starting on {}",
-                APP_VERSION, &bind_addr
+                "Ferrous Beats API version {} is starting on {}, the API is available under {}",
+                APP_VERSION, &bind_addr, API_PATH_PREFIX
             );
 
             debug!("Axum router state: {:#?}", app);
@@ -179,7 +235,342 @@ async fn main() -> anyhow::Result<()> {
                 .await
                 .context("axum::serve failed")?;
         }
+        Commands::ImportTool(import_tool_command) => {
+            init_tracing(if cli.verbose {
+                Level::DEBUG
+            } else {
+                Level::INFO
+            })?;
+
+            info!(
+                "Importing {} from {}",
+                import_tool_command.tool,
+                import_tool_command.path.display()
+            );
+
+            let installation = import_tool_from_path(
+                Path::new(&import_tool_command.tools_download_dir),
+                import_tool_command.tool,
+                &import_tool_command.path,
+            )
+            .await
+            .context(format!("Failed to import {}", import_tool_command.tool))?;
+
+            info!(
+                "{} version {} imported successfully to {}",
+                import_tool_command.tool,
+                installation.installed_version,
+                installation.install_dir.display()
+            );
+        }
+        Commands::Doctor(run_command) => {
+            let config_source = ConfigSource::new(run_command, run_matches.clone(), cli.verbose);
+            let config = config_source.load()?;
+
+            init_tracing(config.logging_settings.level)?;
+
+            let auth_store = read_auth_store(Path::new(&config.server_settings.data_dir))
+                .await
+                .context("Failed to load users and API tokens")?;
+            let app_state = create_app_state(&config, config_source, auth_store)?;
+            let doctor_response = run_doctor_checks(&app_state).await;
+            log_failed_doctor_checks(&doctor_response);
+
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&doctor_response)
+                    .context("Failed to serialize doctor report")?
+            );
+
+            if !doctor_response.healthy {
+                anyhow::bail!("One or more doctor checks failed");
+            }
+        }
+        Commands::Config(config_command) => match config_command.command {
+            ConfigSubcommands::Print(run_command) => {
+                let config = config::load_config(&run_command, run_matches, cli.verbose)?;
+
+                match &config.config_file {
+                    Some(config_file) => println!("# Config file: {}", config_file.display()),
+                    None => println!("# No config file found"),
+                }
+                print!(
+                    "{}",
+                    toml::to_string_pretty(&FileConfig::from(&config))
+                        .context("Failed to serialize config")?
+                );
+            }
+        },
+        Commands::Admin(admin_command) => {
+            init_tracing(if cli.verbose {
+                Level::DEBUG
+            } else {
+                Level::WARN
+            })?;
+
+            run_admin_command(admin_command).await?;
+        }
     }
 
     Ok(())
 }
+
+/// Routes of the API, served under [`API_PATH_PREFIX`]. Handlers of new routes are also listed in [`handlers::openapi::ApiDoc`] to document them.
+fn create_api_router(app_state: AppState) -> Router {
+    Router::new()
+        .route("/", get(handle_api_hello))
+        .route("/openapi.json", get(handle_openapi_document))
+        .route("/docs", get(handle_docs_page))
+        .route("/docs/docs.js", get(handle_docs_script))
+        .route("/docs/docs.css", get(handle_docs_stylesheet))
+        .route("/health", get(handle_health))
+        .route("/auth/login", post(handle_login))
+        .route("/auth/logout", post(handle_logout))
+        .route("/auth/session", get(handle_auth_session))
+        .route("/doctor", get(handle_doctor))
+        .route("/admin/reload", post(handle_config_reload))
+        .route("/library/list", get(handle_list_library_files))
+        .route("/library/index", get(handle_library_index))
+        .route("/library/split", post(handle_split_library_file))
+        .route("/library/play/:library_file_name", get(handle_play_audio))
+        .route("/library/videos/list", get(handle_list_library_videos))
+        .route(
+            "/library/videos/play/:library_file_name",
+            get(handle_play_video),
+        )
+        .route("/download/audio", post(handle_audio_download))
+        .route("/download/playlist", post(handle_playlist_download))
+        .route("/download/search", post(handle_media_search))
+        .route("/download/video", post(handle_video_download))
+        .route(
+            "/download/playlist/:download_id",
+            get(handle_playlist_download_status),
+        )
+        .route("/identify/audio", post(handle_audio_identification))
+        .route("/convert/audio", post(handle_audio_conversion))
+        .route(
+            "/subscriptions",
+            get(handle_list_subscriptions).post(handle_create_subscription),
+        )
+        .route(
+            "/subscriptions/:subscription_id",
+            delete(handle_delete_subscription),
+        )
+        // Tools: yt-dlp routes
+        .route("/tools/yt-dlp/download", post(handle_yt_dlp_download))
+        .route("/tools/yt-dlp/status", get(handle_yt_dlp_status))
+        .route("/tools/yt-dlp/update", post(handle_yt_dlp_update))
+        // Tools: ffmpeg routes
+        .route("/tools/ffmpeg/download", post(handle_ffmpeg_download))
+        .route("/tools/ffmpeg/status", get(handle_ffmpeg_status))
+        .route("/tools/ffmpeg/update", post(handle_ffmpeg_update))
+        // Tools: chromaprint routes
+        .route(
+            "/tools/chromaprint/download",
+            post(handle_chromaprint_download),
+        )
+        .route(
+            "/tools/chromaprint/status",
+            get(handle_chromaprint_fpcalc_status),
+        )
+        .route("/tools/chromaprint/update", post(handle_chromaprint_update))
+        // Tools: shared routes
+        .route(
+            "/tools/:tool/download/progress",
+            get(handle_tool_download_progress),
+        )
+        .route("/tools/:tool/rollback", post(handle_tool_rollback))
+        // The body of tool imports is streamed to disk and limited by the handler
+        .route("/tools/:tool/import", post(handle_tool_import))
+        .fallback(handle_api_not_found)
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            require_authentication,
+        ))
+        .layer(middleware::from_fn(render_problem_details))
+        .layer(SetResponseHeaderLayer::overriding(
+            header::CACHE_CONTROL,
+            header::HeaderValue::from_static("no-store"),
+        ))
+        // The docs page sets its own policy to load its script and styles
+        .layer(SetResponseHeaderLayer::if_not_present(
+            header::CONTENT_SECURITY_POLICY,
+            header::HeaderValue::from_static("default-src 'none'"),
+        ))
+        .with_state(app_state)
+}
+
+async fn run_admin_command(admin_command: AdminCommand) -> anyhow::Result<()> {
+    let data_dir = Path::new(&admin_command.data_dir);
+    let auth_store: SharedAuthStore = Arc::new(RwLock::new(read_auth_store(data_dir).await?));
+
+    match admin_command.command {
+        AdminSubcommands::CreateUser { username } => {
+            let password = read_password()?;
+            create_user(data_dir, &auth_store, &username, password).await?;
+            println!("Created user {}", username);
+        }
+        AdminSubcommands::DeleteUser { username } => {
+            if !delete_user(data_dir, &auth_store, &username).await? {
+                anyhow::bail!("User {} does not exist", username);
+            }
+            println!("Deleted user {}", username);
+        }
+        AdminSubcommands::ListUsers => {
+            for user in auth_store.read().await.users.iter() {
+                println!(
+                    "{}\tcreated {}",
+                    user.username,
+                    user.created_at.format(&Rfc3339)?
+                );
+            }
+        }
+        AdminSubcommands::CreateToken { name, mut scopes } => {
+            scopes.sort_by_key(|scope| scope.to_string());
+            scopes.dedup();
+            let (stored_token, token) = create_token(data_dir, &auth_store, &name, scopes).await?;
+            eprintln!(
+                "Created API token {} with ID {}. Store it now, it cannot be shown again",
+                stored_token.name, stored_token.id
+            );
+            println!("{}", token);
+        }
+        AdminSubcommands::ListTokens => {
+            for token in auth_store.read().await.tokens.iter() {
+                let scopes = token
+                    .scopes
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                println!(
+                    "{}\t{}\t{}\tcreated {}",
+                    token.id,
+                    token.name,
+                    scopes,
+                    token.created_at.format(&Rfc3339)?
+                );
+            }
+        }
+        AdminSubcommands::RevokeToken { id } => {
+            if !revoke_token(data_dir, &auth_store, &id).await? {
+                anyhow::bail!("API token {} does not exist", id);
+            }
+            println!("Revoked API token {}", id);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a password from the first line of standard input, so it can be piped in by scripts.
+/// On a terminal it is prompted for twice without being shown.
+fn read_password() -> anyhow::Result<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        let password =
+            rpassword::prompt_password("Password: ").context("Failed to read password")?;
+        let repeated_password =
+            rpassword::prompt_password("Repeat password: ").context("Failed to read password")?;
+        if password != repeated_password {
+            anyhow::bail!("Passwords do not match");
+        }
+        return Ok(password);
+    }
+
+    let mut password = String::new();
+    stdin
+        .read_line(&mut password)
+        .context("Failed to read password from standard input")?;
+
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Returns the matches of the subcommand that was run, which hold the arguments of nested commands such as `config print`.
+fn get_innermost_subcommand_matches(matches: &ArgMatches) -> &ArgMatches {
+    match matches.subcommand() {
+        Some((_, subcommand_matches)) => get_innermost_subcommand_matches(subcommand_matches),
+        None => matches,
+    }
+}
+
+fn create_app_state(
+    config: &Config,
+    config_source: ConfigSource,
+    auth_store: AuthStore,
+) -> anyhow::Result<AppState> {
+    info!("Creating HTTP client");
+    if config.server_settings.disable_doh {
+        warn!("Disabling DNS over HTTPS (DoH) for HTTP client. This may affect your privacy");
+    }
+
+    let http_client = create_http_client_builder(config)
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    let shared_config = SharedConfig::new(config.clone());
+    let redirect_config = shared_config.clone();
+    let media_http_client = create_http_client_builder(config)
+        .redirect(get_media_redirect_policy(move || {
+            redirect_config.current().url_policy_settings.clone()
+        }))
+        .build()
+        .context("Failed to create HTTP client for media downloads")?;
+
+    Ok(AppState {
+        config: shared_config,
+        config_source: Arc::new(config_source),
+        config_reload_lock: Arc::new(Mutex::new(())),
+        http_client,
+        media_http_client,
+        download_progress: DownloadProgressRegistry::default(),
+        direct_download_progress: DownloadProgressRegistry::default(),
+        playlist_downloads: PlaylistDownloadRegistry::default(),
+        library_index_lock: Arc::new(Mutex::new(())),
+        subscriptions_lock: Arc::new(Mutex::new(())),
+        sessions: SessionRegistry::default(),
+        auth_store: Arc::new(RwLock::new(auth_store)),
+    })
+}
+
+fn create_http_client_builder(config: &Config) -> ClientBuilder {
+    let http_client_builder = Client::builder();
+    if config.server_settings.disable_doh {
+        return http_client_builder;
+    }
+
+    http_client_builder.dns_resolver(Arc::new(CloudflareDoHResolver::default()))
+}
+
+/// Lets browsers call the API from other sites. While authentication is enabled, only the configured origins can.
+/// The config is read for every request, so reloading it applies changed origins.
+fn create_cors_layer(config: SharedConfig) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            let config = config.current();
+            !config.auth_settings.enabled
+                || config
+                    .auth_settings
+                    .cors_origins
+                    .iter()
+                    .any(|cors_origin| origin.as_bytes() == cors_origin.as_bytes())
+        }))
+        .allow_methods(Any)
+        .allow_headers(AllowHeaders::mirror_request())
+        .expose_headers(Any)
+}
+
+fn init_tracing(level: Level) -> anyhow::Result<()> {
+    let tracing_subscriber = FmtSubscriber::builder()
+        .with_max_level(level)
+        .with_thread_ids(true)
+        .with_thread_names(true)
+        .with_ansi(true)
+        .with_target(true)
+        .with_file(false)
+        .with_line_number(false)
+        .finish();
+
+    tracing::subscriber::set_global_default(tracing_subscriber)
+        .context("Failed to set global default tracing subscriber")
+}